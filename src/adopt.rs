@@ -0,0 +1,267 @@
+use clap::ArgMatches;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Config;
+
+use std::{
+  collections::BTreeMap,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+pub const STATE_FILE: &str = "adopted.json";
+
+// a bundle is a clearsigned toml document with an aliases table, as made by
+// `gpg --clearsign` on a file like the aliases section of the config. a serial, increased with
+// each version, is covered by the signature, so an older version can't be passed off as new
+#[derive(Deserialize)]
+struct Bundle {
+  serial: Option<u64>,
+  aliases: BTreeMap<String, String>,
+}
+
+// the last version adopted from each signer, by fingerprint, to compare the next one with
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+  signers: BTreeMap<String, Accepted>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Accepted {
+  serial: Option<u64>,
+  aliases: BTreeMap<String, String>,
+}
+
+// someone else's attestations are only a reason to look: each adopted alias is attested again
+// with our own method and key, so from then on it's verified like any alias we added ourselves
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let path = PathBuf::from(matches.value_of_os("bundle").expect("required clap argument"));
+
+  if !config.signing.enabled {
+    error!("signing is disabled, so adopted aliases could not be attested");
+    return 1;
+  }
+  if crate::manage::check_writable(config, "adopt aliases").is_err() {
+    return 1;
+  }
+
+  let signed = match crate::read_signature(&path) {
+    Ok(s) => s,
+    Err(exit) => return exit,
+  };
+  let verified = match crate::verify_signature(signed) {
+    Ok(v) => v,
+    Err(exit) => {
+      error!("the signature on {} does not verify", path.to_string_lossy());
+      return exit;
+    },
+  };
+
+  info!("{} was signed by {}", path.to_string_lossy(), verified.signer);
+  crate::conflict::describe("signer", &verified.signer);
+  match matches.value_of("signer") {
+    Some(expected) => if crate::check_signer(expected, &verified.signer).is_err() {
+      return 1;
+    },
+    None => match crate::confirm(config, &config.prompts.text("confirm_adopt_signer", &[("signer", &verified.signer)])) {
+      Ok(true) => {},
+      Ok(false) => {
+        error!("adopting aliases from {} was not authorised", verified.signer);
+        return 1;
+      },
+      Err(exit) => return exit,
+    },
+  }
+
+  let bundle: Bundle = match toml::from_str(&verified.content) {
+    Ok(b) => b,
+    Err(e) => {
+      error!("could not parse the aliases in {}: {}", path.to_string_lossy(), e);
+      return 1;
+    },
+  };
+
+  let state_path = match crate::data_dir(config) {
+    Ok(d) => d.join(STATE_FILE),
+    Err(exit) => return exit,
+  };
+  let mut state = match load(&state_path) {
+    Ok(s) => s,
+    Err(exit) => return exit,
+  };
+  if let Err(exit) = check_serial(state.signers.get(&verified.signer), &bundle) {
+    return exit;
+  }
+
+  let names: Vec<&String> = bundle.aliases.keys().collect();
+
+  let mut adopted = 0;
+  let mut skipped = 0;
+  for (i, name) in names.iter().enumerate() {
+    let key_id = bundle.aliases[*name].as_str();
+    crate::batch::set_pending(names[i..].iter().map(ToString::to_string).collect());
+
+    if let Some(existing) = config.aliases.get(name.as_str()) {
+      if existing.key() != key_id {
+        warn!("skipping `{}`: it already points to `{}` here, not `{}`", name, existing.key(), key_id);
+      } else {
+        info!("`{}` already points to `{}`", name, key_id);
+      }
+      skipped += 1;
+      continue;
+    }
+    if let Some(part) = crate::naming::unsafe_part(name) {
+      warn!("skipping `{}`: the name {}, which can't be part of a file name", name.escape_default(), part);
+      skipped += 1;
+      continue;
+    }
+    if let Some(problem) = crate::naming::problem(config, name) {
+      warn!("skipping `{}`: the name {}, as the [naming] rules require", name, problem);
+      skipped += 1;
+      continue;
+    }
+    if crate::manage::check_key(key_id).is_err() {
+      warn!("skipping `{}`: its key is not in the keyring", name);
+      skipped += 1;
+      continue;
+    }
+
+    crate::conflict::describe(name, key_id);
+    let args = [("alias", name.as_str()), ("id", key_id)];
+    match crate::confirm_batch(config, "confirm_adopt", &args) {
+      Ok(true) => {},
+      Ok(false) => {
+        skipped += 1;
+        continue;
+      },
+      Err(exit) => return exit,
+    }
+
+    if let Err(exit) = adopt(config, name, key_id) {
+      return exit;
+    }
+    crate::audit::record(config, &format!("adopted alias `{}` for key `{}` attested by {}", name, key_id, verified.signer));
+    adopted += 1;
+  }
+
+  info!("adopted {} alias(es), skipped {}", adopted, skipped);
+
+  if !config.simulate {
+    state.signers.insert(verified.signer, Accepted { serial: bundle.serial, aliases: bundle.aliases });
+    if let Err(exit) = save(&state_path, &state) {
+      return exit;
+    }
+  }
+  0
+}
+
+// shows what changed since the last version adopted from the same signer, refusing anything that
+// isn't newer: an older serial is a rollback, and the same serial with other aliases means two
+// different versions were signed as one
+fn check_serial(last: Option<&Accepted>, bundle: &Bundle) -> Result<(), i32> {
+  let last = match last {
+    Some(l) => l,
+    None => {
+      if bundle.serial.is_none() {
+        warn!("the bundle has no serial, so an older version adopted later couldn't be told apart from a newer one");
+      }
+      return Ok(());
+    },
+  };
+
+  match (last.serial, bundle.serial) {
+    (Some(_), None) => {
+      error!("the bundle has no serial, but the last one adopted from this signer did: it may be an older version");
+      return Err(1);
+    },
+    (Some(old), Some(new)) if new < old => {
+      error!("the bundle's serial {} is older than the last one adopted from this signer ({}): refusing a rollback", new, old);
+      return Err(1);
+    },
+    (Some(old), Some(new)) if new == old && last.aliases != bundle.aliases => {
+      error!("the bundle has the same serial ({}) as the last one adopted from this signer, but different aliases", new);
+      return Err(1);
+    },
+    _ => {},
+  }
+
+  let changes = changelog(&last.aliases, &bundle.aliases);
+  if changes.is_empty() {
+    info!("no aliases changed since the last version adopted from this signer");
+    return Ok(());
+  }
+  match last.serial {
+    Some(old) => info!("changes since version {}:", old),
+    None => info!("changes since the last version adopted:"),
+  }
+  for change in changes {
+    info!("  {}", change);
+  }
+  Ok(())
+}
+
+fn changelog(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Vec<String> {
+  let mut changes = Vec::new();
+  for (name, key) in new {
+    match old.get(name) {
+      None => changes.push(format!("+ {} = {}", name, key)),
+      Some(old_key) if old_key != key => changes.push(format!("~ {}: {} -> {}", name, old_key, key)),
+      Some(_) => {},
+    }
+  }
+  for (name, key) in old {
+    if !new.contains_key(name) {
+      changes.push(format!("- {} = {}", name, key));
+    }
+  }
+  changes
+}
+
+// a missing record just means nothing was adopted yet, but an unreadable one would quietly turn
+// off rollback detection
+fn load(path: &Path) -> Result<State, i32> {
+  let contents = match std::fs::read(path) {
+    Ok(c) => c,
+    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(State::default()),
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  serde_json::from_slice(&contents).map_err(|e| {
+    error!("could not parse {}: {}", path.to_string_lossy(), e);
+    1
+  })
+}
+
+pub fn is_valid(path: &Path) -> bool {
+  std::fs::read(path).ok()
+    .and_then(|s| serde_json::from_slice::<State>(&s).ok())
+    .is_some()
+}
+
+fn save(path: &Path, state: &State) -> Result<(), i32> {
+  let tmp = path.with_extension("json.tmp");
+  let res = serde_json::to_vec_pretty(state)
+    .map_err(std::io::Error::from)
+    .and_then(|json| crate::files::create_file(&tmp).and_then(|mut f| f.write_all(&json)))
+    .and_then(|_| std::fs::rename(&tmp, path));
+  if let Err(e) = res {
+    error!("could not record the adopted version in {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+  Ok(())
+}
+
+// attested before the config is edited, like `add`, so an alias is never left unattested
+fn adopt(config: &Config, name: &str, key_id: &str) -> Result<(), i32> {
+  crate::attest(config, name, key_id)?;
+
+  if config.simulate {
+    info!("simulating: the config would be edited to add alias `{}`", name);
+    return Ok(());
+  }
+  crate::edit::add_alias(&config.path, name, key_id, &[])?;
+  crate::edit::finish(config)
+}
@@ -0,0 +1,46 @@
+use std::ffi::OsString;
+
+// `@FILE` is replaced by the arguments in FILE, one per line, so build systems can pass hundreds of
+// aliases without running into command line length limits. blank lines and lines starting with `#`
+// are skipped, and `@@x` is a literal `@x`. arguments after `--` are left alone, since they're
+// gpg's, and files aren't expanded inside files
+pub fn expand(args: Vec<OsString>) -> Result<Vec<OsString>, i32> {
+  let mut expanded = Vec::with_capacity(args.len());
+  let mut args = args.into_iter();
+  expanded.extend(args.next());
+
+  let mut after_separator = false;
+  for arg in args {
+    if after_separator || arg == "--" {
+      after_separator = true;
+      expanded.push(arg);
+      continue;
+    }
+
+    let path = match arg.to_str().and_then(|a| a.strip_prefix('@')) {
+      Some(literal) if literal.starts_with('@') => {
+        expanded.push(OsString::from(literal));
+        continue;
+      },
+      Some(path) if !path.is_empty() => path,
+      _ => {
+        expanded.push(arg);
+        continue;
+      },
+    };
+
+    let contents = match std::fs::read_to_string(path) {
+      Ok(c) => c,
+      Err(e) => {
+        eprintln!("could not read arguments from {}: {}", path, e);
+        return Err(1);
+      },
+    };
+    expanded.extend(contents.lines()
+      .map(|l| l.trim_end_matches('\r'))
+      .filter(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+      .map(OsString::from));
+  }
+
+  Ok(expanded)
+}
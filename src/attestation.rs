@@ -0,0 +1,148 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol, SignatureSummary};
+
+use std::path::Path;
+
+// checks a single attestation the way gpgv does: any key in the given keyring is trusted to have
+// made it, and nothing else is consulted. no config is loaded and the user's keyring isn't touched,
+// so audit tooling and CI get the same answer on any machine
+pub fn run(matches: &ArgMatches) -> i32 {
+  let file = Path::new(matches.value_of_os("file").unwrap());
+  let keyring = Path::new(matches.value_of_os("keyring").unwrap());
+
+  let signed = match std::fs::read(file) {
+    Ok(s) => s,
+    Err(e) => {
+      error!("could not read {}: {}", file.to_string_lossy(), e);
+      return 1;
+    },
+  };
+  let keys = match std::fs::read(keyring) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not read {}: {}", keyring.to_string_lossy(), e);
+      return 1;
+    },
+  };
+
+  if let Err(e) = gpgme::init().check_engine_version(Protocol::OpenPgp) {
+    error!("gpg cannot be used: {}", e);
+    return 1;
+  }
+
+  let home = std::env::temp_dir().join(format!("gpg-alias-verify-{}", std::process::id()));
+  if let Err(e) = crate::files::create_dir_all(&home) {
+    error!("could not create {}: {}", home.to_string_lossy(), e);
+    return 1;
+  }
+  let res = verify(&home, keys, signed, matches.value_of("signer"), matches.value_of("alias"));
+  if let Err(e) = std::fs::remove_dir_all(&home) {
+    warn!("could not remove {}: {}", home.to_string_lossy(), e);
+  }
+
+  match res {
+    Ok(()) => 0,
+    Err(exit) => exit,
+  }
+}
+
+fn verify(home: &Path, keys: Vec<u8>, signed: Vec<u8>, signer: Option<&str>, alias: Option<&str>) -> Result<(), i32> {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  if let Err(e) = ctx.set_engine_home_dir(home.to_string_lossy()) {
+    error!("could not use {} as gpg's home directory: {}", home.to_string_lossy(), e);
+    return Err(1);
+  }
+  match ctx.import(keys) {
+    Ok(res) if res.imported() > 0 => {},
+    Ok(_) => {
+      error!("the keyring has no keys in it");
+      return Err(1);
+    },
+    Err(e) => {
+      error!("could not import the keyring: {}", e);
+      return Err(1);
+    },
+  }
+
+  let mut plaintext = Vec::new();
+  let verify_res = match ctx.verify_opaque(signed, &mut plaintext) {
+    Ok(res) => res,
+    Err(e) => {
+      error!("could not verify signature: {}", e);
+      return Err(1);
+    },
+  };
+  let sigs: Vec<_> = verify_res.signatures().collect();
+  if sigs.len() != 1 {
+    error!("invalid number of signatures: expected 1, got {}", sigs.len());
+    return Err(1);
+  }
+
+  // nothing in a throwaway keyring is certified, so gpg never calls a signature fully valid: a good
+  // signature by a key that is still usable is what counts, as with gpgv
+  let sig = &sigs[0];
+  let unusable = SignatureSummary::KEY_REVOKED | SignatureSummary::KEY_EXPIRED | SignatureSummary::SIG_EXPIRED;
+  if let Err(e) = sig.status() {
+    error!("invalid signature: {}", e);
+    return Err(1);
+  }
+  if sig.summary().intersects(unusable) {
+    error!("invalid signature: the key that made it has been revoked or has expired, or the signature has expired");
+    return Err(1);
+  }
+
+  let made_by = sig.fingerprint().unwrap_or("?");
+  if let Some(signer) = signer.filter(|s| !made_by.eq_ignore_ascii_case(s.trim_start_matches("0x"))) {
+    error!("signature made by wrong key (got {}, expected {})", made_by, signer);
+    return Err(1);
+  }
+
+  let signed_for = sig.notations()
+    .find(|n| n.name() == Ok(crate::NOTATION_ALIAS))
+    .and_then(|n| n.value().ok().map(ToString::to_string));
+  if let Some(alias) = alias {
+    match signed_for {
+      Some(ref n) if n == alias => {},
+      Some(ref n) => {
+        error!("signature was made for alias `{}`, not `{}`", n, alias);
+        return Err(1);
+      },
+      None => {
+        error!("signature is missing the required alias notation");
+        return Err(1);
+      },
+    }
+  }
+
+  let content = match std::str::from_utf8(&plaintext) {
+    Ok(s) => s.trim_end(),
+    Err(e) => {
+      error!("could not create utf-8 string from signed data: {}", e);
+      return Err(1);
+    },
+  };
+  let attested = crate::pin::parse(content);
+
+  println!("signer:      {}", made_by);
+  if let Some(alias) = &signed_for {
+    println!("alias:       {}", alias);
+  }
+  println!("key:         {}", attested.id);
+  if let Some(fingerprint) = attested.fingerprint {
+    println!("fingerprint: {}", fingerprint);
+  }
+  if let Some(uid) = attested.uid {
+    println!("uid:         {}", uid);
+  }
+  if let Some(expires) = attested.expires {
+    println!("expires:     {}", expires);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,331 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Config, Method};
+
+use std::{
+  ffi::OsString,
+  fs::OpenOptions,
+  io::Write,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const LOG_FILE: &str = "audit.log";
+
+// how long a webhook delivery may take, retries included, before it is abandoned
+const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct Audit {
+  // where events go: "file" (audit.log in the data directory), "syslog" or "webhook:<url>"
+  #[serde(default = "default_sinks")]
+  pub sinks: Vec<String>,
+  // also record every alias resolved, not just attestations and other changes
+  #[serde(default)]
+  pub resolutions: bool,
+  pub webhook_timeout_secs: Option<u64>,
+}
+
+impl Default for Audit {
+  fn default() -> Self {
+    Audit {
+      sinks: default_sinks(),
+      resolutions: false,
+      webhook_timeout_secs: None,
+    }
+  }
+}
+
+fn default_sinks() -> Vec<String> {
+  vec!["file".to_string()]
+}
+
+enum Sink<'a> {
+  File,
+  Syslog,
+  Webhook(&'a str),
+}
+
+impl<'a> Sink<'a> {
+  fn parse(name: &'a str) -> Option<Sink<'a>> {
+    match name {
+      "file" => Some(Sink::File),
+      "syslog" => Some(Sink::Syslog),
+      _ => name.strip_prefix("webhook:")
+        .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+        .map(Sink::Webhook),
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+  time: u64,
+  version: &'static str,
+  event: &'a str,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+  generated: Option<u64>,
+  version: &'static str,
+  signing_key: &'a str,
+  aliases: Vec<Entry<'a>>,
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+  alias: &'a str,
+  key: &'a str,
+  fingerprint: Option<String>,
+  system: bool,
+  attestation: Option<Attestation>,
+  verified: bool,
+  provenance: Option<crate::provenance::Provenance<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct Attestation {
+  pub method: &'static str,
+  pub signer: Option<String>,
+  pub date: Option<u64>,
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  match matches.subcommand() {
+    ("export", Some(sub)) => export(config, sub),
+    _ => unreachable!("clap requires a subcommand"),
+  }
+}
+
+fn export(config: &Config, matches: &ArgMatches) -> i32 {
+  let output = PathBuf::from(matches.value_of_os("output").expect("required clap argument"));
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+
+  let mut aliases = Vec::with_capacity(names.len());
+  for name in names {
+    let key_id = config.aliases[name].key();
+    let fingerprint = crate::pin::fingerprint(key_id);
+    let (attestation, verified) = if config.signing.enabled {
+      (attestation(config, &mut ctx, name, key_id), crate::verify_attestation(config, name, key_id).is_ok())
+    } else {
+      (None, false)
+    };
+
+    aliases.push(Entry {
+      alias: name,
+      key: key_id,
+      fingerprint,
+      system: config.system.aliases.contains(name.as_str()),
+      attestation,
+      verified,
+      provenance: config.aliases[name].entry().and_then(crate::provenance::of),
+    });
+  }
+
+  let report = Report {
+    generated: unix(SystemTime::now()),
+    version: clap::crate_version!(),
+    signing_key: &config.signing.key,
+    aliases,
+  };
+  let json = match serde_json::to_vec_pretty(&report) {
+    Ok(j) => j,
+    Err(e) => {
+      error!("could not serialise report: {}", e);
+      return 1;
+    },
+  };
+
+  info!("signing report. you may need to enter your pgp passphrase");
+  let signature = match crate::sign_detached(config, &json) {
+    Ok(s) => s,
+    Err(exit) => return exit,
+  };
+
+  let mut sig_path = OsString::from(output.as_os_str());
+  sig_path.push(".sig");
+  let sig_path = PathBuf::from(sig_path);
+  if write(&output, &json).is_err() || write(&sig_path, &signature).is_err() {
+    return 1;
+  }
+
+  info!("wrote {} and {}", output.to_string_lossy(), sig_path.to_string_lossy());
+  0
+}
+
+pub fn check_sinks(audit: &Audit) -> Result<(), i32> {
+  let unknown: Vec<&String> = audit.sinks.iter().filter(|s| Sink::parse(s).is_none()).collect();
+  for sink in &unknown {
+    error!("unknown audit sink `{}`: use \"file\", \"syslog\" or \"webhook:<http(s) url>\"", sink);
+  }
+  if !unknown.is_empty() {
+    return Err(1);
+  }
+  Ok(())
+}
+
+// sends an event to each configured sink. failing to record is worth a warning, not aborting
+// whatever was being recorded
+pub fn record(config: &Config, event: &str) {
+  let time = unix(SystemTime::now()).unwrap_or(0);
+  for sink in config.audit.sinks.iter().filter_map(|s| Sink::parse(s)) {
+    match sink {
+      Sink::File => record_file(config, time, event),
+      // a simulated run has nothing real to tell anyone else about
+      _ if config.simulate => {},
+      Sink::Syslog => record_syslog(event),
+      Sink::Webhook(url) => record_webhook(config, url, time, event),
+    }
+  }
+}
+
+fn record_file(config: &Config, time: u64, event: &str) {
+  if config.no_write {
+    return;
+  }
+
+  let path = match crate::data_dir(config) {
+    Ok(d) => d.join(LOG_FILE),
+    Err(_) => return,
+  };
+  let res = if path.exists() {
+    OpenOptions::new().append(true).open(&path)
+  } else {
+    crate::files::create_file(&path)
+  };
+  let res = res.and_then(|mut f| writeln!(f, "{} {}", time, event));
+  if let Err(e) = res {
+    warn!("could not record audit event in {}: {}", path.to_string_lossy(), e);
+  }
+}
+
+#[cfg(unix)]
+fn record_syslog(event: &str) {
+  use std::ffi::CString;
+
+  let message = match CString::new(event) {
+    Ok(m) => m,
+    Err(_) => return,
+  };
+  // openlog keeps the ident pointer, so it has to be static
+  unsafe {
+    libc::openlog(b"gpg-alias\0".as_ptr().cast(), libc::LOG_PID, libc::LOG_AUTH);
+    libc::syslog(libc::LOG_NOTICE, b"%s\0".as_ptr().cast(), message.as_ptr());
+  }
+}
+
+#[cfg(not(unix))]
+fn record_syslog(_: &str) {
+  warn!("syslog is not available on this platform, so the audit event was not sent there");
+}
+
+// delivered by curl in the background, so a slow or unreachable endpoint never holds up
+// resolution. curl retries transient failures with exponential backoff until the timeout is up
+fn record_webhook(config: &Config, url: &str, time: u64, event: &str) {
+  let body = match serde_json::to_vec(&Event { time, version: clap::crate_version!(), event }) {
+    Ok(b) => b,
+    Err(e) => {
+      warn!("could not serialise audit event: {}", e);
+      return;
+    },
+  };
+  let timeout = config.audit.webhook_timeout_secs.unwrap_or(DEFAULT_WEBHOOK_TIMEOUT_SECS).to_string();
+
+  let child = Command::new("curl")
+    .args(["--silent", "--fail", "--max-time", &timeout, "--retry", "3", "--retry-max-time", &timeout])
+    .args(["--header", "Content-Type: application/json", "--data-binary", "@-", "--", url])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn();
+  let mut child = match child {
+    Ok(c) => c,
+    Err(e) => {
+      warn!("could not run curl to send audit event to {}: {}", url, e);
+      return;
+    },
+  };
+
+  if let Some(mut stdin) = child.stdin.take() {
+    if let Err(e) = stdin.write_all(&body) {
+      warn!("could not send audit event to {}: {}", url, e);
+    }
+  }
+  // not waited for here, so a slow webhook never holds up the command: curl outlives us if it has
+  // to, bounded by its own timeout. in long-running processes such as `serve`, the thread reaps it
+  // so it doesn't linger as a zombie
+  std::thread::spawn(move || child.wait());
+}
+
+// what the attestation says about itself, independent of whether it currently verifies
+pub fn attestation(config: &Config, ctx: &mut Context, alias: &str, id: &str) -> Option<Attestation> {
+  let path = if config.system.aliases.contains(alias) {
+    crate::system_signature_path(alias)
+  } else {
+    match config.signing.method {
+      Method::File => crate::signature_path(config, alias).ok()?,
+      Method::Lsign => {
+        let date = crate::lsign::certified_at(config, id).ok()??;
+        return Some(Attestation {
+          method: "lsign",
+          signer: Some(config.signing.key.clone()),
+          date: unix(date),
+        });
+      },
+      Method::Hmac => {
+        let path = crate::mac::attestation_path(config, alias).ok()?;
+        let date = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        return Some(Attestation {
+          method: "hmac",
+          signer: None,
+          date: unix(date),
+        });
+      },
+    }
+  };
+
+  let signed = crate::read_signature(&path).ok()?;
+  let mut plaintext = Vec::new();
+  let res = ctx.verify_opaque(signed, &mut plaintext).ok()?;
+  let sig = res.signatures().next()?;
+
+  Some(Attestation {
+    method: "file",
+    signer: sig.fingerprint().ok().map(ToString::to_string),
+    date: sig.creation_time().and_then(unix),
+  })
+}
+
+fn unix(time: SystemTime) -> Option<u64> {
+  time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), ()> {
+  let mut file = match crate::files::create_file(path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not create {}: {}", path.to_string_lossy(), e);
+      return Err(());
+    },
+  };
+  if let Err(e) = file.write_all(data) {
+    error!("could not write {}: {}", path.to_string_lossy(), e);
+    return Err(());
+  }
+
+  Ok(())
+}
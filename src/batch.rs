@@ -0,0 +1,35 @@
+use std::sync::Mutex;
+
+// state for runs that would otherwise ask the same question many times: what is still to come, and
+// which questions were answered "yes to all". it never outlives the process
+struct Batch {
+  pending: Vec<String>,
+  accepted: Vec<&'static str>,
+}
+
+static BATCH: Mutex<Batch> = Mutex::new(Batch { pending: Vec::new(), accepted: Vec::new() });
+
+pub fn set_pending(items: Vec<String>) {
+  if let Ok(mut batch) = BATCH.lock() {
+    batch.pending = items;
+  }
+}
+
+// the item currently being handled is still pending, so a batch is only worth offering with more
+pub fn is_active() -> bool {
+  BATCH.lock().map(|b| b.pending.len() > 1).unwrap_or(false)
+}
+
+pub fn pending() -> Vec<String> {
+  BATCH.lock().map(|b| b.pending.clone()).unwrap_or_default()
+}
+
+pub fn accept_all(kind: &'static str) {
+  if let Ok(mut batch) = BATCH.lock() {
+    batch.accepted.push(kind);
+  }
+}
+
+pub fn is_accepted(kind: &str) -> bool {
+  BATCH.lock().map(|b| b.accepted.contains(&kind)).unwrap_or(false)
+}
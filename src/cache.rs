@@ -0,0 +1,191 @@
+use serde_derive::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::Config;
+
+use std::{
+  fs::{File, OpenOptions},
+  io::Write,
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+// attestations verified recently, shared by every gpg-alias process using the data directory, such
+// as the native host and whatever is run from the shell. each line is `<unix time> <digest>`
+pub const CACHE_FILE: &str = "resolution-cache";
+// writers hold this while appending or compacting. readers never wait: an append is a single write
+// and a compaction replaces the file whole, so at worst they see a partial line and ignore it
+pub const LOCK_FILE: &str = "resolution-cache.lock";
+// compacted past this many lines, dropping expired and repeated entries
+const MAX_LINES: usize = 1000;
+const DEFAULT_TTL_SECONDS: u64 = 600;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Cache {
+  #[serde(default)]
+  pub enabled: bool,
+  pub ttl_seconds: Option<u64>,
+}
+
+// runs `verify` unless the same attestation was verified for the same key within the ttl. anyone
+// able to write to the data directory can add entries, which is why the cache is off by default
+pub fn verified<F>(config: &Config, alias: &str, id: &str, sig_path: &Path, verify: F) -> Result<(), i32>
+  where F: FnOnce() -> Result<(), i32>,
+{
+  if !config.cache.enabled || crate::mock::is_enabled() || crate::fresh::is_required(config, alias) {
+    return verify();
+  }
+  let digest = match digest(config, alias, id, sig_path) {
+    Some(d) => d,
+    None => return verify(),
+  };
+
+  if is_cached(config, &digest) {
+    debug!("attestation for `{}` was verified recently, according to the cache", alias);
+    // the digest covers the fingerprint, so it's the one verifying accepted
+    if let Some(fingerprint) = crate::pin::fingerprint(id) {
+      crate::pin::remember(id, &fingerprint);
+    }
+    return Ok(());
+  }
+  verify()?;
+  if !config.no_write {
+    record(config, &digest);
+  }
+  Ok(())
+}
+
+// covers everything verifying depends on, so an entry can only ever stand for the same answer: a
+// changed attestation, alias expiry or signing key misses, as does the key ID now matching another
+// key or its primary user ID changing, which verifying would have caught
+fn digest(config: &Config, alias: &str, id: &str, sig_path: &Path) -> Option<String> {
+  let signed = std::fs::read(sig_path).ok()?;
+
+  let fingerprint = crate::pin::fingerprint(id);
+  let uid = crate::pin::primary_uid(id);
+
+  let mut hasher = Sha256::new();
+  let parts = [
+    Some(config.signing.key.as_str()),
+    Some(alias),
+    Some(id),
+    crate::metadata::expires(config, alias),
+    crate::required_notation(config, alias),
+    fingerprint.as_deref(),
+    uid.as_deref(),
+  ];
+  for part in &parts {
+    hasher.update(part.unwrap_or("").as_bytes());
+    hasher.update([0]);
+  }
+  hasher.update(&signed);
+  Some(hex::encode(hasher.finalize()))
+}
+
+fn is_cached(config: &Config, digest: &str) -> bool {
+  let contents = match std::fs::read_to_string(config.data_dir.join(CACHE_FILE)) {
+    Ok(c) => c,
+    Err(_) => return false,
+  };
+  let now = now();
+  let ttl = config.cache.ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS);
+  contents.lines()
+    .filter_map(parse)
+    .any(|(time, d)| d == digest && time <= now && now - time <= ttl)
+}
+
+fn record(config: &Config, digest: &str) {
+  let data_dir = match crate::data_dir(config) {
+    Ok(d) => d,
+    Err(_) => return,
+  };
+  let path = data_dir.join(CACHE_FILE);
+  let _lock = match lock(&data_dir.join(LOCK_FILE)) {
+    Some(l) => l,
+    None => return,
+  };
+
+  let res = if path.exists() {
+    OpenOptions::new().append(true).open(&path)
+  } else {
+    crate::files::create_file(&path)
+  };
+  let res = res.and_then(|mut f| f.write_all(format!("{} {}\n", now(), digest).as_bytes()));
+  if let Err(e) = res {
+    warn!("could not update {}: {}", path.to_string_lossy(), e);
+    return;
+  }
+
+  compact(config, &path);
+}
+
+fn compact(config: &Config, path: &Path) {
+  let contents = match std::fs::read_to_string(path) {
+    Ok(c) => c,
+    Err(_) => return,
+  };
+  if contents.lines().count() <= MAX_LINES {
+    return;
+  }
+
+  let now = now();
+  let ttl = config.cache.ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS);
+  let mut kept: Vec<(u64, &str)> = Vec::new();
+  for (time, digest) in contents.lines().filter_map(parse).filter(|(t, _)| *t <= now && now - *t <= ttl) {
+    match kept.iter_mut().find(|(_, d)| *d == digest) {
+      Some(entry) => entry.0 = entry.0.max(time),
+      None => kept.push((time, digest)),
+    }
+  }
+
+  let tmp = path.with_extension("tmp");
+  let res = crate::files::create_file(&tmp)
+    .and_then(|mut f| kept.iter().try_for_each(|(time, digest)| writeln!(f, "{} {}", time, digest)))
+    .and_then(|_| std::fs::rename(&tmp, path));
+  if let Err(e) = res {
+    warn!("could not compact {}: {}", path.to_string_lossy(), e);
+  }
+}
+
+fn parse(line: &str) -> Option<(u64, &str)> {
+  let (time, digest) = line.split_once(' ')?;
+  if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+    return None;
+  }
+  Some((time.parse().ok()?, digest))
+}
+
+// a cache is always safe to throw away, so any line that doesn't parse makes it invalid
+pub fn is_valid(path: &Path) -> bool {
+  std::fs::read_to_string(path)
+    .map(|s| s.lines().all(|l| parse(l).is_some()))
+    .unwrap_or(false)
+}
+
+fn now() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// held until the returned file is dropped
+#[cfg(unix)]
+fn lock(path: &Path) -> Option<File> {
+  use std::os::unix::io::AsRawFd;
+
+  let file = match OpenOptions::new().create(true).append(true).open(path) {
+    Ok(f) => f,
+    Err(e) => {
+      warn!("could not open {}: {}", path.to_string_lossy(), e);
+      return None;
+    },
+  };
+  if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+    warn!("could not lock {}: {}", path.to_string_lossy(), std::io::Error::last_os_error());
+    return None;
+  }
+  Some(file)
+}
+
+#[cfg(not(unix))]
+fn lock(path: &Path) -> Option<File> {
+  OpenOptions::new().create(true).append(true).open(path).ok()
+}
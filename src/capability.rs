@@ -0,0 +1,51 @@
+use gpgme::Context;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capability {
+  Encrypt,
+  Sign,
+  Auth,
+}
+
+impl Capability {
+  pub fn from_name(name: &str) -> Option<Capability> {
+    match name {
+      "encrypt" => Some(Capability::Encrypt),
+      "sign" => Some(Capability::Sign),
+      "auth" => Some(Capability::Auth),
+      _ => None,
+    }
+  }
+
+  pub fn verb(self) -> &'static str {
+    match self {
+      Capability::Encrypt => "encrypt",
+      Capability::Sign => "sign",
+      Capability::Auth => "authenticate",
+    }
+  }
+
+  // gpgme reports a key as capable if any of its usable subkeys is
+  pub fn check(self, key_id: &str) -> Result<bool, i32> {
+    let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+      Ok(c) => c,
+      Err(e) => {
+        error!("could not create gpgme context: {}", e);
+        return Err(1);
+      },
+    };
+    let key = match ctx.get_key(key_id) {
+      Ok(k) => k,
+      Err(e) => {
+        error!("could not find key `{}` in keyring: {}", key_id, e);
+        return Err(1);
+      },
+    };
+
+    Ok(match self {
+      Capability::Encrypt => key.can_encrypt(),
+      Capability::Sign => key.can_sign(),
+      Capability::Auth => key.can_authenticate(),
+    })
+  }
+}
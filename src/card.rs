@@ -0,0 +1,92 @@
+use clap::ArgMatches;
+use gpgme::Context;
+
+use crate::Config;
+
+use std::{io::Write, path::Path};
+
+// a clearsigned statement of what an alias points to, small enough to paste into a ticket or an
+// e-mail when someone asks us to vouch for it. only an alias that verifies gets one, and anyone with
+// the signing key's public key can check it with `gpg --verify`
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("alias").expect("required clap argument");
+
+  if !config.signing.enabled {
+    error!("identity cards are signed with the signing key, so signing has to be enabled");
+    return 1;
+  }
+  if config.groups.contains_key(name) {
+    error!("`{}` is a group: cards are made for single aliases", name);
+    return 1;
+  }
+  let key_id = match crate::resolve_verified(config, name) {
+    Ok(k) => k,
+    Err(exit) => return exit,
+  };
+
+  let text = match render(config, name, &key_id) {
+    Ok(t) => t,
+    Err(exit) => return exit,
+  };
+  let signed = match crate::sign(config, &text, None) {
+    Ok(s) => s,
+    Err(exit) => return exit,
+  };
+
+  match matches.value_of_os("output") {
+    Some(path) => {
+      let path = Path::new(path);
+      let res = crate::files::create_file(path).and_then(|mut f| f.write_all(&signed));
+      if let Err(e) = res {
+        error!("could not write {}: {}", path.to_string_lossy(), e);
+        return 1;
+      }
+      info!("wrote the card for `{}` to {}", name, path.to_string_lossy());
+    },
+    None => print!("{}", String::from_utf8_lossy(&signed)),
+  }
+
+  0
+}
+
+fn render(config: &Config, name: &str, key_id: &str) -> Result<String, i32> {
+  let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let key = match ctx.get_key(key_id) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not find the key for `{}`: {}", name, e);
+      return Err(1);
+    },
+  };
+
+  let fingerprint = key.fingerprint().map(ToString::to_string).unwrap_or_else(|_| key_id.to_string());
+  let mut lines = vec![
+    "gpg-alias identity card".to_string(),
+    String::new(),
+    format!("alias:       {}", name),
+    format!("fingerprint: {}", crate::roster::grouped(&fingerprint)),
+  ];
+  for uid in key.user_ids().filter(|u| !u.is_revoked() && !u.is_invalid()) {
+    if let Ok(id) = uid.id() {
+      lines.push(format!("uid:         {}", id));
+    }
+  }
+
+  let alias = &config.aliases[name];
+  if let Some(p) = alias.entry().and_then(crate::provenance::of) {
+    lines.push(format!("verified:    {}", p.describe()));
+  }
+  if let Some(expires) = crate::metadata::expires(config, name) {
+    lines.push(format!("expires:     {}", expires));
+  }
+  lines.push(format!("issued:      {}", crate::provenance::today()));
+  lines.push(String::new());
+
+  Ok(lines.join("\n"))
+}
@@ -0,0 +1,34 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  // the exit status is the only output
+  log::set_max_level(log::LevelFilter::Off);
+
+  for alias in matches.values_of("alias").expect("required clap argument") {
+    if let Err(exit) = check(config, alias) {
+      return exit;
+    }
+  }
+
+  0
+}
+
+// the same checks resolving the alias for gpg makes, so whatever passes here would be encrypted to
+fn check(config: &Config, alias: &str) -> Result<(), i32> {
+  let keys = if config.groups.contains_key(alias) {
+    crate::groups::expand(config, alias, true).map_err(|_| crate::EXIT_UNVERIFIED)?
+  } else {
+    // anything that stops a configured alias resolving is about its attestation
+    let key_id = crate::resolve_verified(config, alias)
+      .map_err(|exit| if config.aliases.contains_key(alias) { crate::EXIT_UNVERIFIED } else { exit })?;
+    vec![key_id.into_owned()]
+  };
+
+  for key_id in keys {
+    crate::validity::check(config, alias, &key_id).map_err(|_| crate::EXIT_KEY_PROBLEM)?;
+  }
+
+  Ok(())
+}
@@ -1,4 +1,4 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
 
 pub fn app<'a, 'b>() -> App<'a, 'b> {
   App::new(clap::crate_name!())
@@ -8,19 +8,682 @@ pub fn app<'a, 'b>() -> App<'a, 'b> {
     .help_message("prints help information")
     .version_message("prints version information")
     .version_short("v")
+    .setting(AppSettings::SubcommandsNegateReqs)
+
+    .arg(Arg::with_name("config")
+      .long("config")
+      .takes_value(true)
+      .value_name("PATH")
+      .global(true)
+      .help("uses the config file at PATH instead of the default (or GPG_ALIAS_CONFIG)"))
+
+    .arg(Arg::with_name("profile")
+      .long("profile")
+      .takes_value(true)
+      .value_name("NAME")
+      .global(true)
+      .conflicts_with("config")
+      .help("uses gpg-alias.NAME.toml and its own data directory, keeping separate sets of aliases apart"))
+
+    .arg(Arg::with_name("data-dir")
+      .long("data-dir")
+      .takes_value(true)
+      .value_name("PATH")
+      .global(true)
+      .help("stores signatures in PATH instead of the default data directory"))
+
+    .arg(Arg::with_name("no-write")
+      .long("no-write")
+      .global(true)
+      .help("never creates directories, a default config or signatures"))
+
+    .arg(Arg::with_name("backend")
+      .long("backend")
+      .takes_value(true)
+      .possible_values(&["gpgme", "mock"])
+      .default_value("gpgme")
+      .global(true)
+      .help("signs and verifies attestations with gpg, or with an in-memory mock that needs no keys"))
+
+    .arg(Arg::with_name("simulate")
+      .long("simulate")
+      .global(true)
+      .help("uses the mock backend and a temporary data directory, and never edits the config"))
+
+    .arg(Arg::with_name("define")
+      .long("define")
+      .takes_value(true)
+      .value_name("NAME=FINGERPRINT")
+      .multiple(true)
+      .number_of_values(1)
+      .global(true)
+      .help("defines an unattested alias for this run only, which groups can include as @NAME"))
+
+    .arg(Arg::with_name("verbose")
+      .short("V")
+      .long("verbose")
+      .multiple(true)
+      .global(true)
+      .help("logs more: once for debug messages, twice for trace messages"))
+
+    .arg(Arg::with_name("quiet")
+      .short("q")
+      .long("quiet")
+      .multiple(true)
+      .global(true)
+      .help("logs less: once for only warnings and errors, twice for only errors, three times for nothing"))
+
+    .arg(Arg::with_name("fresh")
+      .long("fresh")
+      .global(true)
+      .help("refreshes every alias's key and checks the signing key before trusting anything, as require_fresh does"))
+
+    .arg(Arg::with_name("force")
+      .long("force")
+      .global(true)
+      .help("uses aliases past their expiry date, with a warning"))
+
+    .arg(Arg::with_name("batch")
+      .long("batch")
+      .global(true)
+      .conflicts_with("yes")
+      .help("fails instead of asking for confirmation (the default when stdin is not a terminal)"))
+
+    .arg(Arg::with_name("yes")
+      .long("yes")
+      .global(true)
+      .help("answers yes to every confirmation instead of asking"))
+
+    .arg(Arg::with_name("timings")
+      .long("timings")
+      .global(true)
+      .help("reports how long each stage took at the end of the run"))
 
     .arg(Arg::with_name("sign-all")
       .short("s")
       .long("sign-all")
       .help("check for any unsigned aliases, sign them, then exit"))
 
+    .arg(Arg::with_name("progress-format")
+      .long("progress-format")
+      .takes_value(true)
+      .possible_values(&["text", "json"])
+      .requires("sign-all")
+      .help("with --sign-all, prints progress as json lines on stdout instead of a progress bar"))
+
     .arg(Arg::with_name("recipients")
       .short("r")
       .long("recipients")
       .help("prefixes each alias with `-r ` for use on the command line"))
 
+    .arg(Arg::with_name("output-format")
+      .long("output-format")
+      .takes_value(true)
+      .possible_values(&["plain", "recipients", "json"])
+      .conflicts_with_all(&["recipients", "template", "annotate"])
+      .help("prints one key per line, `-r` arguments (like -r), or a json array with each key's alias, fingerprint and status"))
+
+    .arg(Arg::with_name("template")
+      .long("template")
+      .takes_value(true)
+      .value_name("TEMPLATE")
+      .conflicts_with_all(&["recipients", "annotate"])
+      .help("prints TEMPLATE for each key, filling in {alias}, {key}, {fingerprint} and {status}"))
+
+    .arg(Arg::with_name("output")
+      .long("output")
+      .takes_value(true)
+      .conflicts_with_all(&["output-format", "template"])
+      .possible_values(&["rfc822-to"])
+      .help("also prints a `To:` header value naming the owners of the keys, after any `-r` output"))
+
+    .arg(Arg::with_name("tar")
+      .long("tar")
+      .conflicts_with_all(&["borg", "recipients", "output-format", "template", "output", "annotate", "sign-all"])
+      .help("prints the gpg command that encrypts a tar stream to the aliases, for `eval`, as `integrate tar` does"))
+
+    .arg(Arg::with_name("borg")
+      .long("borg")
+      .conflicts_with_all(&["recipients", "output-format", "template", "output", "annotate", "sign-all"])
+      .help("prints the commands that keep borg's repository passphrase encrypted to the aliases, as `integrate borg` does"))
+
+    .arg(Arg::with_name("annotate")
+      .long("annotate")
+      .help("prefixes each output line with the alias it came from, e.g. `alice: -r FPR`"))
+
+    .arg(Arg::with_name("preview")
+      .long("preview")
+      .help("first writes who the keys belong to, and through which alias or group, to stderr"))
+
+    .arg(Arg::with_name("only-verified")
+      .long("only-verified")
+      .help("skips aliases whose signature is missing or invalid instead of failing"))
+
+    .arg(Arg::with_name("require-capability")
+      .long("require-capability")
+      .takes_value(true)
+      .possible_values(&["encrypt", "sign", "auth"])
+      .help("skips aliases whose keys lack the capability instead of printing them"))
+
+    .arg(Arg::with_name("shell-quote")
+      .long("shell-quote")
+      .takes_value(true)
+      .possible_values(&["posix", "fish"])
+      .help("quotes each key ID in `-r` output for the given shell"))
+
+    .arg(Arg::with_name("fetch")
+      .long("fetch")
+      .help("fetches an alias's key via WKD or its keyserver if it's missing from the keyring, asking before using it"))
+
+    .arg(Arg::with_name("from-env")
+      .long("from-env")
+      .help("also reads whitespace-separated aliases from GPG_ALIAS_RECIPIENTS"))
+
     .arg(Arg::with_name("alias")
-      .help("alias to print")
-      .multiple(true)
-      .required_unless("sign-all"))
+      .help("alias to print (defaults to the current workspace's recipients)")
+      .multiple(true))
+
+    .subcommand(SubCommand::with_name("selftest")
+      .about("signs and verifies a test payload, then resolves an alias, reporting timings")
+      .arg(Arg::with_name("alias")
+        .help("alias to resolve (defaults to the first configured alias)")))
+
+    .subcommand(SubCommand::with_name("fetch")
+      .about("fetches the keys for aliases from their keyserver or WKD domain")
+      .arg(Arg::with_name("alias")
+        .help("aliases to fetch keys for (defaults to all aliases)")
+        .multiple(true))
+      .arg(Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .default_value("text")
+        .help("prints progress as json lines on stdout instead of a progress bar")))
+
+    .subcommand(SubCommand::with_name("verify-all")
+      .about("verifies the signatures of all aliases without prompting")
+      .arg(Arg::with_name("report")
+        .long("report")
+        .takes_value(true)
+        .value_name("mail:ADDRESS|file:PATH")
+        .multiple(true)
+        .number_of_values(1)
+        .help("sends a summary report of failures by mail or writes it to a file"))
+      .arg(Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .default_value("text")
+        .help("prints progress as json lines on stdout instead of a progress bar")))
+
+    .subcommand(SubCommand::with_name("verify-directory")
+      .about("compares aliases' keys with what an organisation publishes through WKD")
+      .arg(Arg::with_name("domain")
+        .long("domain")
+        .takes_value(true)
+        .value_name("DOMAIN")
+        .required(true)
+        .help("checks the user IDs at DOMAIN against its WKD"))
+      .arg(Arg::with_name("report")
+        .long("report")
+        .takes_value(true)
+        .value_name("mail:ADDRESS|file:PATH")
+        .multiple(true)
+        .number_of_values(1)
+        .help("sends a summary report of mismatches by mail or writes it to a file"))
+      .arg(Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .default_value("text")
+        .help("prints progress as json lines on stdout instead of a progress bar")))
+
+    .subcommand(SubCommand::with_name("explain")
+      .about("prints how an alias was resolved, without prompting")
+      .arg(Arg::with_name("alias")
+        .help("alias to explain")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("whoami")
+      .about("prints the signing key's details and which attestations it made, rather than an older key"))
+
+    .subcommand(SubCommand::with_name("card")
+      .about("prints a signed text block vouching for what an alias points to, to paste into a ticket or e-mail")
+      .arg(Arg::with_name("alias")
+        .help("alias to vouch for")
+        .required(true))
+      .arg(Arg::with_name("output")
+        .short("o")
+        .long("output")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("writes the card to FILE instead")))
+
+    .subcommand(SubCommand::with_name("verify-attestation")
+      .about("verifies a single attestation file against a keyring, without loading any config")
+      .arg(Arg::with_name("keyring")
+        .long("keyring")
+        .takes_value(true)
+        .value_name("FILE")
+        .required(true)
+        .help("keyring whose keys are trusted to have made the attestation"))
+      .arg(Arg::with_name("signer")
+        .long("signer")
+        .takes_value(true)
+        .value_name("FINGERPRINT")
+        .help("requires the attestation to have been made by this key"))
+      .arg(Arg::with_name("alias")
+        .long("alias")
+        .takes_value(true)
+        .value_name("ALIAS")
+        .help("requires the attestation to have been made for this alias"))
+      .arg(Arg::with_name("file")
+        .help("attestation to verify")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("run")
+      .about("runs gpg with the keys for the given aliases as recipients")
+      .arg(Arg::with_name("to")
+        .long("to")
+        .takes_value(true)
+        .value_name("ALIAS")
+        .multiple(true)
+        .number_of_values(1)
+        .required(true)
+        .help("alias to add as a recipient"))
+      .arg(Arg::with_name("gpg-args")
+        .long("gpg-args")
+        .takes_value(true)
+        .allow_hyphen_values(true)
+        .help("whitespace-separated arguments to pass to gpg before the recipients"))
+      .arg(Arg::with_name("print-cmd")
+        .long("print-cmd")
+        .help("prints the gpg command, quoted for the shell, instead of running it"))
+      .arg(Arg::with_name("args")
+        .help("arguments to pass to gpg after the recipients, such as files")
+        .multiple(true)
+        .last(true)))
+
+    .subcommand(SubCommand::with_name("info")
+      .about("prints a summary of the alias database"))
+
+    .subcommand(SubCommand::with_name("for-email")
+      .about("prints the fingerprint of the aliased key with a user ID for an e-mail address")
+      .arg(Arg::with_name("email")
+        .help("e-mail address to look up")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("check")
+      .about("verifies aliases without printing anything, exiting unsuccessfully if any are untrustworthy")
+      .arg(Arg::with_name("alias")
+        .help("aliases to check")
+        .multiple(true)
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("recipient-files")
+      .about("exports the keys for aliases to files for use with gpg's --recipient-file")
+      .arg(Arg::with_name("dir")
+        .long("dir")
+        .takes_value(true)
+        .value_name("DIR")
+        .help("writes each key to DIR/<alias>.asc"))
+      .arg(Arg::with_name("output")
+        .short("o")
+        .long("output")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("writes all keys to FILE"))
+      .group(ArgGroup::with_name("destination")
+        .args(&["dir", "output"])
+        .required(true))
+      .arg(Arg::with_name("alias")
+        .help("aliases to export keys for")
+        .multiple(true)
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("prompt-status")
+      .about("prints a short token counting failing attestations, for shell prompts; cached between runs"))
+
+    .subcommand(SubCommand::with_name("due")
+      .about("lists aliases whose keys expire soon or whose attestations are due for re-verification")
+      .arg(Arg::with_name("within")
+        .long("within")
+        .takes_value(true)
+        .value_name("DAYS")
+        .default_value("30")
+        .help("includes anything due within DAYS days")))
+
+    .subcommand(SubCommand::with_name("audit")
+      .about("produces reports on the state of the alias database")
+      .setting(AppSettings::SubcommandRequiredElseHelp)
+      .subcommand(SubCommand::with_name("export")
+        .about("writes a signed JSON snapshot of every alias, its key and its attestation")
+        .arg(Arg::with_name("output")
+          .short("o")
+          .long("output")
+          .takes_value(true)
+          .value_name("FILE")
+          .required(true)
+          .help("writes the report to FILE and its detached signature to FILE.sig"))))
+
+    .subcommand(SubCommand::with_name("group")
+      .about("builds and manages groups")
+      .setting(AppSettings::SubcommandRequiredElseHelp)
+      .subcommand(SubCommand::with_name("build")
+        .about("adds a group of keyring keys chosen from a search, with aliases for any that have none")
+        .arg(Arg::with_name("name")
+          .help("name of the new group")
+          .required(true))
+        .arg(Arg::with_name("search")
+          .long("search")
+          .takes_value(true)
+          .value_name("QUERY")
+          .required(true)
+          .help("lists the usable keys matching QUERY, such as @example.com, to choose members from")))
+      .subcommand(SubCommand::with_name("add")
+        .about("adds aliases, groups or key IDs to a group, adding the group if there is none, and attests it again")
+        .arg(Arg::with_name("name")
+          .help("group to add to")
+          .required(true))
+        .arg(Arg::with_name("member")
+          .help("aliases, groups, `@name`s or key IDs to add")
+          .multiple(true)
+          .required(true)))
+      .subcommand(SubCommand::with_name("remove")
+        .about("removes members from a group and attests it again, removing the group once it has none")
+        .arg(Arg::with_name("name")
+          .help("group to remove from")
+          .required(true))
+        .arg(Arg::with_name("member")
+          .help("members to remove")
+          .multiple(true)
+          .required(true)))
+      .subcommand(SubCommand::with_name("list")
+        .about("lists groups and their members"))
+      .subcommand(SubCommand::with_name("members")
+        .about("lists a group's members and what each one is")
+        .arg(Arg::with_name("name")
+          .help("group to list")
+          .required(true))))
+
+    .subcommand(SubCommand::with_name("integrate")
+      .about("prints the gpg commands a backup tool needs to encrypt to aliases")
+      .arg(Arg::with_name("tool")
+        .help("tool to integrate with")
+        .possible_values(&["tar", "borg"])
+        .required(true))
+      .arg(Arg::with_name("passphrase-file")
+        .long("passphrase-file")
+        .takes_value(true)
+        .value_name("PATH")
+        .default_value(crate::integrate::PASSPHRASE_FILE)
+        .help("where borg's encrypted repository passphrase is kept"))
+      .arg(Arg::with_name("alias")
+        .help("aliases to encrypt to")
+        .multiple(true)
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("fsck")
+      .about("checks the config and data directory for problems, repairing what is safe unless --no-write is given"))
+
+    .subcommand(SubCommand::with_name("metrics")
+      .about("prints gauges in the Prometheus text format, such as how many attestations fail")
+      .arg(Arg::with_name("textfile")
+        .long("textfile")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("writes FILE atomically instead, for node_exporter's textfile collector"))
+      .arg(Arg::with_name("expiry-days")
+        .long("expiry-days")
+        .takes_value(true)
+        .value_name("DAYS")
+        .help("counts keys expiring within DAYS days (30 by default)")))
+
+    .subcommand(SubCommand::with_name("rescue")
+      .about("restores aliases from attestation files that still verify, or from a signed audit report")
+      .arg(Arg::with_name("manifest")
+        .long("manifest")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("also restores and attests again the verified aliases in a report made by `audit export`, signed in FILE.sig")))
+
+    .subcommand(SubCommand::with_name("validate")
+      .about("checks the config's aliases against its own rules, such as [naming], and for mistakes in its groups")
+      .arg(Arg::with_name("fix")
+        .long("fix")
+        .help("removes aliases that only repeat the system alias they shadow")))
+
+    .subcommand(SubCommand::with_name("serve")
+      .about("answers JSON-RPC requests to resolve, verify, list and add aliases, for long-running tools")
+      .arg(Arg::with_name("stdio")
+        .long("stdio")
+        .required(true)
+        .help("reads one request per line on stdin and writes one response per line on stdout")))
+
+    .subcommand(SubCommand::with_name("native-host")
+      .about("answers resolve and verify queries from browser extensions over native messaging")
+      .arg(Arg::with_name("browser-args")
+        .help("arguments browsers pass to native hosts, which are ignored")
+        .multiple(true)
+        .hidden(true)))
+
+    .subcommand(SubCommand::with_name("add")
+      .about("adds an alias for a key in the local keyring and attests it")
+      .arg(Arg::with_name("alias")
+        .help("the alias to add")
+        .required(true))
+      .arg(Arg::with_name("key")
+        .help("the key ID the alias points to")
+        .required(true))
+      .arg(Arg::with_name("protocol")
+        .long("protocol")
+        .takes_value(true)
+        .possible_values(&["openpgp", "cms"])
+        .help("whether the key is an OpenPGP key (the default) or an S/MIME certificate"))
+      .arg(Arg::with_name("verified-via")
+        .long("verified-via")
+        .takes_value(true)
+        .possible_values(&["in-person", "wkd", "keysigning-party"])
+        .help("records how the key was checked to belong to its owner"))
+      .arg(Arg::with_name("verified-on")
+        .long("verified-on")
+        .takes_value(true)
+        .value_name("DATE")
+        .requires("verified-via")
+        .help("records when the key was checked, as YYYY-MM-DD (defaults to today)"))
+      .arg(Arg::with_name("verified-by")
+        .long("verified-by")
+        .takes_value(true)
+        .value_name("NAME")
+        .requires("verified-via")
+        .help("records who checked the key")))
+
+    .subcommand(SubCommand::with_name("remove")
+      .about("removes an alias and its attestation")
+      .arg(Arg::with_name("alias")
+        .help("the alias to remove")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("rename")
+      .about("renames an alias, carrying its attestation over if it verifies")
+      .arg(Arg::with_name("old")
+        .help("the alias to rename")
+        .required(true))
+      .arg(Arg::with_name("new")
+        .help("the new name")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("retire")
+      .about("marks an alias to be removed by `prune` after a date, warning whenever it's used until then")
+      .arg(Arg::with_name("alias")
+        .help("the alias to retire")
+        .required(true))
+      .arg(Arg::with_name("after")
+        .long("after")
+        .takes_value(true)
+        .value_name("DATE")
+        .help("the last day the alias can be used, like 2024-01-31")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("prune")
+      .about("removes retired aliases whose date has passed, archiving their attestations"))
+
+    .subcommand(SubCommand::with_name("list")
+      .about("lists aliases and the keys they point to")
+      .arg(Arg::with_name("tag")
+        .long("tag")
+        .takes_value(true)
+        .value_name("TAG")
+        .multiple(true)
+        .number_of_values(1)
+        .help("only lists aliases with TAG (with several, aliases with all of them)")))
+
+    .subcommand(SubCommand::with_name("wrap")
+      .about("runs gpg with any recipient or local user that names an alias replaced by its key")
+      .setting(AppSettings::TrailingVarArg)
+      .setting(AppSettings::AllowLeadingHyphen)
+      .arg(Arg::with_name("args")
+        .help("arguments to pass to gpg")
+        .multiple(true)))
+
+    .subcommand(SubCommand::with_name("filter")
+      .about("copies stdin to stdout, replacing `@alias` and `@group` with verified fingerprints")
+      .arg(Arg::with_name("strict")
+        .long("strict")
+        .help("fails on an `@name` that isn't an alias or group, instead of leaving it")))
+
+    .subcommand(SubCommand::with_name("seal")
+      .about("signs the whole config, which is then refused if it changes without being sealed again"))
+
+    .subcommand(SubCommand::with_name("adopt")
+      .about("adds aliases from a bundle signed by someone else, attesting each accepted one yourself")
+      .arg(Arg::with_name("signer")
+        .long("signer")
+        .takes_value(true)
+        .value_name("KEY")
+        .help("requires the bundle to be signed by KEY instead of asking whether to trust its signer"))
+      .arg(Arg::with_name("bundle")
+        .help("a clearsigned toml file with an [aliases] table")
+        .required(true)))
+
+    .subcommand(SubCommand::with_name("import")
+      .about("proposes aliases for the keys in the keyring, or reads them from a file made by export, attesting each accepted one")
+      .arg(Arg::with_name("file")
+        .long("file")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("imports the aliases in FILE (toml, or json if it ends in .json) instead of walking the keyring"))
+      .arg(Arg::with_name("keylist")
+        .long("keylist")
+        .takes_value(true)
+        .value_name("FILE")
+        .conflicts_with("file")
+        .requires("signer")
+        .help("proposes aliases for the keys in a signed keylist, whose signature is in FILE.asc"))
+      .arg(Arg::with_name("signer")
+        .long("signer")
+        .takes_value(true)
+        .value_name("KEY")
+        .requires("keylist")
+        .help("key the keylist must be signed by"))
+      .arg(Arg::with_name("signature")
+        .long("signature")
+        .takes_value(true)
+        .value_name("FILE")
+        .requires("keylist")
+        .help("reads the keylist's detached signature from FILE instead")))
+
+    .subcommand(SubCommand::with_name("export")
+      .about("writes every verified alias to a file that import --file (or, once clearsigned, adopt) can read")
+      .arg(Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .possible_values(&["toml", "json", "markdown", "html", "keylist"])
+        .default_value("toml")
+        .help("the format to write: markdown and html write a roster for people to read instead, and keylist a signed keylist"))
+      .arg(Arg::with_name("signature-url")
+        .long("signature-url")
+        .takes_value(true)
+        .value_name("URL")
+        .help("where the keylist's signature will be published, as keylists record it"))
+      .arg(Arg::with_name("serial")
+        .long("serial")
+        .takes_value(true)
+        .value_name("N")
+        .help("numbers this version of the set, so adopting an older version later is refused"))
+      .arg(Arg::with_name("output")
+        .short("o")
+        .long("output")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("writes to FILE instead of stdout")))
+
+    .subcommand(SubCommand::with_name("encrypt")
+      .about("encrypts a file (or stdin) to the keys for the given aliases")
+      .arg(Arg::with_name("to")
+        .long("to")
+        .takes_value(true)
+        .value_name("ALIAS")
+        .multiple(true)
+        .number_of_values(1)
+        .required(true)
+        .help("alias or group to encrypt to"))
+      .arg(Arg::with_name("as")
+        .long("as")
+        .takes_value(true)
+        .value_name("ALIAS")
+        .help("also signs as the key for ALIAS"))
+      .arg(Arg::with_name("sender")
+        .long("sender")
+        .takes_value(true)
+        .value_name("ADDRESS")
+        .requires("as")
+        .help("records ADDRESS, which must be one of the signing key's user IDs, as the signer's"))
+      .args(&crypt_args()))
+
+    .subcommand(SubCommand::with_name("decrypt")
+      .about("decrypts a file (or stdin)")
+      .arg(Arg::with_name("cms")
+        .long("cms")
+        .help("decrypts S/MIME (CMS) data instead of OpenPGP"))
+      .args(&crypt_args()))
+
+    .subcommand(SubCommand::with_name("sign")
+      .about("signs a file (or stdin) as the key for an alias")
+      .arg(Arg::with_name("as")
+        .long("as")
+        .takes_value(true)
+        .value_name("ALIAS")
+        .required(true)
+        .help("alias whose key signs"))
+      .arg(Arg::with_name("detach")
+        .long("detach")
+        .conflicts_with("clear")
+        .help("makes a detached signature"))
+      .arg(Arg::with_name("clear")
+        .long("clear")
+        .help("makes a cleartext signature"))
+      .arg(Arg::with_name("sender")
+        .long("sender")
+        .takes_value(true)
+        .value_name("ADDRESS")
+        .help("records ADDRESS, which must be one of the signing key's user IDs, as the signer's"))
+      .args(&crypt_args()))
+}
+
+fn crypt_args<'a, 'b>() -> [Arg<'a, 'b>; 3] {
+  [
+    Arg::with_name("armor")
+      .short("a")
+      .long("armor")
+      .help("writes ASCII-armored output"),
+    Arg::with_name("output")
+      .short("o")
+      .long("output")
+      .takes_value(true)
+      .value_name("FILE")
+      .help("writes to FILE instead of stdout"),
+    Arg::with_name("file")
+      .help("the file to read (defaults to stdin)"),
+  ]
 }
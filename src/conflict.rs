@@ -0,0 +1,67 @@
+use gpgme::{Context, Protocol};
+
+use crate::Config;
+
+// the config and the attestation disagree: either the config was edited on purpose and needs a new
+// attestation, or it was tampered with and should go back to what was attested. never guess which
+pub fn resolve(config: &Config, alias: &str, configured: &str, attested: &str) -> Result<Option<String>, i32> {
+  let args = [("alias", alias), ("configured", configured), ("attested", attested)];
+  warn!("alias `{}` points to `{}`, but its signature attests `{}`", alias, configured, attested);
+  info!("{}", config.prompts.text("conflict_notice", &args));
+  info!("{}", config.prompts.text("conflict_hint", &args));
+  if !config.groups.contains_key(alias) {
+    describe("attested", attested);
+    describe("configured", configured);
+  }
+
+  // a group's attestation covers the keys it expands to, which can't be written back to the config
+  if !config.groups.contains_key(alias) && crate::confirm(config, &config.prompts.text("conflict_trust", &args))? {
+    if config.no_write {
+      error!("--no-write prevents reverting alias `{}` in the config", alias);
+      return Err(1);
+    }
+    if config.simulate {
+      info!("simulating: the config would be reverted to the attested key `{}`", attested);
+    } else {
+      crate::edit::replace_key(&config.path, alias, configured, attested)?;
+      crate::edit::finish(config)?;
+      info!("reverted alias `{}` to the attested key `{}`", alias, attested);
+    }
+    return Ok(Some(attested.to_string()));
+  }
+
+  if crate::confirm(config, &config.prompts.text("conflict_reattest", &args))? {
+    if config.no_write {
+      error!("--no-write prevents re-attesting alias `{}`", alias);
+      return Err(1);
+    }
+    crate::attest(config, alias, configured)?;
+    return Ok(None);
+  }
+
+  error!("alias `{}` does not match its signature and the conflict was not resolved", alias);
+  Err(1)
+}
+
+pub fn describe(label: &str, id: &str) {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return;
+    },
+  };
+  let key = match ctx.get_key(id) {
+    Ok(k) => k,
+    Err(e) => {
+      warn!("{} key `{}` is not in the keyring: {}", label, id, e);
+      return;
+    },
+  };
+
+  info!("{} key `{}`:", label, id);
+  info!("  fingerprint: {}", key.fingerprint().unwrap_or("<invalid>"));
+  for uid in key.user_ids() {
+    info!("  uid: {}", uid.id().unwrap_or("<invalid>"));
+  }
+}
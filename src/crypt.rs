@@ -0,0 +1,218 @@
+use clap::ArgMatches;
+use gpgme::{Context, EncryptFlags, Key, Protocol};
+
+use crate::{protocol::KeyProtocol, Config};
+
+use std::{
+  io::{Read, Write},
+  path::PathBuf,
+};
+
+pub fn encrypt(config: &Config, matches: &ArgMatches) -> i32 {
+  exit_status(try_encrypt(config, matches))
+}
+
+pub fn decrypt(_: &Config, matches: &ArgMatches) -> i32 {
+  exit_status(try_decrypt(matches))
+}
+
+pub fn sign(config: &Config, matches: &ArgMatches) -> i32 {
+  exit_status(try_sign(config, matches))
+}
+
+fn exit_status(res: Result<(), i32>) -> i32 {
+  match res {
+    Ok(()) => 0,
+    Err(exit) => exit,
+  }
+}
+
+fn try_encrypt(config: &Config, matches: &ArgMatches) -> Result<(), i32> {
+  let aliases: Vec<&str> = matches.values_of("to").expect("required clap argument").collect();
+  let recipients = crate::run::recipients(config, &aliases)?;
+
+  let protocol = recipients.first().map(|k| crate::protocol::of(k)).unwrap_or_default();
+  if let Some(other) = recipients.iter().find(|k| crate::protocol::of(k) != protocol) {
+    error!("`{}` is an {} key, but other recipients are {}: encrypt to them separately", other, crate::protocol::of(other).name(), protocol.name());
+    return Err(1);
+  }
+
+  let mut ctx = context(matches, protocol)?;
+  let keys = recipients.iter()
+    .map(|id| find_key(&mut ctx, id))
+    .collect::<Result<Vec<Key>, i32>>()?;
+  let signer = match matches.value_of("as") {
+    Some(alias) => {
+      let key_id = signer_key(config, alias)?;
+      if crate::protocol::of(&key_id) != protocol {
+        error!("`{}` can't sign: its key is not an {} key like the recipients'", alias, protocol.name());
+        return Err(1);
+      }
+      add_signer(&mut ctx, alias, &key_id, sender(config, matches, alias).as_deref())?;
+      true
+    },
+    None => false,
+  };
+  let input = read_input(matches)?;
+
+  // the recipients were verified through their attestations, which is the trust gpg-alias offers
+  // in place of gpg's own validity model
+  let mut output = Vec::new();
+  let flags = EncryptFlags::ALWAYS_TRUST | crate::options::encrypt_flags(&crate::options::for_names(config, &aliases))?;
+  let res = if signer {
+    crate::tty::ensure_gpg_tty();
+    ctx.sign_and_encrypt_with_flags(&keys, input, &mut output, flags).map(|_| ())
+  } else {
+    ctx.encrypt_with_flags(&keys, input, &mut output, flags).map(|_| ())
+  };
+  if let Err(e) = res {
+    error!("could not encrypt: {}", e);
+    return Err(1);
+  }
+  write_output(matches, &output)
+}
+
+fn try_decrypt(matches: &ArgMatches) -> Result<(), i32> {
+  let protocol = if matches.is_present("cms") { KeyProtocol::Cms } else { KeyProtocol::OpenPgp };
+  let mut ctx = context(matches, protocol)?;
+  let input = read_input(matches)?;
+  crate::tty::ensure_gpg_tty();
+
+  let mut output = Vec::new();
+  if let Err(e) = ctx.decrypt(input, &mut output) {
+    error!("could not decrypt: {}", e);
+    return Err(1);
+  }
+  write_output(matches, &output)
+}
+
+fn try_sign(config: &Config, matches: &ArgMatches) -> Result<(), i32> {
+  let alias = matches.value_of("as").expect("required clap argument");
+  let key_id = signer_key(config, alias)?;
+  let mut ctx = context(matches, crate::protocol::of(&key_id))?;
+  add_signer(&mut ctx, alias, &key_id, sender(config, matches, alias).as_deref())?;
+  let input = read_input(matches)?;
+  crate::tty::ensure_gpg_tty();
+
+  let mut output = Vec::new();
+  let res = if matches.is_present("detach") {
+    ctx.sign_detached(input, &mut output)
+  } else if matches.is_present("clear") {
+    ctx.sign_clear(input, &mut output)
+  } else {
+    ctx.sign_normal(input, &mut output)
+  };
+  if let Err(e) = res {
+    error!("could not sign: {}", e);
+    return Err(1);
+  }
+  write_output(matches, &output)
+}
+
+fn context(matches: &ArgMatches, protocol: KeyProtocol) -> Result<Context, i32> {
+  let protocol = match protocol {
+    KeyProtocol::OpenPgp => Protocol::OpenPgp,
+    KeyProtocol::Cms => Protocol::Cms,
+  };
+  let mut ctx = match Context::from_protocol(protocol) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  ctx.set_armor(matches.is_present("armor"));
+  Ok(ctx)
+}
+
+fn find_key(ctx: &mut Context, id: &str) -> Result<Key, i32> {
+  match ctx.get_key(id) {
+    Ok(k) => Ok(k),
+    Err(e) => {
+      error!("could not find key `{}` in keyring: {}", id, e);
+      Err(crate::EXIT_KEY_PROBLEM)
+    },
+  }
+}
+
+// the key an alias to sign as resolves to, verified like any other alias
+fn signer_key(config: &Config, alias: &str) -> Result<String, i32> {
+  let key_id = crate::resolve(config, alias)?.into_owned();
+  crate::validity::check(config, alias, &key_id)?;
+  Ok(key_id)
+}
+
+// --sender, or the `sender` configured for the alias
+fn sender(config: &Config, matches: &ArgMatches, alias: &str) -> Option<String> {
+  matches.value_of("sender").map(ToString::to_string)
+    .or_else(|| config.aliases.get(alias).and_then(|a| a.entry()).and_then(|e| e.sender.clone()))
+}
+
+fn add_signer(ctx: &mut Context, alias: &str, key_id: &str, sender: Option<&str>) -> Result<(), i32> {
+  let key = find_key(ctx, key_id)?;
+  if !key.has_secret() {
+    error!("there is no secret key for `{}`, so it cannot sign", alias);
+    return Err(crate::EXIT_KEY_PROBLEM);
+  }
+
+  ctx.clear_signers();
+  if let Err(e) = ctx.add_signer(&key) {
+    error!("could not add `{}` as a signer: {}", alias, e);
+    return Err(1);
+  }
+
+  // embedded in the signature so verifiers know which of the key's user IDs to expect, which is
+  // only a useful hint if the key really has it
+  if let Some(sender) = sender {
+    let has_uid = key.user_ids()
+      .filter(|u| !u.is_revoked() && !u.is_invalid())
+      .any(|u| u.email().is_ok_and(|e| e.eq_ignore_ascii_case(sender)));
+    if !has_uid {
+      error!("the key for `{}` has no user ID for {}, so it cannot sign as that sender", alias, sender);
+      return Err(crate::EXIT_KEY_PROBLEM);
+    }
+    if let Err(e) = ctx.set_sender(sender) {
+      error!("could not set the sender to {}: {}", sender, e);
+      return Err(1);
+    }
+  }
+  Ok(())
+}
+
+// the whole input is read before anything is written, so a failure never leaves half an output
+fn read_input(matches: &ArgMatches) -> Result<Vec<u8>, i32> {
+  let mut input = Vec::new();
+  let res = match matches.value_of_os("file").filter(|f| *f != "-") {
+    Some(path) => std::fs::File::open(path).and_then(|mut f| f.read_to_end(&mut input)),
+    None => std::io::stdin().read_to_end(&mut input),
+  };
+  if let Err(e) = res {
+    error!("could not read input: {}", e);
+    return Err(1);
+  }
+
+  Ok(input)
+}
+
+fn write_output(matches: &ArgMatches, output: &[u8]) -> Result<(), i32> {
+  match matches.value_of_os("output").filter(|o| *o != "-") {
+    Some(path) => {
+      let path = PathBuf::from(path);
+      let res = crate::files::create_file(&path).and_then(|mut f| f.write_all(output));
+      if let Err(e) = res {
+        error!("could not write {}: {}", path.to_string_lossy(), e);
+        return Err(1);
+      }
+    },
+    None => {
+      let stdout = std::io::stdout();
+      let mut stdout = stdout.lock();
+      if let Err(e) = stdout.write_all(output).and_then(|_| stdout.flush()) {
+        error!("could not write output: {}", e);
+        return Err(1);
+      }
+    },
+  }
+
+  Ok(())
+}
@@ -0,0 +1,80 @@
+use gpgme::Context;
+use serde_derive::Deserialize;
+
+use crate::{protocol::KeyProtocol, Config};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Deduplication {
+  #[serde(default)]
+  pub prefer: Prefer,
+}
+
+// what several recipients naming the same certificate collapse to. only a key ID ending in `!`
+// pins a subkey: any other ID, even a subkey's, lets gpg pick the certificate's encryption subkey
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Prefer {
+  // the pinned subkeys, as whoever pinned them asked for
+  #[default]
+  Subkey,
+  // the certificate once, by its primary fingerprint
+  Primary,
+}
+
+// recipients that name the same certificate, such as a group with one alias for a person's key and
+// another pinning one of its subkeys, are collapsed so nothing is encrypted to it twice. keys that
+// can't be looked up are left as they are
+pub fn collapse(config: &Config, keys: Vec<String>) -> Vec<String> {
+  if keys.len() < 2 || crate::mock::is_enabled() {
+    return keys;
+  }
+
+  let primaries: Vec<Option<String>> = keys.iter().map(|k| primary(k)).collect();
+  let mut collapsed: Vec<String> = Vec::new();
+  for (i, key) in keys.iter().enumerate() {
+    let fpr = match &primaries[i] {
+      Some(f) => f,
+      None => {
+        collapsed.push(key.clone());
+        continue;
+      },
+    };
+    let same: Vec<usize> = (0..keys.len()).filter(|j| primaries[*j].as_ref() == Some(fpr)).collect();
+    if same.len() == 1 {
+      collapsed.push(key.clone());
+      continue;
+    }
+
+    let first = same[0] == i;
+    let any_pinned = same.iter().any(|j| is_pinned(&keys[*j]));
+    let chosen = match config.deduplication.prefer {
+      Prefer::Subkey if any_pinned => is_pinned(key).then(|| key.clone()),
+      Prefer::Subkey => first.then(|| key.clone()),
+      Prefer::Primary => first.then(|| fpr.clone()),
+    };
+    if first {
+      let named: Vec<&str> = same.iter().map(|j| keys[*j].as_str()).collect();
+      info!("{} all name the certificate {}: encrypting to it once", named.join(", "), fpr);
+    }
+    if let Some(chosen) = chosen.filter(|c| !collapsed.contains(c)) {
+      collapsed.push(chosen);
+    }
+  }
+
+  collapsed
+}
+
+fn is_pinned(key_id: &str) -> bool {
+  key_id.ends_with('!')
+}
+
+// S/MIME certificates have no subkeys, so only exact duplicates of them are ever removed
+fn primary(key_id: &str) -> Option<String> {
+  if crate::protocol::of(key_id) == KeyProtocol::Cms {
+    return None;
+  }
+
+  let mut ctx = Context::from_protocol(gpgme::Protocol::OpenPgp).ok()?;
+  let key = ctx.get_key(key_id.trim_end_matches('!')).ok()?;
+  key.fingerprint().ok().map(ToString::to_string)
+}
@@ -0,0 +1,53 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+use std::collections::HashMap;
+
+// aliases given with --define last for the one run and are never attested, so only full
+// fingerprints are accepted: there's nothing else to catch a mistyped or colliding key ID
+pub fn parse(matches: &ArgMatches) -> Result<HashMap<String, String>, i32> {
+  let mut defined = HashMap::new();
+  for definition in matches.values_of("define").into_iter().flatten() {
+    let (name, fingerprint) = match definition.split_once('=') {
+      Some((n, f)) if !n.trim().is_empty() => (n.trim(), f.trim()),
+      _ => {
+        error!("--define needs NAME=FINGERPRINT, not `{}`", definition);
+        return Err(1);
+      },
+    };
+    let fingerprint = fingerprint.trim_start_matches("0x").to_uppercase();
+    if !(fingerprint.len() == 40 || fingerprint.len() == 64) || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+      error!("--define needs a full fingerprint for `{}`, not `{}`", name, fingerprint);
+      return Err(1);
+    }
+    if defined.insert(name.to_string(), fingerprint).is_some() {
+      error!("`{}` is defined more than once", name);
+      return Err(1);
+    }
+  }
+
+  Ok(defined)
+}
+
+pub fn check(config: &Config) -> Result<(), i32> {
+  let mut clashes: Vec<&String> = config.defined.keys()
+    .filter(|n| config.aliases.contains_key(*n) || config.groups.contains_key(*n))
+    .collect();
+  if clashes.is_empty() {
+    return Ok(());
+  }
+
+  clashes.sort();
+  for name in clashes {
+    error!("`{}` is already an alias or group in the config, so it can't be defined", name);
+  }
+  Err(1)
+}
+
+// the key for a name defined for this run, warning that nothing vouches for it
+pub fn key<'a>(config: &'a Config, name: &str) -> Option<&'a str> {
+  let key = config.defined.get(name)?;
+  warn!("`{}` was defined on the command line, so its key {} is not attested", name, key);
+  Some(key)
+}
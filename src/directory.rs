@@ -0,0 +1,159 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::{progress::Progress, protocol::KeyProtocol, Config};
+
+use std::{
+  path::Path,
+  process::{Command, Stdio},
+};
+
+// compares each alias's key with what the domain's WKD publishes for the key's addresses there. a
+// mismatch means either the local key is stale or the directory has been tampered with, and only a
+// person can tell which, so nothing is changed
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  if crate::mock::is_enabled() {
+    error!("mock keys have no addresses to look up in a directory");
+    return 1;
+  }
+
+  let domain = matches.value_of("domain").expect("required clap argument").to_ascii_lowercase();
+  let reports = match crate::verify::reports(matches) {
+    Ok(r) => r,
+    Err(exit) => return exit,
+  };
+  let gpg = match crate::gpg_program() {
+    Ok(g) => g,
+    Err(exit) => return exit,
+  };
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+
+  // keys found via WKD are imported into a throwaway keyring, so the real one is never touched
+  let home = std::env::temp_dir().join(format!("gpg-alias-wkd-{}", std::process::id()));
+  if let Err(e) = crate::files::create_dir_all(&home) {
+    error!("could not create {}: {}", home.to_string_lossy(), e);
+    return 1;
+  }
+
+  let mut names: Vec<&String> = config.aliases.keys()
+    .filter(|n| config.aliases[n.as_str()].protocol() == KeyProtocol::OpenPgp)
+    .collect();
+  names.sort();
+
+  let suffix = format!("@{}", domain);
+  let mut progress = Progress::new("verify-directory", names.len(), matches.value_of("format") == Some("json"));
+  let mut checked = 0;
+  let mut problems = Vec::new();
+  for name in &names {
+    let key_id = config.aliases[name.as_str()].key();
+    let key = match ctx.get_key(key_id) {
+      Ok(k) => k,
+      Err(e) => {
+        warn!("could not find key `{}` for alias `{}`: {}", key_id, name, e);
+        progress.step(name);
+        continue;
+      },
+    };
+    let fingerprint = key.fingerprint().unwrap_or("").to_string();
+    let addresses: Vec<String> = key.user_ids()
+      .filter(|u| !u.is_revoked())
+      .filter_map(|u| u.email().ok().map(str::to_ascii_lowercase))
+      .filter(|e| e.ends_with(&suffix))
+      .collect();
+
+    for address in addresses {
+      checked += 1;
+      let published = lookup(&gpg, &home, &address);
+      if published.is_empty() {
+        warn!("{} publishes no key for {}, which alias `{}` uses", domain, address, name);
+        problems.push(format!("{}: nothing published for {}", name, address));
+      } else if !published.iter().any(|p| p.eq_ignore_ascii_case(&fingerprint)) {
+        error!("{} publishes {} for {}, but alias `{}` points to {}", domain, published.join(", "), address, name, fingerprint);
+        problems.push(format!("{}: {} publishes {} for {}, not {}", name, domain, published.join(", "), address, fingerprint));
+      }
+    }
+    progress.step(name);
+  }
+  progress.finish();
+
+  if let Err(e) = std::fs::remove_dir_all(&home) {
+    warn!("could not remove {}: {}", home.to_string_lossy(), e);
+  }
+
+  if problems.is_empty() {
+    info!("all {} address(es) at {} match the directory", checked, domain);
+  } else {
+    error!("{} of {} address(es) at {} do not match the directory", problems.len(), checked, domain);
+  }
+
+  let report = format_report(&domain, checked, &problems);
+  let subject = format!("{} mismatch(es) with the directory at {}", problems.len(), domain);
+  let delivered = crate::verify::deliver(&reports, &subject, problems.is_empty(), &report);
+
+  if !problems.is_empty() {
+    return crate::EXIT_KEY_PROBLEM;
+  }
+  if !delivered {
+    return 1;
+  }
+  0
+}
+
+// the fingerprints of the primary keys WKD returns for an address. gpg doesn't tell a missing key
+// apart from an unreachable server, so either gives none
+fn lookup(gpg: &str, home: &Path, address: &str) -> Vec<String> {
+  debug!("looking up {} via WKD", address);
+  let output = Command::new(gpg)
+    .arg("--homedir")
+    .arg(home)
+    .args(["--batch", "--no-tty", "--with-colons", "--auto-key-locate", "clear,wkd,nodefault", "--locate-external-keys"])
+    .arg(address)
+    .stderr(Stdio::null())
+    .output();
+  let output = match output {
+    Ok(o) => o,
+    Err(e) => {
+      error!("could not run gpg: {}", e);
+      return Vec::new();
+    },
+  };
+
+  // each primary key's fingerprint is on the fpr line straight after its pub line
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let mut fingerprints = Vec::new();
+  let mut after_pub = false;
+  for line in stdout.lines() {
+    if after_pub && line.starts_with("fpr:") {
+      if let Some(fpr) = line.split(':').nth(9) {
+        fingerprints.push(fpr.to_string());
+      }
+    }
+    after_pub = line.starts_with("pub:");
+  }
+
+  fingerprints
+}
+
+fn format_report(domain: &str, checked: usize, problems: &[String]) -> String {
+  let mut report = format!(
+    "gpg-alias directory report for {}\n\nchecked: {}\nmismatched: {}\n",
+    domain,
+    checked,
+    problems.len(),
+  );
+
+  if !problems.is_empty() {
+    report.push('\n');
+  }
+  for problem in problems {
+    report.push_str(&format!("MISMATCH {}\n", problem));
+  }
+
+  report
+}
@@ -0,0 +1,91 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::Config;
+
+use std::time::{Duration, SystemTime};
+
+const DAY: u64 = 24 * 60 * 60;
+
+struct Due<'a> {
+  alias: &'a str,
+  reason: &'static str,
+  when: SystemTime,
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let within = match matches.value_of("within").expect("defaulted clap argument").parse::<u64>() {
+    Ok(d) => d,
+    Err(_) => {
+      error!("--within must be a number of days");
+      return 1;
+    },
+  };
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+
+  let now = SystemTime::now();
+  let horizon = now + Duration::from_secs(within * DAY);
+
+  let mut due = Vec::new();
+  for (name, alias) in &config.aliases {
+    let key_id = alias.key();
+
+    match ctx.get_key(key_id) {
+      Ok(key) => if let Some(expires) = key.subkeys().next().and_then(|k| k.expiration_time()) {
+        due.push(Due { alias: name, reason: "key expires", when: expires });
+      },
+      Err(e) => warn!("could not find key `{}` for alias `{}`: {}", key_id, name, e),
+    }
+
+    if !config.signing.enabled {
+      continue;
+    }
+
+    let attested = match crate::attested_at(config, name, key_id) {
+      Ok(Some(t)) => t,
+      Ok(None) => {
+        due.push(Due { alias: name, reason: "not attested", when: now });
+        continue;
+      },
+      Err(exit) => return exit,
+    };
+    if let Some(days) = config.signing.max_age_days {
+      due.push(Due { alias: name, reason: "attestation exceeds max age", when: attested + Duration::from_secs(days * DAY) });
+    }
+    if let Some(days) = alias.entry().and_then(|e| e.reverify_days) {
+      due.push(Due { alias: name, reason: "manual re-verification", when: attested + Duration::from_secs(days * DAY) });
+    }
+  }
+
+  due.retain(|d| d.when <= horizon);
+  due.sort_by(|a, b| a.when.cmp(&b.when).then_with(|| a.alias.cmp(b.alias)));
+
+  if due.is_empty() {
+    info!("nothing is due within {} days", within);
+    return 0;
+  }
+
+  let alias_width = due.iter().map(|d| d.alias.len()).max().unwrap_or(0);
+  let reason_width = due.iter().map(|d| d.reason.len()).max().unwrap_or(0);
+  for d in &due {
+    println!("{:<aw$}  {:<rw$}  {}", d.alias, d.reason, relative(now, d.when), aw = alias_width, rw = reason_width);
+  }
+
+  0
+}
+
+fn relative(now: SystemTime, when: SystemTime) -> String {
+  match when.duration_since(now) {
+    Ok(d) if d.as_secs() < DAY => "today".to_string(),
+    Ok(d) => format!("in {} days", d.as_secs() / DAY),
+    Err(e) if e.duration().as_secs() < DAY => "today".to_string(),
+    Err(e) => format!("{} days overdue", e.duration().as_secs() / DAY),
+  }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+pub struct Duplicate<'a> {
+  pub name: String,
+  // as each definition spells it, which only differs between case variants
+  pub names: Vec<String>,
+  pub definitions: Vec<(usize, &'a str)>,
+}
+
+// toml only reports the first duplicate key it sees without saying where, so scan the aliases
+// table ourselves to be able to name every conflicting line and value
+pub fn find(source: &str) -> Vec<Duplicate<'_>> {
+  group(source, |name| name.to_string())
+}
+
+// names that only differ in case, such as `Alice` and `alice`. they're distinct aliases, since
+// names are matched exactly, but one of them is almost certainly a mistake
+pub fn case_variants(source: &str) -> Vec<Duplicate<'_>> {
+  group(source, str::to_lowercase).into_iter()
+    .filter(|dup| {
+      let first = &dup.names[0];
+      dup.names.iter().any(|n| n != first)
+    })
+    .collect()
+}
+
+fn group<F>(source: &str, key: F) -> Vec<Duplicate<'_>>
+  where F: Fn(&str) -> String,
+{
+  let mut seen: HashMap<String, Vec<(usize, String, &str)>> = HashMap::new();
+  let mut order = Vec::new();
+
+  for (line, name, value) in all_definitions(source) {
+    let key = key(&name);
+    let defs = seen.entry(key.clone()).or_insert_with(|| {
+      order.push(key);
+      Vec::new()
+    });
+    defs.push((line + 1, name, value));
+  }
+
+  order.into_iter()
+    .filter_map(|key| {
+      let defs = seen.remove(&key)?;
+      if defs.len() < 2 {
+        return None;
+      }
+      Some(Duplicate {
+        name: defs[0].1.clone(),
+        names: defs.iter().map(|(_, name, _)| name.clone()).collect(),
+        definitions: defs.iter().map(|(line, _, value)| (*line, *value)).collect(),
+      })
+    })
+    .collect()
+}
+
+// `alice = ...` lines in the aliases table, and `[aliases.alice]` tables, which define it as well
+fn all_definitions(source: &str) -> Vec<(usize, String, &str)> {
+  let mut defs: Vec<(usize, String, &str)> = crate::edit::definitions(source).into_iter()
+    .map(|d| (d.line, d.name, d.value))
+    .collect();
+  for (i, line) in source.lines().enumerate() {
+    let header = line.trim();
+    if !header.starts_with('[') || header.starts_with("[[") {
+      continue;
+    }
+    let header = header.trim_start_matches('[').trim_end_matches(']').trim();
+    if let Some(name) = header.strip_prefix("aliases.") {
+      defs.push((i, name.trim().trim_matches('"').to_string(), "a table"));
+    }
+  }
+  defs.sort_by_key(|(line, _, _)| *line);
+  defs
+}
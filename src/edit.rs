@@ -0,0 +1,330 @@
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+pub struct Definition<'a> {
+  pub line: usize,
+  pub name: String,
+  pub value: &'a str,
+}
+
+// a line-based view of the aliases table, so edits can leave comments and formatting alone
+pub fn definitions(source: &str) -> Vec<Definition<'_>> {
+  let mut defs = Vec::new();
+  let mut in_aliases = false;
+
+  for (i, line) in source.lines().enumerate() {
+    let line = line.trim();
+    if line.starts_with('[') {
+      in_aliases = line.trim_start_matches('[').trim_end_matches(']').trim() == "aliases";
+      continue;
+    }
+    if !in_aliases || line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let eq = match line.find('=') {
+      Some(i) => i,
+      None => continue,
+    };
+    defs.push(Definition {
+      line: i,
+      name: unquote(line[..eq].trim()),
+      value: line[eq + 1..].trim(),
+    });
+  }
+
+  defs
+}
+
+fn unquote(key: &str) -> String {
+  let quoted = key.len() >= 2
+    && ((key.starts_with('"') && key.ends_with('"')) || (key.starts_with('\'') && key.ends_with('\'')));
+  if quoted {
+    key[1..key.len() - 1].to_string()
+  } else {
+    key.to_string()
+  }
+}
+
+pub fn replace_key(path: &Path, alias: &str, old: &str, new: &str) -> Result<(), i32> {
+  let source = read(path)?;
+
+  let line = find(path, &source, alias)?;
+
+  let quoted = format!("\"{}\"", old);
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  if !lines[line].contains(&quoted) {
+    error!("could not find key `{}` for alias `{}` in {}: edit it by hand", old, alias, path.to_string_lossy());
+    return Err(1);
+  }
+  lines[line] = lines[line].replacen(&quoted, &format!("\"{}\"", new), 1);
+
+  write(path, &lines)
+}
+
+// new definitions go after the last one in the aliases table, which is added if there is none
+// any extra fields make the definition an inline table, keeping it on one line
+pub fn add_alias(path: &Path, alias: &str, key: &str, fields: &[(&str, String)]) -> Result<(), i32> {
+  let source = read(path)?;
+  let defs = definitions(&source);
+  if defs.iter().any(|d| d.name == alias) {
+    error!("alias `{}` is already defined in {}", alias, path.to_string_lossy());
+    return Err(1);
+  }
+
+  let definition = if fields.is_empty() {
+    format!("{} = {}", key_name(alias), string(key))
+  } else {
+    let fields: Vec<String> = std::iter::once(format!("key = {}", string(key)))
+      .chain(fields.iter().map(|(name, value)| format!("{} = {}", name, string(value))))
+      .collect();
+    format!("{} = {{ {} }}", key_name(alias), fields.join(", "))
+  };
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  match defs.last().map(|d| d.line).or_else(|| aliases_header(&source)) {
+    Some(line) => lines.insert(line + 1, definition),
+    None => {
+      if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+        lines.push(String::new());
+      }
+      lines.push("[aliases]".to_string());
+      lines.push(definition);
+    },
+  }
+
+  write(path, &lines)
+}
+
+pub fn remove_alias(path: &Path, alias: &str) -> Result<(), i32> {
+  let source = read(path)?;
+  let line = find(path, &source, alias)?;
+
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  lines.remove(line);
+
+  write(path, &lines)
+}
+
+pub fn rename_alias(path: &Path, old: &str, new: &str) -> Result<(), i32> {
+  let source = read(path)?;
+  let defs = definitions(&source);
+  if defs.iter().any(|d| d.name == new) {
+    error!("alias `{}` is already defined in {}", new, path.to_string_lossy());
+    return Err(1);
+  }
+  let def = match defs.into_iter().find(|d| d.name == old) {
+    Some(d) => d,
+    None => {
+      error!("could not find alias `{}` in {}: edit it by hand", old, path.to_string_lossy());
+      return Err(1);
+    },
+  };
+
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  let indent: String = lines[def.line].chars().take_while(|c| c.is_whitespace()).collect();
+  lines[def.line] = format!("{}{} = {}", indent, key_name(new), def.value);
+
+  write(path, &lines)
+}
+
+// sets one field of an alias, turning a plain definition into an inline table if it isn't one yet.
+// a field that's already set is only replaced if it still has the value the config was loaded with
+pub fn set_field(path: &Path, alias: &str, name: &str, old: Option<&str>, value: &str) -> Result<(), i32> {
+  let source = read(path)?;
+  let def = match definitions(&source).into_iter().find(|d| d.name == alias) {
+    Some(d) => d,
+    None => {
+      error!("could not find alias `{}` in {}: edit it by hand", alias, path.to_string_lossy());
+      return Err(1);
+    },
+  };
+  let field = format!("{} = {}", name, string(value));
+
+  let updated = if let Some(old) = old {
+    let current = format!("{} = {}", name, string(old));
+    def.value.find(&current).map(|_| def.value.replacen(&current, &field, 1))
+  } else if def.value.starts_with('{') {
+    def.value.rfind('}').map(|end| format!("{}, {} {}", def.value[..end].trim_end(), field, &def.value[end..]))
+  } else if def.value.starts_with('"') {
+    def.value[1..].find('"').map(|end| {
+      let (key, rest) = def.value.split_at(end + 2);
+      format!("{{ key = {}, {} }}{}", key, field, rest)
+    })
+  } else {
+    None
+  };
+  let updated = match updated {
+    Some(u) => u,
+    None => {
+      error!("could not set `{}` for alias `{}` in {}: edit it by hand", name, alias, path.to_string_lossy());
+      return Err(1);
+    },
+  };
+
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  let indent: String = lines[def.line].chars().take_while(|c| c.is_whitespace()).collect();
+  lines[def.line] = format!("{}{} = {}", indent, key_name(alias), updated);
+
+  write(path, &lines)
+}
+
+// new groups go at the end of the groups table, which is added if there is none
+pub fn add_group(path: &Path, name: &str, members: &[String]) -> Result<(), i32> {
+  let source = read(path)?;
+  let members: Vec<String> = members.iter().map(|m| string(m)).collect();
+  let definition = format!("{} = [{}]", key_name(name), members.join(", "));
+
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  let header = lines.iter().position(|l| is_header(l, "groups"));
+  match header {
+    Some(header) => {
+      // after the table's last definition, leaving any blank lines and comments before the next
+      let end = lines[header + 1..].iter().position(|l| l.trim().starts_with('[')).map_or(lines.len(), |i| header + 1 + i);
+      let last = lines[header..end].iter().rposition(|l| !l.trim().is_empty() && !l.trim().starts_with('#')).map_or(header, |i| header + i);
+      lines.insert(last + 1, definition);
+    },
+    None => {
+      if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+        lines.push(String::new());
+      }
+      lines.push("[groups]".to_string());
+      lines.push(definition);
+    },
+  }
+
+  write(path, &lines)
+}
+
+// replaces a group's members, keeping its definition where it is
+pub fn set_group(path: &Path, name: &str, members: &[String]) -> Result<(), i32> {
+  let source = read(path)?;
+  let line = find_group(path, &source, name)?;
+
+  let members: Vec<String> = members.iter().map(|m| string(m)).collect();
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  let indent: String = lines[line].chars().take_while(|c| c.is_whitespace()).collect();
+  lines[line] = format!("{}{} = [{}]", indent, key_name(name), members.join(", "));
+
+  write(path, &lines)
+}
+
+pub fn remove_group(path: &Path, name: &str) -> Result<(), i32> {
+  let source = read(path)?;
+  let line = find_group(path, &source, name)?;
+
+  let mut lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+  lines.remove(line);
+
+  write(path, &lines)
+}
+
+// only a definition on a single line can be edited without parsing the whole array
+fn find_group(path: &Path, source: &str, name: &str) -> Result<usize, i32> {
+  let mut in_groups = false;
+  for (i, line) in source.lines().enumerate() {
+    let line = line.trim();
+    if line.starts_with('[') {
+      in_groups = is_header(line, "groups");
+      continue;
+    }
+    if !in_groups || line.starts_with('#') {
+      continue;
+    }
+    let (key, value) = match line.split_once('=') {
+      Some((key, value)) => (unquote(key.trim()), value.trim()),
+      None => continue,
+    };
+    if key != name {
+      continue;
+    }
+    if !value.starts_with('[') || !value.split('#').next().unwrap_or("").trim_end().ends_with(']') {
+      error!("group `{}` spans several lines in {}: edit it by hand", name, path.to_string_lossy());
+      return Err(1);
+    }
+    return Ok(i);
+  }
+
+  error!("could not find group `{}` in {}: edit it by hand", name, path.to_string_lossy());
+  Err(1)
+}
+
+fn is_header(line: &str, table: &str) -> bool {
+  let line = line.trim();
+  line.starts_with('[') && line.trim_start_matches('[').trim_end_matches(']').trim() == table
+}
+
+fn find(path: &Path, source: &str, alias: &str) -> Result<usize, i32> {
+  match definitions(source).into_iter().find(|d| d.name == alias) {
+    Some(d) => Ok(d.line),
+    None => {
+      error!("could not find alias `{}` in {}: edit it by hand", alias, path.to_string_lossy());
+      Err(1)
+    },
+  }
+}
+
+fn aliases_header(source: &str) -> Option<usize> {
+  source.lines().position(|l| is_header(l, "aliases"))
+}
+
+// bare keys where toml allows them, quoted otherwise
+fn key_name(alias: &str) -> String {
+  let bare = !alias.is_empty() && alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if bare {
+    alias.to_string()
+  } else {
+    string(alias)
+  }
+}
+
+fn string(value: &str) -> String {
+  format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// whatever has to follow any edit gpg-alias makes to the config
+pub fn finish(config: &crate::Config) -> Result<(), i32> {
+  crate::generation::bump(config);
+  crate::seal::refresh(config)
+}
+
+fn read(path: &Path) -> Result<String, i32> {
+  match std::fs::read_to_string(path) {
+    Ok(s) => Ok(s),
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      Err(1)
+    },
+  }
+}
+
+pub fn temp_path(path: &Path) -> PathBuf {
+  path.with_extension("toml.tmp")
+}
+
+// write to a temporary file and rename it over the config, so a failure never leaves it truncated
+fn write(path: &Path, lines: &[String]) -> Result<(), i32> {
+  let tmp = temp_path(path);
+
+  let mut file = match crate::files::create_file(&tmp) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not create {}: {}", tmp.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let mut contents = lines.join("\n");
+  contents.push('\n');
+  if let Err(e) = file.write_all(contents.as_bytes()).and_then(|_| file.sync_all()) {
+    error!("could not write {}: {}", tmp.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  if let Err(e) = std::fs::rename(&tmp, path) {
+    error!("could not replace {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  Ok(())
+}
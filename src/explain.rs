@@ -0,0 +1,157 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::{Alias, Config, Method};
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("alias").expect("required clap argument");
+
+  println!("alias:       {}", name);
+  match &config.system.path {
+    Some(path) if config.system.aliases.contains(name) => println!("config:      {} (system)", path.to_string_lossy()),
+    _ => println!("config:      {}", config.path.to_string_lossy()),
+  }
+
+  let alias = match config.aliases.get(name) {
+    Some(a) => a,
+    None => {
+      println!("entry:       not found");
+      return 1;
+    },
+  };
+
+  match alias {
+    Alias::Key(k) => println!("entry:       key `{}`", k),
+    Alias::Entry(e) => {
+      println!("entry:       key `{}` (table)", e.key);
+      if let Some(ks) = &e.keyserver {
+        println!("keyserver:   {}", ks);
+      }
+      if let Some(wkd) = &e.wkd {
+        println!("wkd:         {}", wkd);
+      }
+      if let Some(comment) = &e.comment {
+        println!("comment:     {}", comment);
+      }
+      if !e.tags.is_empty() {
+        println!("tags:        {}", e.tags.join(", "));
+      }
+      if let Some(expires) = &e.expires {
+        let state = if crate::metadata::is_expired(expires) { "expired" } else { "not yet expired" };
+        println!("expires:     {} ({})", expires, state);
+      }
+    },
+  }
+
+  let mut ok = explain_attestation(config, name, alias.key());
+  ok &= explain_key(config, alias.key());
+
+  if ok {
+    0
+  } else {
+    1
+  }
+}
+
+fn explain_attestation(config: &Config, name: &str, key_id: &str) -> bool {
+  if !config.signing.enabled {
+    println!("attestation: none (signing disabled)");
+    return true;
+  }
+
+  if config.system.aliases.contains(name) {
+    let sig_path = crate::system_signature_path(name);
+    return match crate::verify_attestation(config, name, key_id) {
+      Ok(_) => {
+        println!("attestation: {} (valid, signed by system key)", sig_path.to_string_lossy());
+        true
+      },
+      Err(_) => {
+        println!("attestation: {} (missing or invalid)", sig_path.to_string_lossy());
+        false
+      },
+    };
+  }
+
+  if config.signing.method == Method::Lsign {
+    return match crate::verify_attestation(config, name, key_id) {
+      Ok(_) => {
+        println!("attestation: local certification by `{}`", config.signing.key);
+        true
+      },
+      Err(_) => {
+        println!("attestation: no local certification by `{}`", config.signing.key);
+        false
+      },
+    };
+  }
+
+  if config.signing.method == Method::Hmac {
+    let path = match crate::mac::attestation_path(config, name) {
+      Ok(p) => p,
+      Err(_) => return false,
+    };
+    return match crate::verify_attestation(config, name, key_id) {
+      Ok(_) => {
+        println!("attestation: {} (valid hmac)", path.to_string_lossy());
+        true
+      },
+      Err(_) => {
+        println!("attestation: {} (missing or invalid)", path.to_string_lossy());
+        false
+      },
+    };
+  }
+
+  let sig_path = match crate::signature_path(config, name) {
+    Ok(p) => p,
+    Err(_) => return false,
+  };
+  if !sig_path.exists() {
+    println!("attestation: missing ({})", sig_path.to_string_lossy());
+    return false;
+  }
+
+  match crate::check_existing_signature(&config.signing.key, key_id, crate::metadata::expires(config, name), crate::required_notation(config, name), sig_path.clone()) {
+    Ok(_) => {
+      println!(
+        "attestation: {} (valid, signed by `{}`)",
+        sig_path.to_string_lossy(),
+        config.signing.key,
+      );
+      true
+    },
+    Err(_) => {
+      println!("attestation: {} (invalid)", sig_path.to_string_lossy());
+      false
+    },
+  }
+}
+
+fn explain_key(config: &Config, key_id: &str) -> bool {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return false;
+    },
+  };
+
+  let key = match ctx.get_key(key_id) {
+    Ok(k) => k,
+    Err(e) => {
+      println!("key:         not found in local OpenPGP keyring ({})", e);
+      return false;
+    },
+  };
+
+  println!("key:         {} (local OpenPGP keyring)", key.fingerprint().unwrap_or("?"));
+  for uid in key.user_ids() {
+    println!("uid:         {}", uid.id().unwrap_or("?"));
+  }
+  for weakness in crate::strength::weaknesses(&key, config.validity.min_rsa_bits) {
+    println!("weak:        {}", weakness);
+  }
+
+  true
+}
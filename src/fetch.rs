@@ -0,0 +1,314 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+use serde_derive::Deserialize;
+
+use crate::{progress::Progress, Alias, Config};
+
+use std::{
+  process::Command,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc,
+    Mutex,
+  },
+  time::{Duration, Instant},
+};
+
+const DEFAULT_JOBS: usize = 4;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+// how often a running gpg is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// set by the SIGINT handler while fetching many keys
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Fetch {
+  // fetch an alias's key when it's missing from the keyring, as --fetch does
+  #[serde(default)]
+  pub missing: bool,
+  // how many keys are fetched at once
+  pub jobs: Option<usize>,
+  // how long a single gpg lookup may take before it is stopped
+  pub timeout_secs: Option<u64>,
+}
+
+pub fn timeout(config: &Config) -> Duration {
+  Duration::from_secs(config.fetch.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  if crate::mock::is_enabled() {
+    error!("fetching imports keys into the real keyring, so it can't be simulated");
+    return 1;
+  }
+
+  let names: Vec<&str> = match matches.values_of("alias") {
+    Some(v) => v.collect(),
+    None => {
+      let mut names: Vec<&str> = config.aliases.keys().map(String::as_str).collect();
+      names.sort();
+      names
+    },
+  };
+
+  let gpg = match crate::gpg_program() {
+    Ok(g) => g,
+    Err(exit) => return exit,
+  };
+
+  let mut failed = 0;
+  let mut aliases = Vec::new();
+  for name in names {
+    match config.aliases.get(name) {
+      Some(alias) => aliases.push((name, alias)),
+      None => {
+        error!("no such alias found: `{}`", name);
+        failed += 1;
+      },
+    }
+  }
+
+  let jobs = config.fetch.jobs.unwrap_or(DEFAULT_JOBS).max(1);
+  let timeout = timeout(config);
+  let progress = Progress::new("fetch", aliases.len(), matches.value_of("format") == Some("json"));
+  let (fetched, skipped) = fetch_all(&gpg, &aliases, jobs, timeout, progress);
+  failed += aliases.len() - fetched - skipped;
+
+  if skipped > 0 {
+    error!("interrupted: {} key(s) were not fetched", skipped);
+  }
+  if failed > 0 {
+    error!("could not fetch {} key(s)", failed);
+  }
+  if skipped > 0 || failed > 0 {
+    return 1;
+  }
+
+  0
+}
+
+// each lookup is a gpg process of its own, so they run side by side on threads, at most `jobs` at a
+// time, without an async runtime: gpg does the network I/O, and this only waits for it. on Ctrl-C no more are started, and the running ones, which get the same SIGINT from the
+// terminal, are waited for: gpg imports a key whole or not at all, so nothing is left half done.
+// returns how many keys were fetched and how many were never tried
+fn fetch_all(gpg: &str, aliases: &[(&str, &Alias)], jobs: usize, timeout: Duration, mut progress: Progress) -> (usize, usize) {
+  INTERRUPTED.store(false, Ordering::SeqCst);
+  let previous = on_interrupt();
+
+  let queue = Mutex::new(aliases.iter());
+  let (done, results) = mpsc::channel();
+  let mut fetched = 0;
+  let mut tried = 0;
+  std::thread::scope(|scope| {
+    for _ in 0..jobs.min(aliases.len()) {
+      let (queue, done) = (&queue, done.clone());
+      scope.spawn(move || loop {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+          break;
+        }
+        let next = match queue.lock() {
+          Ok(mut q) => q.next(),
+          Err(_) => None,
+        };
+        let (name, alias) = match next {
+          Some(n) => n,
+          None => break,
+        };
+        let ok = fetch(gpg, name, alias, timeout).is_ok();
+        if done.send((*name, ok)).is_err() {
+          break;
+        }
+      });
+    }
+    drop(done);
+
+    for (name, ok) in results {
+      tried += 1;
+      if ok {
+        fetched += 1;
+      }
+      progress.step(name);
+    }
+  });
+  progress.finish();
+
+  restore_interrupt(previous);
+  (fetched, aliases.len() - tried)
+}
+
+#[cfg(unix)]
+extern "C" fn interrupted(_: libc::c_int) {
+  INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn on_interrupt() -> libc::sighandler_t {
+  unsafe { libc::signal(libc::SIGINT, interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t) }
+}
+
+#[cfg(unix)]
+fn restore_interrupt(previous: libc::sighandler_t) {
+  unsafe {
+    libc::signal(libc::SIGINT, previous);
+  }
+}
+
+// Ctrl-C ends the process as usual elsewhere
+#[cfg(not(unix))]
+fn on_interrupt() {}
+
+#[cfg(not(unix))]
+fn restore_interrupt(_: ()) {}
+
+pub fn fetch(gpg: &str, name: &str, alias: &Alias, timeout: Duration) -> Result<(), i32> {
+  if alias.protocol() == crate::protocol::KeyProtocol::Cms {
+    error!("alias `{}` uses S/MIME, whose certificates can't be fetched from a keyserver or WKD", name);
+    return Err(1);
+  }
+
+  let entry = alias.entry();
+  let keyserver = entry.and_then(|e| e.keyserver.as_ref());
+  let wkd = entry.and_then(|e| e.wkd.as_ref());
+
+  if let Some(domain) = wkd {
+    let addresses = wkd_addresses(name, alias.key(), domain)?;
+    info!("fetching key for `{}` via WKD at {}", name, domain);
+    let ok = gpg_succeeded(Command::new(gpg)
+      .arg("--auto-key-locate")
+      .arg("clear,wkd")
+      .arg("--locate-external-keys")
+      .args(&addresses), timeout);
+    if ok {
+      return Ok(());
+    }
+    if keyserver.is_none() {
+      error!("could not fetch key for `{}` via WKD", name);
+      return Err(1);
+    }
+    warn!("could not fetch key for `{}` via WKD, falling back to keyserver", name);
+  }
+
+  let mut cmd = Command::new(gpg);
+  match keyserver {
+    Some(ks) => {
+      info!("fetching key for `{}` from {}", name, ks);
+      cmd.arg("--keyserver").arg(ks);
+    },
+    None => info!("fetching key for `{}` from the default keyserver", name),
+  }
+  cmd.arg("--recv-keys").arg(alias.key());
+
+  if !gpg_succeeded(&mut cmd, timeout) {
+    error!("could not fetch key for `{}`", name);
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+// fetches an alias's key if it isn't in the keyring yet, then shows what arrived and asks before it
+// is used. a declined key stays in the keyring, but the alias isn't resolved to it
+pub fn missing(config: &Config, name: &str) -> Result<(), i32> {
+  let alias = match config.aliases.get(name) {
+    Some(a) => a,
+    None => return Ok(()),
+  };
+  // mock keys never exist in the keyring
+  if crate::mock::is_enabled() {
+    return Ok(());
+  }
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  if ctx.get_key(alias.key()).is_ok() {
+    return Ok(());
+  }
+
+  info!("the key for alias `{}` is not in the keyring", name);
+  let gpg = crate::gpg_program()?;
+  fetch(&gpg, name, alias, timeout(config))?;
+
+  let key = match ctx.get_key(alias.key()) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("key `{}` for alias `{}` is still not in the keyring after fetching it: {}", alias.key(), name, e);
+      return Err(1);
+    },
+  };
+  let fingerprint = key.fingerprint().unwrap_or("").to_string();
+  warn!("fetched key {} for alias `{}`", fingerprint, name);
+  for uid in key.user_ids() {
+    warn!("  {}", uid.id().unwrap_or(""));
+  }
+
+  let args = [("alias", name), ("id", alias.key()), ("fingerprint", fingerprint.as_str())];
+  if !crate::confirm_batch(config, "confirm_fetched", &args)? {
+    error!("the key fetched for alias `{}` was not accepted", name);
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+// a keyserver that never answers would otherwise hold everything up, so gpg is stopped after the
+// timeout. it keeps its keyring consistent when killed, like when it's interrupted
+fn gpg_succeeded(cmd: &mut Command, timeout: Duration) -> bool {
+  let mut child = match cmd.spawn() {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not run gpg: {}", e);
+      return false;
+    },
+  };
+
+  let deadline = Instant::now() + timeout;
+  loop {
+    match child.try_wait() {
+      Ok(Some(status)) => return status.success(),
+      Ok(None) if Instant::now() >= deadline => {
+        error!("gpg took longer than {} second(s), so it was stopped", timeout.as_secs());
+        let _ = child.kill();
+        let _ = child.wait();
+        return false;
+      },
+      Ok(None) => std::thread::sleep(POLL_INTERVAL),
+      Err(e) => {
+        error!("could not wait for gpg: {}", e);
+        return false;
+      },
+    }
+  }
+}
+
+// WKD is keyed on e-mail addresses, so use the addresses at the domain on the key we already have,
+// or the alias itself if it looks like an address at that domain
+fn wkd_addresses(name: &str, key_id: &str, domain: &str) -> Result<Vec<String>, i32> {
+  let suffix = format!("@{}", domain.to_ascii_lowercase());
+
+  let mut addresses = Vec::new();
+  if let Ok(mut ctx) = Context::from_protocol(Protocol::OpenPgp) {
+    if let Ok(key) = ctx.get_key(key_id) {
+      addresses.extend(key.user_ids()
+        .filter_map(|u| u.email().ok())
+        .filter(|e| e.to_ascii_lowercase().ends_with(&suffix))
+        .map(ToString::to_string));
+    }
+  }
+
+  if addresses.is_empty() && name.to_ascii_lowercase().ends_with(&suffix) {
+    addresses.push(name.to_string());
+  }
+
+  if addresses.is_empty() {
+    error!("no address at {} known for `{}`, so it cannot be looked up via WKD", domain, name);
+    return Err(1);
+  }
+
+  Ok(addresses)
+}
@@ -0,0 +1,171 @@
+use std::{
+  fs::{DirBuilder, File, OpenOptions},
+  io,
+  path::Path,
+};
+
+const DIR_MODE: u32 = 0o700;
+const FILE_MODE: u32 = 0o600;
+
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+  if path.is_dir() {
+    return Ok(());
+  }
+
+  let mut builder = DirBuilder::new();
+  builder.recursive(true);
+  with_dir_mode(&mut builder);
+  builder.create(path)?;
+  set_mode(path, DIR_MODE)
+}
+
+// a directory that must not exist yet, so one made beforehand by someone else is never used
+pub fn create_new_dir(path: &Path) -> io::Result<()> {
+  let mut builder = DirBuilder::new();
+  with_dir_mode(&mut builder);
+  builder.create(path)?;
+  set_mode(path, DIR_MODE)
+}
+
+// never readable by others, even for a moment: the mode given at creation can only be narrowed by
+// the umask, and is then set exactly. a file that already existed keeps its mode until then
+pub fn create_file(path: &Path) -> io::Result<File> {
+  let mut options = OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  with_file_mode(&mut options);
+  let file = options.open(path)?;
+  set_mode(path, FILE_MODE)?;
+  Ok(file)
+}
+
+pub fn make_private(path: &Path) -> io::Result<()> {
+  set_mode(path, if path.is_dir() { DIR_MODE } else { FILE_MODE })
+}
+
+#[cfg(unix)]
+pub fn is_private(path: &Path) -> io::Result<bool> {
+  use std::os::unix::fs::PermissionsExt;
+
+  Ok(std::fs::metadata(path)?.permissions().mode() & 0o077 == 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_private(_: &Path) -> io::Result<bool> {
+  Ok(true)
+}
+
+#[cfg(unix)]
+fn with_dir_mode(builder: &mut DirBuilder) {
+  use std::os::unix::fs::DirBuilderExt;
+
+  builder.mode(DIR_MODE);
+}
+
+#[cfg(unix)]
+fn with_file_mode(options: &mut OpenOptions) {
+  use std::os::unix::fs::OpenOptionsExt;
+
+  options.mode(FILE_MODE);
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+
+  // set explicitly after creation, since modes given at creation time are masked by the umask
+  std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn with_dir_mode(_: &mut DirBuilder) {}
+
+#[cfg(not(unix))]
+fn with_file_mode(_: &mut OpenOptions) {}
+
+#[cfg(not(unix))]
+fn set_mode(_: &Path, _: u32) -> io::Result<()> {
+  Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  use std::{
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+  };
+
+  // tests run in parallel, so each gets an empty directory under a random name, which creating it
+  // checks no one else has
+  struct Scratch(PathBuf);
+
+  impl Scratch {
+    fn new(label: &str) -> Scratch {
+      let mut random = [0; 8];
+      getrandom::getrandom(&mut random).unwrap();
+      let path = std::env::temp_dir().join(format!("gpg-alias-files-{}-{}", label, hex::encode(random)));
+      std::fs::create_dir(&path).unwrap();
+      Scratch(path)
+    }
+  }
+
+  impl Drop for Scratch {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.0);
+    }
+  }
+
+  fn mode(path: &Path) -> u32 {
+    std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+  }
+
+  #[test]
+  fn new_files_are_private() {
+    let scratch = Scratch::new("new-file");
+    let path = scratch.0.join("hmac.key");
+    create_file(&path).unwrap();
+    assert_eq!(mode(&path), FILE_MODE);
+  }
+
+  #[test]
+  fn existing_files_are_made_private() {
+    let scratch = Scratch::new("existing-file");
+    let path = scratch.0.join("alice.asc");
+    std::fs::write(&path, "old").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    create_file(&path).unwrap();
+    assert_eq!(mode(&path), FILE_MODE);
+    assert_eq!(std::fs::read(&path).unwrap(), b"");
+  }
+
+  #[test]
+  fn new_directories_are_private() {
+    let scratch = Scratch::new("new-dir");
+    let parent = scratch.0.join("gpg-alias");
+    let path = parent.join("profiles");
+    create_dir_all(&path).unwrap();
+    assert_eq!(mode(&path), DIR_MODE);
+    assert_eq!(mode(&parent), DIR_MODE);
+  }
+
+  #[test]
+  fn new_dirs_must_not_exist() {
+    let scratch = Scratch::new("new-dir-exists");
+    let path = scratch.0.join("dir");
+    create_new_dir(&path).unwrap();
+    assert_eq!(mode(&path), DIR_MODE);
+    assert!(create_new_dir(&path).is_err());
+  }
+
+  #[test]
+  fn make_private_narrows_modes() {
+    let scratch = Scratch::new("make-private");
+    let path = scratch.0.join("gpg-alias.toml");
+    std::fs::write(&path, "").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o666)).unwrap();
+    make_private(&path).unwrap();
+    assert_eq!(mode(&path), FILE_MODE);
+    assert!(is_private(&path).unwrap());
+  }
+}
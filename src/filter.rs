@@ -0,0 +1,118 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+use std::{
+  collections::HashMap,
+  io::{Read, Write},
+};
+
+// replaces `@alias` (or `@group`) in stdin with the full fingerprints it verifies to. stdin is the
+// text being filtered, so nothing can be asked: aliases have to be attested already. `@@` is a
+// literal `@`, and an `@` inside a word, like in an e-mail address, is left alone
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let mut input = String::new();
+  if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+    error!("could not read stdin: {}", e);
+    return 1;
+  }
+
+  // written only once everything has been replaced, so a failure never leaves half a file
+  let output = match substitute(config, &input, matches.is_present("strict")) {
+    Ok(o) => o,
+    Err(exit) => return exit,
+  };
+  let mut stdout = std::io::stdout();
+  if let Err(e) = stdout.write_all(output.as_bytes()).and_then(|_| stdout.flush()) {
+    error!("could not write to stdout: {}", e);
+    return 1;
+  }
+
+  0
+}
+
+fn substitute(config: &Config, input: &str, strict: bool) -> Result<String, i32> {
+  let mut output = String::with_capacity(input.len());
+  let mut resolved: HashMap<&str, String> = HashMap::new();
+  let mut rest = input;
+
+  while let Some(at) = rest.find('@') {
+    let (before, after) = rest.split_at(at);
+    output.push_str(before);
+    let after = &after[1..];
+
+    if let Some(stripped) = after.strip_prefix('@') {
+      output.push('@');
+      rest = stripped;
+      continue;
+    }
+    let in_word = output.chars().last().is_some_and(is_name_char);
+    let len = name_len(after);
+    if in_word || len == 0 {
+      output.push('@');
+      rest = after;
+      continue;
+    }
+
+    let name = &after[..len];
+    rest = &after[len..];
+    if !resolved.contains_key(name) {
+      match fingerprints(config, name)? {
+        Some(f) => {
+          resolved.insert(name, f);
+        },
+        None if strict => {
+          error!("`@{}` is not an alias or group", name);
+          return Err(crate::EXIT_UNKNOWN_ALIAS);
+        },
+        None => {
+          output.push('@');
+          output.push_str(name);
+          continue;
+        },
+      }
+    }
+    output.push_str(&resolved[name]);
+  }
+  output.push_str(rest);
+
+  Ok(output)
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_alphanumeric() || "._:-".contains(c)
+}
+
+// names end before any trailing punctuation, so `@alice.` at the end of a sentence is `alice`
+fn name_len(s: &str) -> usize {
+  let len = s.find(|c| !is_name_char(c)).unwrap_or(s.len());
+  s[..len].trim_end_matches(|c| "._:-".contains(c)).len()
+}
+
+// groups become their members' fingerprints, separated by spaces
+fn fingerprints(config: &Config, name: &str) -> Result<Option<String>, i32> {
+  let keys = if config.groups.contains_key(name) {
+    crate::groups::expand(config, name, true).map_err(|_| crate::EXIT_UNVERIFIED)?
+  } else if config.aliases.contains_key(name) {
+    let key_id = crate::resolve_verified(config, name).map_err(|_| crate::EXIT_UNVERIFIED)?;
+    vec![key_id.to_string()]
+  } else {
+    return Ok(None);
+  };
+
+  let mut fingerprints = Vec::new();
+  for key_id in keys {
+    if crate::validity::check(config, name, &key_id).is_err() {
+      return Err(crate::EXIT_KEY_PROBLEM);
+    }
+    match crate::pin::fingerprint(&key_id) {
+      Some(f) => fingerprints.push(f),
+      None => {
+        error!("could not find key `{}` for `{}` in the keyring", key_id, name);
+        return Err(crate::EXIT_KEY_PROBLEM);
+      },
+    }
+  }
+
+  Ok(Some(fingerprints.join(" ")))
+}
@@ -0,0 +1,71 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::Config;
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let email = matches.value_of("email").expect("required clap argument");
+
+  // one context per protocol, each made when an alias first needs it, so gpgsm is only required
+  // if an S/MIME alias is configured
+  let mut contexts: Vec<(Protocol, Context)> = Vec::new();
+
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+
+  let mut matched: Vec<(&str, String)> = Vec::new();
+  for name in names {
+    let key_id = config.aliases[name].key();
+    let protocol = crate::protocol::gpgme(key_id);
+    let ctx = match contexts.iter().position(|(p, _)| *p == protocol) {
+      Some(i) => &mut contexts[i].1,
+      None => match Context::from_protocol(protocol) {
+        Ok(c) => {
+          contexts.push((protocol, c));
+          &mut contexts.last_mut().expect("just pushed").1
+        },
+        Err(e) => {
+          error!("could not create gpgme context: {}", e);
+          return 1;
+        },
+      },
+    };
+    let key = match ctx.get_key(key_id) {
+      Ok(k) => k,
+      Err(_) => continue,
+    };
+
+    let has_email = key.user_ids()
+      .filter_map(|u| u.email().ok())
+      .any(|e| e.eq_ignore_ascii_case(email));
+    if !has_email {
+      continue;
+    }
+
+    match key.fingerprint() {
+      Ok(f) => matched.push((name.as_str(), f.to_string())),
+      Err(_) => warn!("key for alias `{}` has an invalid fingerprint", name),
+    }
+  }
+
+  let (alias, fingerprint) = match matched.first() {
+    Some(m) => m,
+    None => {
+      error!("no alias has a key with a user ID for {}", email);
+      return 1;
+    },
+  };
+
+  if matched.iter().any(|(_, f)| f != fingerprint) {
+    let names: Vec<&str> = matched.iter().map(|(n, _)| *n).collect();
+    error!("aliases with different keys match {}: {}", email, names.join(", "));
+    return 1;
+  }
+
+  if let Err(exit) = crate::resolve_verified(config, alias) {
+    return exit;
+  }
+
+  println!("{}", fingerprint);
+  0
+}
@@ -0,0 +1,76 @@
+use crate::{Config, Method};
+
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Mutex,
+};
+
+// each key is refreshed at most once a run, however many groups name it
+static REFRESHED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static SIGNING_KEY_CHECKED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_required(config: &Config, alias: &str) -> bool {
+  config.fresh || config.aliases.get(alias)
+    .and_then(|a| a.entry())
+    .is_some_and(|e| e.require_fresh)
+}
+
+// for high-stakes uses nothing is taken from earlier runs: the key is refreshed from where it's
+// published, so a revocation since shows up, and the key that made the attestations has to be
+// valid right now too
+pub fn verify(config: &Config, alias: &str) -> Result<(), i32> {
+  if !is_required(config, alias) {
+    return Ok(());
+  }
+  // mock keys aren't published anywhere
+  if crate::mock::is_enabled() {
+    debug!("not refreshing the mock key for `{}`", alias);
+    return Ok(());
+  }
+
+  refresh(config, alias)?;
+  check_signing_key(config)
+}
+
+fn refresh(config: &Config, alias: &str) -> Result<(), i32> {
+  let entry = &config.aliases[alias];
+  if entry.protocol() == crate::protocol::KeyProtocol::Cms {
+    warn!("alias `{}` uses S/MIME, whose certificates can't be refreshed: only its local status is checked", alias);
+    return Ok(());
+  }
+  if let Ok(refreshed) = REFRESHED.lock() {
+    if refreshed.iter().any(|k| k == entry.key()) {
+      return Ok(());
+    }
+  }
+
+  info!("refreshing the key for `{}`, since it has to be verified afresh", alias);
+  let gpg = crate::gpg_program()?;
+  if crate::fetch::fetch(&gpg, alias, entry, crate::fetch::timeout(config)).is_err() {
+    error!("could not refresh the key for `{}`, so its current status is unknown", alias);
+    return Err(1);
+  }
+
+  if let Ok(mut refreshed) = REFRESHED.lock() {
+    refreshed.push(entry.key().to_string());
+  }
+  Ok(())
+}
+
+// the hmac method has no signing key to check
+fn check_signing_key(config: &Config) -> Result<(), i32> {
+  if !config.signing.enabled || config.signing.method == Method::Hmac || SIGNING_KEY_CHECKED.load(Ordering::Relaxed) {
+    return Ok(());
+  }
+
+  let problems = crate::validity::problems(&config.signing.key, None)?;
+  for problem in &problems {
+    error!("signing key `{}` {}, so attestations it made can't be trusted afresh", config.signing.key, problem);
+  }
+  if !problems.is_empty() {
+    return Err(1);
+  }
+
+  SIGNING_KEY_CHECKED.store(true, Ordering::Relaxed);
+  Ok(())
+}
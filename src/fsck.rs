@@ -0,0 +1,234 @@
+use crate::{Config, Method};
+
+use std::path::{Path, PathBuf};
+
+enum Fix {
+  Remove(PathBuf),
+  MakePrivate(PathBuf),
+}
+
+struct Problem {
+  description: String,
+  fix: Option<Fix>,
+}
+
+pub fn run(config: &Config) -> i32 {
+  let mut problems = Vec::new();
+
+  check_private(&config.path, &mut problems);
+  let tmp = crate::edit::temp_path(&config.path);
+  if tmp.exists() {
+    problems.push(Problem {
+      description: format!("{} was left behind by an interrupted config edit", tmp.to_string_lossy()),
+      fix: Some(Fix::Remove(tmp)),
+    });
+  }
+
+  if config.data_dir.is_dir() {
+    check_private(&config.data_dir, &mut problems);
+    if check_data_dir(config, &mut problems).is_err() {
+      return 1;
+    }
+  }
+
+  if problems.is_empty() {
+    info!("no problems found");
+    return 0;
+  }
+
+  let mut remaining = 0;
+  for problem in problems {
+    warn!("{}", problem.description);
+    let fix = match problem.fix {
+      Some(f) if !config.no_write => f,
+      _ => {
+        remaining += 1;
+        continue;
+      },
+    };
+
+    let (res, done, path) = match &fix {
+      Fix::Remove(path) => (std::fs::remove_file(path), "removed", path),
+      Fix::MakePrivate(path) => (crate::files::make_private(path), "restricted permissions on", path),
+    };
+    match res {
+      Ok(()) => info!("{} {}", done, path.to_string_lossy()),
+      Err(e) => {
+        error!("could not repair {}: {}", path.to_string_lossy(), e);
+        remaining += 1;
+      },
+    }
+  }
+
+  if remaining > 0 {
+    error!("{} problem(s) need attention", remaining);
+    return 1;
+  }
+
+  0
+}
+
+fn check_private(path: &Path, problems: &mut Vec<Problem>) {
+  match crate::files::is_private(path) {
+    Ok(true) => {},
+    Ok(false) => problems.push(Problem {
+      description: format!("{} can be read by other users", path.to_string_lossy()),
+      fix: Some(Fix::MakePrivate(path.to_path_buf())),
+    }),
+    Err(e) => problems.push(Problem {
+      description: format!("could not read the permissions of {}: {}", path.to_string_lossy(), e),
+      fix: None,
+    }),
+  }
+}
+
+fn check_data_dir(config: &Config, problems: &mut Vec<Problem>) -> Result<(), ()> {
+  let entries = match std::fs::read_dir(&config.data_dir) {
+    Ok(e) => e,
+    Err(e) => {
+      error!("could not read {}: {}", config.data_dir.to_string_lossy(), e);
+      return Err(());
+    },
+  };
+
+  let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+  paths.sort();
+
+  for path in paths {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+    if file_name == crate::prompt_status::CACHE_FILE {
+      // a cache is always safe to throw away, so don't bother checking it closely
+      let valid = std::fs::read_to_string(&path)
+        .map(|s| s.is_empty() || (s.starts_with("gpg-alias:") && s.ends_with('!')))
+        .unwrap_or(false);
+      if !valid {
+        problems.push(Problem {
+          description: format!("{} is not a valid prompt-status cache", path.to_string_lossy()),
+          fix: Some(Fix::Remove(path)),
+        });
+      }
+      continue;
+    }
+
+    if file_name == crate::cache::CACHE_FILE {
+      if !crate::cache::is_valid(&path) {
+        problems.push(Problem {
+          description: format!("{} is not a valid resolution cache", path.to_string_lossy()),
+          fix: Some(Fix::Remove(path)),
+        });
+      }
+      continue;
+    }
+    if file_name == crate::cache::LOCK_FILE {
+      continue;
+    }
+
+    if file_name == crate::generation::GENERATION_FILE {
+      let valid = std::fs::read_to_string(&path).map(|s| crate::generation::is_valid(&s)).unwrap_or(false);
+      if !valid {
+        problems.push(Problem {
+          description: format!("{} is not a valid generation counter", path.to_string_lossy()),
+          fix: Some(Fix::Remove(path)),
+        });
+      }
+      continue;
+    }
+
+    if file_name == crate::key_changes::STATE_FILE {
+      // only used to point out changes, so losing it costs nothing but one run's worth of warnings
+      if !crate::key_changes::is_valid(&path) {
+        problems.push(Problem {
+          description: format!("{} is not a valid keyring state record", path.to_string_lossy()),
+          fix: Some(Fix::Remove(path)),
+        });
+      }
+      continue;
+    }
+
+    if file_name == crate::adopt::STATE_FILE {
+      // unlike a cache, this is what detects rollbacks, so it isn't thrown away
+      if !crate::adopt::is_valid(&path) {
+        problems.push(Problem {
+          description: format!("{} is not a valid record of adopted bundles: adopting will fail until it's fixed or removed", path.to_string_lossy()),
+          fix: None,
+        });
+      }
+      check_private(&path, problems);
+      continue;
+    }
+
+    if file_name == crate::retire::ARCHIVE_DIR && path.is_dir() {
+      continue;
+    }
+
+    if file_name == crate::audit::LOG_FILE || file_name == crate::mac::SECRET_FILE || file_name == crate::seal::MARKER_FILE {
+      check_private(&path, problems);
+      continue;
+    }
+
+    let (alias, method) = match (file_name.strip_suffix(".asc"), file_name.strip_suffix(".mac")) {
+      (Some(a), _) => (a, Method::File),
+      (_, Some(a)) => (a, Method::Hmac),
+      _ => {
+        problems.push(Problem {
+          description: format!("{} is not a file gpg-alias created", path.to_string_lossy()),
+          fix: None,
+        });
+        continue;
+      },
+    };
+
+    let in_use = (config.aliases.contains_key(alias) || config.groups.contains_key(alias))
+      && !config.system.aliases.contains(alias)
+      && config.signing.method == method;
+    if !in_use {
+      problems.push(Problem {
+        description: format!("{} is not the signature of any alias: remove it if it's no longer needed, or restore its alias with `gpg-alias rescue`", path.to_string_lossy()),
+        fix: None,
+      });
+      continue;
+    }
+
+    check_private(&path, problems);
+    check_attestation(config, alias, method, &path, problems);
+  }
+
+  Ok(())
+}
+
+fn check_attestation(config: &Config, alias: &str, method: Method, path: &Path, problems: &mut Vec<Problem>) {
+  let complete = std::fs::read_to_string(path)
+    .map(|s| match method {
+      Method::Hmac => (2..=4).contains(&s.lines().count()),
+      _ => s.contains("-----BEGIN PGP SIGNED MESSAGE-----") && s.trim_end().ends_with("-----END PGP SIGNATURE-----"),
+    })
+    .unwrap_or(false);
+  if !complete {
+    problems.push(Problem {
+      description: format!("{} is truncated or corrupt: delete it and re-attest alias `{}`", path.to_string_lossy(), alias),
+      fix: None,
+    });
+    return;
+  }
+
+  let id = match config.aliases.get(alias) {
+    Some(a) => a.key().to_string(),
+    None => match crate::groups::attested_id(config, alias) {
+      Ok(id) => id,
+      Err(_) => {
+        problems.push(Problem {
+          description: format!("{} can't be checked, since the members of group `{}` don't all verify", path.to_string_lossy(), alias),
+          fix: None,
+        });
+        return;
+      },
+    },
+  };
+  if crate::verify_attestation(config, alias, &id).is_err() {
+    problems.push(Problem {
+      description: format!("{} does not verify for alias `{}`", path.to_string_lossy(), alias),
+      fix: None,
+    });
+  }
+}
@@ -0,0 +1,48 @@
+use crate::Config;
+
+use std::{
+  io::Write,
+  time::SystemTime,
+};
+
+// counts edits gpg-alias makes to the config, so long-running processes such as the native host
+// notice them even when the config's modification time alone wouldn't tell them apart
+pub const GENERATION_FILE: &str = "generation";
+
+#[derive(Debug, PartialEq)]
+pub struct Stamp {
+  generation: u64,
+  config_modified: Option<SystemTime>,
+}
+
+pub fn current(config: &Config) -> Stamp {
+  Stamp {
+    generation: read(config).unwrap_or(0),
+    config_modified: std::fs::metadata(&config.path).and_then(|m| m.modified()).ok(),
+  }
+}
+
+fn read(config: &Config) -> Option<u64> {
+  let path = crate::data_dir(config).ok()?.join(GENERATION_FILE);
+  std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+pub fn is_valid(contents: &str) -> bool {
+  contents.trim().parse::<u64>().is_ok()
+}
+
+pub fn bump(config: &Config) {
+  let path = match crate::data_dir(config) {
+    Ok(d) => d.join(GENERATION_FILE),
+    Err(_) => return,
+  };
+  let next = read(config).unwrap_or(0).wrapping_add(1);
+
+  let tmp = path.with_extension("tmp");
+  let res = crate::files::create_file(&tmp)
+    .and_then(|mut f| writeln!(f, "{}", next))
+    .and_then(|_| std::fs::rename(&tmp, &path));
+  if let Err(e) = res {
+    warn!("could not update {}: {}", path.to_string_lossy(), e);
+  }
+}
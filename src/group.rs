@@ -0,0 +1,400 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::{Config, Method};
+
+use std::path::Path;
+
+// a key offered as a member, with the alias it has or the one it would be given
+struct Member {
+  alias: String,
+  key: String,
+  description: String,
+  is_new: bool,
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  match matches.subcommand() {
+    ("build", Some(sub)) => build(config, sub),
+    ("add", Some(sub)) => add(config, sub),
+    ("remove", Some(sub)) => remove(config, sub),
+    ("list", Some(_)) => list(config),
+    ("members", Some(sub)) => members(config, sub),
+    _ => unreachable!("clap requires a subcommand"),
+  }
+}
+
+// one session instead of adding an alias per key, then the group, then attesting each: the keys
+// matching the search are listed, the chosen ones get aliases if they have none, and the new
+// aliases and the group are attested together before the config is edited
+fn build(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("name").expect("required clap argument");
+  let query = matches.value_of("search").expect("required clap argument");
+
+  if crate::manage::check_writable(config, "build a group").is_err() {
+    return 1;
+  }
+  if config.groups.contains_key(name) || config.aliases.contains_key(name) {
+    error!("`{}` already exists as an alias or group", name);
+    return 1;
+  }
+  if crate::naming::check(config, name).is_err() {
+    return 1;
+  }
+
+  let found = match search(config, query) {
+    Ok(f) => f,
+    Err(exit) => return exit,
+  };
+  if found.is_empty() {
+    error!("no usable keys in the keyring match `{}`", query);
+    return 1;
+  }
+
+  let chosen = match choose(&found) {
+    Ok(c) => c,
+    Err(exit) => return exit,
+  };
+  if chosen.is_empty() {
+    info!("no members were chosen, so group `{}` was not built", name);
+    return 0;
+  }
+  for member in chosen.iter().filter(|m| m.is_new) {
+    if crate::naming::check_safe(&member.alias).is_err() {
+      return 1;
+    }
+    if let Some(problem) = crate::naming::problem(config, &member.alias) {
+      error!("the proposed alias `{}` {}, as the [naming] rules require: add it by hand first", member.alias, problem);
+      return 1;
+    }
+  }
+
+  let mut members: Vec<String> = chosen.iter().map(|m| m.alias.clone()).collect();
+  members.sort();
+  let mut keys: Vec<String> = chosen.iter().map(|m| m.key.clone()).collect();
+  keys.sort();
+  keys.dedup();
+
+  // attested before the config is edited, like `add`, so nothing is left unattested
+  if config.signing.enabled {
+    let mut entries: Vec<(&str, &str)> = chosen.iter()
+      .filter(|m| m.is_new)
+      .map(|m| (m.alias.as_str(), m.key.as_str()))
+      .collect();
+    let canonical = crate::groups::canonical(&keys);
+    // local signatures are on keys, so each key in the group needs one
+    if config.signing.method == Method::Lsign {
+      entries.extend(keys.iter().map(|k| (name, k.as_str())));
+    } else {
+      entries.push((name, canonical.as_str()));
+    }
+    if let Err(exit) = crate::sign_all::attest(config, &entries) {
+      error!("group `{}` was not built, since its aliases were not all attested", name);
+      return exit;
+    }
+  }
+
+  let new = chosen.iter().filter(|m| m.is_new).count();
+  let res = crate::manage::edit_config(config, &format!("add group `{}` and {} alias(es)", name, new), |path| {
+    for member in chosen.iter().filter(|m| m.is_new) {
+      crate::edit::add_alias(path, &member.alias, &member.key, &[])?;
+    }
+    crate::edit::add_group(path, name, &members)
+  });
+  if let Err(exit) = res {
+    return exit;
+  }
+
+  info!("built group `{}` with {} member(s), {} of them new aliases", name, members.len(), new);
+  0
+}
+
+// members are checked as `validate` checks them, so a misspelt alias is never added as a key ID. a
+// group that doesn't exist yet is added
+fn add(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("name").expect("required clap argument");
+  let new: Vec<&str> = matches.values_of("member").expect("required clap argument").collect();
+  let description = format!("add {} member(s) to group `{}`", new.len(), name);
+
+  if crate::manage::check_writable(config, &description).is_err() {
+    return 1;
+  }
+  if config.aliases.contains_key(name) {
+    error!("`{}` is an alias, not a group", name);
+    return 1;
+  }
+  let exists = config.groups.contains_key(name);
+  if !exists && crate::naming::check(config, name).is_err() {
+    return 1;
+  }
+
+  let mut members = config.groups.get(name).cloned().unwrap_or_default();
+  for member in new {
+    if member == name {
+      error!("group `{}` can't include itself", name);
+      return 1;
+    }
+    if !crate::validate::is_known_member(config, member) {
+      error!("`{}` is not an alias, group, `@name` or key ID, so it was not added to group `{}`", member, name);
+      return 1;
+    }
+    if members.iter().any(|m| m == member) {
+      warn!("`{}` is already a member of group `{}`", member, name);
+      continue;
+    }
+    members.push(member.to_string());
+  }
+  if exists && members.len() == config.groups[name].len() {
+    info!("group `{}` is unchanged", name);
+    return 0;
+  }
+
+  let res = change(config, name, &members, &description, |path| if exists {
+    crate::edit::set_group(path, name, &members)
+  } else {
+    crate::edit::add_group(path, name, &members)
+  });
+  if let Err(exit) = res {
+    return exit;
+  }
+
+  info!("group `{}` now has {} member(s)", name, members.len());
+  0
+}
+
+// a group left without members is removed, along with its attestation
+fn remove(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("name").expect("required clap argument");
+  let gone: Vec<&str> = matches.values_of("member").expect("required clap argument").collect();
+  let description = format!("remove {} member(s) from group `{}`", gone.len(), name);
+
+  if crate::manage::check_writable(config, &description).is_err() {
+    return 1;
+  }
+  let current = match config.groups.get(name) {
+    Some(m) => m,
+    None => {
+      error!("no such group `{}`", name);
+      return 1;
+    },
+  };
+  if let Some(member) = gone.iter().find(|g| !current.iter().any(|m| m == *g)) {
+    error!("`{}` is not a member of group `{}`", member, name);
+    return 1;
+  }
+
+  let members: Vec<String> = current.iter().filter(|m| !gone.contains(&m.as_str())).cloned().collect();
+  if members.is_empty() {
+    if let Err(exit) = crate::manage::edit_config(config, &format!("remove group `{}`", name), |path| crate::edit::remove_group(path, name)) {
+      return exit;
+    }
+    if !config.simulate {
+      if let Err(exit) = crate::manage::remove_attestations(config, name) {
+        return exit;
+      }
+      info!("removed group `{}`, which had no members left", name);
+    }
+    return 0;
+  }
+
+  if let Err(exit) = change(config, name, &members, &description, |path| crate::edit::set_group(path, name, &members)) {
+    return exit;
+  }
+
+  info!("group `{}` now has {} member(s)", name, members.len());
+  0
+}
+
+// the group is attested again over the keys its new members expand to before the config is edited,
+// like `add`, and that attestation is removed again if the edit fails
+fn change<F>(config: &Config, name: &str, members: &[String], description: &str, edit: F) -> Result<(), i32>
+  where F: FnOnce(&Path) -> Result<(), i32>,
+{
+  if config.signing.enabled {
+    let keys = match crate::groups::attested_keys_with(config, name, members) {
+      Ok(k) => k,
+      Err(exit) => {
+        error!("group `{}` was not changed, since its members could not all be verified", name);
+        return Err(exit);
+      },
+    };
+    for key in &keys {
+      warn!("  group `{}` will include key ID `{}`", name, key);
+    }
+    if !crate::confirm(config, &config.prompts.text("confirm_group_change", &[("group", name)]))? {
+      error!("changing group `{}` was not authorised", name);
+      return Err(1);
+    }
+
+    let canonical = crate::groups::canonical(&keys);
+    // local signatures are on keys, so each key in the group needs one
+    let entries: Vec<(&str, &str)> = if config.signing.method == Method::Lsign {
+      keys.iter().map(|k| (name, k.as_str())).collect()
+    } else {
+      vec![(name, canonical.as_str())]
+    };
+    for (name, id) in entries {
+      if let Err(exit) = crate::attest(config, name, id) {
+        error!("group `{}` was not changed, since it could not be attested", name);
+        return Err(exit);
+      }
+    }
+  }
+
+  if let Err(exit) = crate::manage::edit_config(config, description, edit) {
+    if config.signing.enabled && config.signing.method != Method::Lsign {
+      let _ = crate::manage::remove_attestations(config, name);
+    }
+    return Err(exit);
+  }
+  Ok(())
+}
+
+fn list(config: &Config) -> i32 {
+  let mut names: Vec<&String> = config.groups.keys().collect();
+  names.sort();
+
+  let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+  for name in names {
+    println!("{:<width$}  {}", name, config.groups[name.as_str()].join(", "), width = width);
+  }
+
+  0
+}
+
+fn members(config: &Config, matches: &ArgMatches) -> i32 {
+  let name = matches.value_of("name").expect("required clap argument");
+  let members = match config.groups.get(name) {
+    Some(m) => m,
+    None => {
+      error!("no such group `{}`", name);
+      return 1;
+    },
+  };
+
+  let width = members.iter().map(|m| m.len()).max().unwrap_or(0);
+  for member in members {
+    let kind = if config.groups.contains_key(member) {
+      "group".to_string()
+    } else if let Some(alias) = config.aliases.get(member) {
+      format!("alias for {}", alias.key())
+    } else if member.starts_with('@') {
+      "defined with --define".to_string()
+    } else if crate::validate::is_key_id(member) {
+      "key ID".to_string()
+    } else {
+      "missing: not an alias, group or key ID".to_string()
+    };
+    println!("{:<width$}  {}", member, kind, width = width);
+  }
+
+  0
+}
+
+// usable keys matching the query, as gpg matches them: e-mail addresses, names or fingerprints
+fn search(config: &Config, query: &str) -> Result<Vec<Member>, i32> {
+  if crate::mock::is_enabled() {
+    error!("the mock backend has no keyring to search");
+    return Err(1);
+  }
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let keys = match ctx.find_keys(vec![query]) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not search the keyring: {}", e);
+      return Err(1);
+    },
+  };
+
+  let mut taken: Vec<String> = config.aliases.keys().chain(config.groups.keys()).cloned().collect();
+  let mut found = Vec::new();
+  for key in keys.filter_map(Result::ok) {
+    if key.is_revoked() || key.is_expired() || key.is_disabled() || key.is_invalid() || !key.can_encrypt() {
+      continue;
+    }
+    let fingerprint = match key.fingerprint() {
+      Ok(f) => f.to_string(),
+      Err(_) => continue,
+    };
+
+    let member = match crate::import::alias_of(config, &fingerprint) {
+      Some(alias) => Member {
+        alias: alias.to_string(),
+        key: config.aliases[alias].key().to_string(),
+        description: crate::import::describe(&key),
+        is_new: false,
+      },
+      None => {
+        let alias = crate::import::unique(&crate::naming::with_prefix(config, &crate::import::proposed_name(&key)), &taken);
+        taken.push(alias.clone());
+        Member {
+          alias,
+          key: fingerprint,
+          description: crate::import::describe(&key),
+          is_new: true,
+        }
+      },
+    };
+    found.push(member);
+  }
+
+  found.sort_by(|a, b| a.alias.cmp(&b.alias));
+  Ok(found)
+}
+
+// with --yes every match is a member
+fn choose(found: &[Member]) -> Result<Vec<&Member>, i32> {
+  match crate::tty::preset_answer() {
+    Some(true) => return Ok(found.iter().collect()),
+    Some(false) => {
+      error!("building a group asks which keys to include: run it in a terminal, or pass --yes to include every match");
+      return Err(1);
+    },
+    None => {},
+  }
+
+  for (i, member) in found.iter().enumerate() {
+    let status = if member.is_new { "new alias" } else { "existing alias" };
+    eprintln!("{:>3}. `{}` ({}): {}", i + 1, member.alias, status, member.description);
+  }
+  loop {
+    let answer = crate::tty::read_line("Members (numbers or ranges like 1 3 5-7, `all`, or nothing to stop): ")?;
+    match selection(&answer, found.len()) {
+      Some(indices) => return Ok(indices.into_iter().map(|i| &found[i]).collect()),
+      None => warn!("`{}` is not a selection: give numbers between 1 and {}", answer, found.len()),
+    }
+  }
+}
+
+// zero-based indices, in order and without repeats
+fn selection(answer: &str, count: usize) -> Option<Vec<usize>> {
+  let answer = answer.trim();
+  if answer == "all" {
+    return Some((0..count).collect());
+  }
+
+  let mut indices = Vec::new();
+  for part in answer.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+    let (start, end) = match part.split_once('-') {
+      Some((start, end)) => (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?),
+      None => {
+        let n = part.parse::<usize>().ok()?;
+        (n, n)
+      },
+    };
+    if start == 0 || start > end || end > count {
+      return None;
+    }
+    indices.extend(start - 1..end);
+  }
+  indices.sort_unstable();
+  indices.dedup();
+  Some(indices)
+}
@@ -0,0 +1,110 @@
+use crate::{Config, Method};
+
+// groups are attested as a whole: the sorted list of keys they expand to is signed like an alias's
+// key, so adding a member, or a member alias changing key, needs the group confirmed again. members
+// defined with --define (`@name`) are left out of the attestation, since they change from run to run
+pub fn expand(config: &Config, name: &str, only_verified: bool) -> Result<Vec<String>, i32> {
+  let mut defined = Vec::new();
+  let mut keys = member_keys(config, name, only_verified, &mut defined)?;
+  if config.signing.enabled {
+    attest_keys(config, name, &keys, only_verified)?;
+  }
+
+  if only_verified && !defined.is_empty() {
+    warn!("leaving {} key(s) defined on the command line out of group `{}`: they cannot be verified", defined.len(), name);
+    return Ok(crate::dedup::collapse(config, keys));
+  }
+  keys.extend(defined);
+  keys.sort();
+  keys.dedup();
+  Ok(crate::dedup::collapse(config, keys))
+}
+
+fn attest_keys(config: &Config, name: &str, keys: &[String], only_verified: bool) -> Result<(), i32> {
+  // local signatures are on keys, so each key in the group needs one
+  if config.signing.method == Method::Lsign {
+    for key in keys {
+      attest(config, name, key, only_verified)?;
+    }
+  } else {
+    attest(config, name, &canonical(keys), only_verified)?;
+  }
+
+  Ok(())
+}
+
+fn attest(config: &Config, name: &str, id: &str, only_verified: bool) -> Result<(), i32> {
+  if only_verified {
+    crate::verify_attestation(config, name, id)
+  } else {
+    // the config is never reverted for groups, so there is no attested key to use instead
+    crate::check_signature(config, name, id).map(|_| ())
+  }
+}
+
+// what the group's attestation covers, without prompting for anything
+pub fn attested_id(config: &Config, name: &str) -> Result<String, i32> {
+  member_keys(config, name, true, &mut Vec::new()).map(|keys| canonical(&keys))
+}
+
+// the keys the group's attestation would cover with these members in place of its own
+pub fn attested_keys_with<'a>(config: &'a Config, name: &'a str, members: &'a [String]) -> Result<Vec<String>, i32> {
+  let mut keys = Vec::new();
+  let mut stack = vec![name];
+  for member in members {
+    collect_member(config, name, member, true, &mut stack, &mut keys, &mut Vec::new())?;
+  }
+  keys.sort();
+  keys.dedup();
+  Ok(keys)
+}
+
+pub fn canonical(keys: &[String]) -> String {
+  keys.join(" ")
+}
+
+fn member_keys(config: &Config, name: &str, only_verified: bool, defined: &mut Vec<String>) -> Result<Vec<String>, i32> {
+  let mut keys = Vec::new();
+  collect(config, name, only_verified, &mut Vec::new(), &mut keys, defined)?;
+  keys.sort();
+  keys.dedup();
+  Ok(keys)
+}
+
+fn collect<'a>(config: &'a Config, name: &'a str, only_verified: bool, stack: &mut Vec<&'a str>, keys: &mut Vec<String>, defined: &mut Vec<String>) -> Result<(), i32> {
+  if stack.contains(&name) {
+    stack.push(name);
+    error!("group `{}` includes itself: {}", name, stack.join(" -> "));
+    return Err(1);
+  }
+  stack.push(name);
+
+  for member in &config.groups[name] {
+    collect_member(config, name, member, only_verified, stack, keys, defined)?;
+  }
+
+  stack.pop();
+  Ok(())
+}
+
+fn collect_member<'a>(config: &'a Config, name: &str, member: &'a str, only_verified: bool, stack: &mut Vec<&'a str>, keys: &mut Vec<String>, defined: &mut Vec<String>) -> Result<(), i32> {
+  if config.groups.contains_key(member) {
+    collect(config, member, only_verified, stack, keys, defined)?;
+  } else if config.aliases.contains_key(member) {
+    let key = if only_verified {
+      crate::resolve_verified(config, member)?.to_string()
+    } else {
+      crate::resolve(config, member)?.into_owned()
+    };
+    keys.push(key);
+  } else if let Some(placeholder) = member.strip_prefix('@') {
+    match crate::define::key(config, placeholder) {
+      Some(key) => defined.push(key.to_string()),
+      None => debug!("`{}` in group `{}` was not defined for this run", member, name),
+    }
+  } else {
+    keys.push(member.to_string());
+  }
+
+  Ok(())
+}
@@ -0,0 +1,376 @@
+use clap::ArgMatches;
+use gpgme::{Context, Key, Protocol};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Config;
+
+use std::{
+  collections::BTreeMap,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+// what export writes and import --file reads. it's the same shape as an adopt bundle, so an export
+// can also be clearsigned and adopted
+#[derive(Serialize, Deserialize)]
+struct AliasSet {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  serial: Option<u64>,
+  aliases: BTreeMap<String, String>,
+}
+
+pub struct Candidate {
+  pub name: String,
+  pub key: String,
+  pub description: String,
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  if crate::manage::check_writable(config, "import aliases").is_err() {
+    return 1;
+  }
+
+  let candidates = match (matches.value_of_os("file"), matches.value_of_os("keylist")) {
+    (Some(path), _) => from_file(config, Path::new(path)),
+    (None, Some(path)) => {
+      let signer = matches.value_of("signer").expect("required clap argument");
+      crate::keylist::candidates(config, Path::new(path), signer, matches.value_of_os("signature").map(Path::new))
+    },
+    (None, None) => from_keyring(config),
+  };
+  let candidates = match candidates {
+    Ok(c) => c,
+    Err(exit) => return exit,
+  };
+  if candidates.is_empty() {
+    info!("nothing to import");
+    return 0;
+  }
+
+  let accepted = match choose(config, candidates) {
+    Ok(a) => a,
+    Err(exit) => return exit,
+  };
+  if accepted.is_empty() {
+    info!("no aliases were imported");
+    return 0;
+  }
+
+  let entries: Vec<(&str, &str)> = accepted.iter().map(|(n, k)| (n.as_str(), k.as_str())).collect();
+  // attested before the config is edited, like `add`, so an alias is never left unattested
+  if config.signing.enabled {
+    if let Err(exit) = crate::sign_all::attest(config, &entries) {
+      error!("no aliases were imported, since they were not all attested");
+      return exit;
+    }
+  }
+
+  if config.simulate {
+    info!("simulating: the config would be edited to add {} alias(es)", entries.len());
+    return 0;
+  }
+  for (name, key) in &entries {
+    if let Err(exit) = crate::edit::add_alias(&config.path, name, key, &[]) {
+      return exit;
+    }
+  }
+  if let Err(exit) = crate::edit::finish(config) {
+    return exit;
+  }
+
+  info!("imported {} alias(es)", entries.len());
+  0
+}
+
+pub fn export(config: &Config, matches: &ArgMatches) -> i32 {
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+  // only what we'd vouch for ourselves is worth handing to someone else
+  names.retain(|name| {
+    let verified = !config.signing.enabled || crate::resolve_verified(config, name).is_ok();
+    if !verified {
+      warn!("leaving out alias `{}`: it could not be verified", name);
+    }
+    verified
+  });
+  let serial = match matches.value_of("serial").map(str::parse::<u64>) {
+    Some(Ok(s)) => Some(s),
+    Some(Err(_)) => {
+      error!("--serial must be a number");
+      return 1;
+    },
+    None => None,
+  };
+  let set = AliasSet {
+    serial,
+    aliases: names.iter().map(|name| (name.to_string(), config.aliases[name.as_str()].key().to_string())).collect(),
+  };
+
+  if matches.value_of("format") == Some("keylist") {
+    return export_keylist(config, &names, matches);
+  }
+
+  let output = match matches.value_of("format") {
+    Some("json") => serde_json::to_string_pretty(&set).map_err(|e| e.to_string()),
+    Some("markdown") => Ok(crate::roster::render(config, &names, crate::roster::Format::Markdown)),
+    Some("html") => Ok(crate::roster::render(config, &names, crate::roster::Format::Html)),
+    _ => toml::to_string(&set).map_err(|e| e.to_string()),
+  };
+  let mut output = match output {
+    Ok(o) => o,
+    Err(e) => {
+      error!("could not serialise aliases: {}", e);
+      return 1;
+    },
+  };
+  if !output.ends_with('\n') {
+    output.push('\n');
+  }
+
+  match matches.value_of_os("output") {
+    Some(path) => {
+      let path = PathBuf::from(path);
+      let res = crate::files::create_file(&path).and_then(|mut f| f.write_all(output.as_bytes()));
+      if let Err(e) = res {
+        error!("could not write {}: {}", path.to_string_lossy(), e);
+        return 1;
+      }
+      info!("exported {} alias(es) to {}", set.aliases.len(), path.to_string_lossy());
+    },
+    None => print!("{}", output),
+  }
+
+  0
+}
+
+fn export_keylist(config: &Config, names: &[&String], matches: &ArgMatches) -> i32 {
+  let (output, url) = match (matches.value_of_os("output"), matches.value_of("signature-url")) {
+    (Some(o), Some(u)) => (Path::new(o), u),
+    _ => {
+      error!("keylists are written with their signature next to them: give --output and --signature-url");
+      return 1;
+    },
+  };
+
+  match crate::keylist::export(config, names, output, url) {
+    Ok(count) => {
+      info!("exported {} key(s) to {} and signed it in {}", count, output.to_string_lossy(), crate::keylist::signature_path(output).to_string_lossy());
+      0
+    },
+    Err(exit) => exit,
+  }
+}
+
+fn from_file(config: &Config, path: &Path) -> Result<Vec<Candidate>, i32> {
+  let contents = match std::fs::read_to_string(path) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let is_json = path.extension().is_some_and(|e| e == "json") || contents.trim_start().starts_with('{');
+  let set: AliasSet = if is_json {
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+  } else {
+    toml::from_str(&contents).map_err(|e| e.to_string())
+  }.map_err(|e| {
+    error!("could not parse the aliases in {}: {}", path.to_string_lossy(), e);
+    1
+  })?;
+
+  let mut ctx = context()?;
+  let mut candidates = Vec::new();
+  for (name, key) in set.aliases {
+    if let Some(existing) = config.aliases.get(&name) {
+      if existing.key() != key {
+        warn!("skipping `{}`: it already points to `{}` here, not `{}`", name, existing.key(), key);
+      }
+      continue;
+    }
+    if crate::manage::check_key(&key).is_err() {
+      warn!("skipping `{}`: its key is not in the keyring", name);
+      continue;
+    }
+
+    let description = ctx.get_key(key.as_str()).ok().map(|k| describe(&k)).unwrap_or_else(|| key.clone());
+    candidates.push(Candidate { name, key, description });
+  }
+
+  Ok(candidates)
+}
+
+fn from_keyring(config: &Config) -> Result<Vec<Candidate>, i32> {
+  if crate::mock::is_enabled() {
+    error!("the mock backend has no keyring to import aliases from");
+    return Err(1);
+  }
+
+  let mut ctx = context()?;
+  let keys = match ctx.keys() {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not list the keyring: {}", e);
+      return Err(1);
+    },
+  };
+
+  let mut taken: Vec<String> = config.aliases.keys().cloned().collect();
+  let mut candidates = Vec::new();
+  for key in keys.filter_map(Result::ok) {
+    if key.is_revoked() || key.is_expired() || key.is_disabled() || key.is_invalid() {
+      continue;
+    }
+    let fingerprint = match key.fingerprint() {
+      Ok(f) => f.to_string(),
+      Err(_) => continue,
+    };
+    if is_aliased(config, &fingerprint) {
+      continue;
+    }
+
+    let name = unique(&crate::naming::with_prefix(config, &proposed_name(&key)), &taken);
+    taken.push(name.clone());
+    candidates.push(Candidate {
+      name,
+      key: fingerprint,
+      description: describe(&key),
+    });
+  }
+
+  candidates.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(candidates)
+}
+
+// asks about each candidate in turn. with --yes every proposal is taken as it is
+fn choose(config: &Config, candidates: Vec<Candidate>) -> Result<Vec<(String, String)>, i32> {
+  let mut accepted: Vec<(String, String)> = Vec::new();
+
+  match crate::tty::preset_answer() {
+    Some(true) => return Ok(candidates.into_iter()
+      .filter(|c| match crate::naming::unsafe_part(&c.name) {
+        Some(part) => {
+          warn!("skipping `{}`: the name {}, which can't be part of a file name", c.name.escape_default(), part);
+          false
+        },
+        None => true,
+      })
+      .filter(|c| match crate::naming::problem(config, &c.name) {
+        Some(problem) => {
+          warn!("skipping `{}`: the name {}, as the [naming] rules require", c.name, problem);
+          false
+        },
+        None => true,
+      })
+      .map(|c| (c.name, c.key))
+      .collect()),
+    Some(false) => {
+      error!("importing asks about each key: run it in a terminal, or pass --yes to accept every proposed alias");
+      return Err(1);
+    },
+    None => {},
+  }
+
+  'candidates: for candidate in candidates {
+    let mut name = candidate.name;
+    loop {
+      let question = format!("Add alias `{}` for {}? [Y]es, [r]ename, [s]kip, [q]uit: ", name, candidate.description);
+      match crate::tty::ask(&question)?.as_str() {
+        "" | "y" | "yes" => match crate::naming::unsafe_part(&name) {
+          Some(part) => warn!("the name `{}` {}, which can't be part of a file name: rename it or skip the key", name.escape_default(), part),
+          None => match crate::naming::problem(config, &name) {
+            Some(problem) => warn!("the name `{}` {}, as the [naming] rules require: rename it or skip the key", name, problem),
+            None => break,
+          },
+        },
+        "s" | "skip" => continue 'candidates,
+        "q" | "quit" => return Ok(accepted),
+        "r" | "rename" => {
+          let new = crate::tty::read_line("New alias: ")?;
+          if new.is_empty() {
+            continue;
+          }
+          if config.aliases.contains_key(&new) || accepted.iter().any(|(n, _)| *n == new) {
+            warn!("alias `{}` already exists", new);
+            continue;
+          }
+          name = new;
+        },
+        _ => {},
+      }
+    }
+    accepted.push((name, candidate.key));
+  }
+
+  Ok(accepted)
+}
+
+pub fn is_aliased(config: &Config, fingerprint: &str) -> bool {
+  alias_of(config, fingerprint).is_some()
+}
+
+// configured key IDs may be fingerprints or any suffix of one
+pub fn alias_of<'a>(config: &'a Config, fingerprint: &str) -> Option<&'a str> {
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+  names.into_iter()
+    .find(|name| {
+      let key = config.aliases[name.as_str()].key().trim_start_matches("0x").to_uppercase();
+      !key.is_empty() && fingerprint.to_uppercase().ends_with(&key)
+    })
+    .map(String::as_str)
+}
+
+// the local part of the key's e-mail address, or its name if it has none
+pub fn proposed_name(key: &Key) -> String {
+  let uid = key.user_ids().find(|u| !u.is_revoked() && !u.is_invalid());
+  let base = uid.as_ref()
+    .and_then(|u| u.email().ok())
+    .and_then(|e| e.split('@').next())
+    .filter(|l| !l.is_empty())
+    .or_else(|| uid.as_ref().and_then(|u| u.name().ok()))
+    .unwrap_or("key");
+
+  slug(base)
+}
+
+// something usable as an alias name, made from a name or an e-mail address's local part
+pub fn slug(base: &str) -> String {
+  let name: String = base.to_lowercase().chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' { c } else { '-' })
+    .collect();
+  let name = name.trim_matches('-');
+  if name.is_empty() {
+    "key".to_string()
+  } else {
+    name.to_string()
+  }
+}
+
+pub fn unique(name: &str, taken: &[String]) -> String {
+  let mut candidate = name.to_string();
+  let mut n = 2;
+  while taken.contains(&candidate) {
+    candidate = format!("{}-{}", name, n);
+    n += 1;
+  }
+  candidate
+}
+
+pub fn describe(key: &Key) -> String {
+  let fingerprint = key.fingerprint().unwrap_or("");
+  match key.user_ids().next().and_then(|u| u.id().ok().map(ToString::to_string)) {
+    Some(uid) => format!("{} ({})", uid, fingerprint),
+    None => fingerprint.to_string(),
+  }
+}
+
+fn context() -> Result<Context, i32> {
+  match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => Ok(c),
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      Err(1)
+    },
+  }
+}
@@ -0,0 +1,86 @@
+use gpgme::{Context, Protocol};
+
+use crate::{Config, Method};
+
+use std::collections::BTreeSet;
+
+// only looks: a data directory that doesn't exist yet is reported as such rather than created, and
+// nothing can be attested in it
+pub fn run(config: &Config) -> i32 {
+  let data_exists = config.data_dir.is_dir();
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+
+  let mut attested = 0;
+  let mut missing = Vec::new();
+  let mut expired = Vec::new();
+  let mut revoked = Vec::new();
+
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+
+  for name in names {
+    let key_id = config.aliases[name].key();
+    let can_exist = data_exists || config.signing.method == Method::Lsign || config.system.aliases.contains(name.as_str());
+    if can_exist && crate::attestation_exists(config, name, key_id).unwrap_or(false) {
+      attested += 1;
+    }
+
+    match ctx.get_key(key_id) {
+      Ok(key) => {
+        if key.is_expired() {
+          expired.push(name.as_str());
+        }
+        if key.is_revoked() {
+          revoked.push(name.as_str());
+        }
+      },
+      Err(_) => missing.push(name.as_str()),
+    }
+  }
+
+  row("config", config.path.to_string_lossy());
+  if data_exists {
+    row("data", config.data_dir.to_string_lossy());
+  } else {
+    row("data", format!("{} (not created yet)", config.data_dir.to_string_lossy()));
+  }
+  row("signing", if config.signing.enabled { "enabled" } else { "disabled" });
+  row("aliases", config.aliases.len());
+  row("groups", config.groups.len());
+  row("namespaces", namespaces(config));
+  row("attested", attested);
+  row("unattested", config.aliases.len() - attested);
+  row("missing from keyring", list(&missing));
+  row("expired keys", list(&expired));
+  row("revoked keys", list(&revoked));
+
+  0
+}
+
+fn row<T: std::fmt::Display>(label: &str, value: T) {
+  println!("{:<22}{}", format!("{}:", label), value);
+}
+
+// names such as `infra:alice` are in the `infra` namespace, as [naming]'s prefix makes them
+fn namespaces(config: &Config) -> String {
+  let found: BTreeSet<&str> = config.aliases.keys().chain(config.groups.keys())
+    .filter_map(|name| name.find(':').map(|i| &name[..i]))
+    .collect();
+  let found: Vec<&str> = found.into_iter().collect();
+  list(&found)
+}
+
+fn list(names: &[&str]) -> String {
+  if names.is_empty() {
+    return "0".to_string();
+  }
+
+  format!("{} ({})", names.len(), names.join(", "))
+}
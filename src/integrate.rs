@@ -0,0 +1,49 @@
+use clap::ArgMatches;
+
+use crate::{quote::Shell, Config};
+
+// the default place for borg's encrypted repository passphrase
+pub const PASSPHRASE_FILE: &str = "borg-passphrase.gpg";
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let tool = matches.value_of("tool").expect("required clap argument");
+  let aliases: Vec<&str> = matches.values_of("alias").expect("required clap argument").collect();
+  let passphrase = matches.value_of("passphrase-file").expect("defaulted clap argument");
+  print(config, tool, &aliases, passphrase)
+}
+
+// neither tar nor borg encrypt to pgp keys themselves, so each integration is the gpg half of the
+// pipeline that tool is normally paired with. what's printed is quoted for the shell, so it's meant
+// for `eval` rather than a bare `$(...)`, which would keep the quotes as part of the arguments
+pub fn print(config: &Config, tool: &str, aliases: &[&str], passphrase: &str) -> i32 {
+  let shell = config.output.shell_quote.unwrap_or(Shell::Posix);
+
+  let gpg = match crate::gpg_program() {
+    Ok(g) => g,
+    Err(exit) => return exit,
+  };
+
+  let mut encrypt = vec![shell.quote(&gpg).into_owned(), "--encrypt".to_string()];
+  encrypt.extend(crate::options::for_names(config, aliases).iter().map(|o| shell.quote(o).into_owned()));
+  for alias in aliases.iter().copied() {
+    let key_id = match crate::resolve(config, alias) {
+      Ok(k) => k,
+      Err(exit) => return exit,
+    };
+    encrypt.push(format!("--recipient {}", shell.quote(&key_id)));
+  }
+  let encrypt = encrypt.join(" ");
+
+  match tool {
+    // tar -cf - DIR | eval "$(gpg-alias --tar ALIAS...)" > DIR.tar.gpg
+    "tar" => println!("{}", encrypt),
+    // borg encrypts the repository itself, but can read its passphrase from a command
+    "borg" => {
+      println!("{} --output {} # reads the passphrase on stdin", encrypt, shell.quote(passphrase));
+      println!("export BORG_PASSCOMMAND={}", shell.quote(&format!("{} --quiet --decrypt {}", shell.quote(&gpg), shell.quote(passphrase))));
+    },
+    _ => unreachable!("clap restricts the tool"),
+  }
+
+  0
+}
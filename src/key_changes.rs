@@ -0,0 +1,190 @@
+use gpgme::{Context, Key, Subkey};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Config;
+
+use std::{
+  collections::BTreeMap,
+  io::Write,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const STATE_FILE: &str = "keyring-state.json";
+
+// what was last seen of each alias's key, so changes made to the keyring in between runs (by a
+// refresh from a keyserver, say) can be pointed out
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+  aliases: BTreeMap<String, KeyState>,
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+struct KeyState {
+  fingerprint: String,
+  revoked: bool,
+  expires: Option<u64>,
+  subkeys: Vec<SubkeyState>,
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+struct SubkeyState {
+  fingerprint: String,
+  encrypt: bool,
+  sign: bool,
+  auth: bool,
+  revoked: bool,
+  expires: Option<u64>,
+}
+
+// warns about changes to the keys of the given aliases since they were last seen. noticing changes
+// is a courtesy, so failing to look is only logged
+pub fn check(config: &Config, aliases: &[&str]) {
+  let path = match crate::data_dir(config) {
+    Ok(d) => d.join(STATE_FILE),
+    Err(_) => return,
+  };
+  let mut state = load(&path);
+  let mut changed = false;
+  for alias in aliases {
+    let key_id = match config.aliases.get(*alias) {
+      Some(a) => a.key(),
+      None => continue,
+    };
+    let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+      Ok(c) => c,
+      Err(e) => {
+        debug!("could not create gpgme context: {}", e);
+        return;
+      },
+    };
+    let current = ctx.get_key(key_id).ok().map(|k| snapshot(&k));
+
+    match (state.aliases.get(*alias), &current) {
+      (Some(old), Some(new)) if old != new => {
+        for change in describe(old, new) {
+          warn!("key for alias `{}` {}", alias, change);
+        }
+      },
+      (Some(_), None) => warn!("key for alias `{}` is no longer in the keyring", alias),
+      _ => {},
+    }
+
+    if state.aliases.get(*alias) != current.as_ref() {
+      changed = true;
+      match current {
+        Some(new) => state.aliases.insert(alias.to_string(), new),
+        None => state.aliases.remove(*alias),
+      };
+    }
+  }
+
+  if changed && !config.no_write {
+    save(&path, &state);
+  }
+}
+
+fn load(path: &Path) -> State {
+  std::fs::read(path).ok()
+    .and_then(|s| serde_json::from_slice(&s).ok())
+    .unwrap_or_default()
+}
+
+pub fn is_valid(path: &Path) -> bool {
+  std::fs::read(path).ok()
+    .and_then(|s| serde_json::from_slice::<State>(&s).ok())
+    .is_some()
+}
+
+fn save(path: &Path, state: &State) {
+  let res = serde_json::to_vec_pretty(state)
+    .map_err(std::io::Error::from)
+    .and_then(|json| crate::files::create_file(&temp_path(path)).and_then(|mut f| f.write_all(&json)))
+    .and_then(|_| std::fs::rename(temp_path(path), path));
+  if let Err(e) = res {
+    warn!("could not record keyring state in {}: {}", path.to_string_lossy(), e);
+  }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+  path.with_extension("json.tmp")
+}
+
+fn snapshot(key: &Key) -> KeyState {
+  KeyState {
+    fingerprint: key.fingerprint().unwrap_or("").to_string(),
+    revoked: key.is_revoked(),
+    expires: key.subkeys().next().and_then(|k| k.expiration_time()).and_then(unix),
+    // the primary key is the first subkey and is described by the fields above
+    subkeys: key.subkeys().skip(1).map(|k| subkey_snapshot(&k)).collect(),
+  }
+}
+
+fn subkey_snapshot(subkey: &Subkey) -> SubkeyState {
+  SubkeyState {
+    fingerprint: subkey.fingerprint().unwrap_or("").to_string(),
+    encrypt: subkey.can_encrypt(),
+    sign: subkey.can_sign(),
+    auth: subkey.can_authenticate(),
+    revoked: subkey.is_revoked(),
+    expires: subkey.expiration_time().and_then(unix),
+  }
+}
+
+fn describe(old: &KeyState, new: &KeyState) -> Vec<String> {
+  let mut changes = Vec::new();
+
+  if old.fingerprint != new.fingerprint {
+    changes.push(format!("is now a different key ({}, was {})", new.fingerprint, old.fingerprint));
+    return changes;
+  }
+  if new.revoked && !old.revoked {
+    changes.push("was revoked".to_string());
+  }
+  if let Some(change) = expiry_change(old.expires, new.expires) {
+    changes.push(format!("had its expiry {}", change));
+  }
+
+  for sub in &new.subkeys {
+    match old.subkeys.iter().find(|s| s.fingerprint == sub.fingerprint) {
+      None => changes.push(format!("gained a new {}", subkey_name(sub))),
+      Some(o) if sub.revoked && !o.revoked => changes.push(format!("had its {} revoked", subkey_name(sub))),
+      Some(o) => if let Some(change) = expiry_change(o.expires, sub.expires) {
+        changes.push(format!("had the expiry of its {} {}", subkey_name(sub), change));
+      },
+    }
+  }
+  for sub in &old.subkeys {
+    if !new.subkeys.iter().any(|s| s.fingerprint == sub.fingerprint) {
+      changes.push(format!("lost its {}", subkey_name(sub)));
+    }
+  }
+
+  changes
+}
+
+fn expiry_change(old: Option<u64>, new: Option<u64>) -> Option<&'static str> {
+  match (old, new) {
+    (Some(_), None) => Some("removed"),
+    (None, Some(_)) => Some("set"),
+    (Some(o), Some(n)) if n > o => Some("extended"),
+    (Some(o), Some(n)) if n < o => Some("brought forward"),
+    _ => None,
+  }
+}
+
+fn subkey_name(sub: &SubkeyState) -> String {
+  let uses: Vec<&str> = [(sub.encrypt, "encryption"), (sub.sign, "signing"), (sub.auth, "authentication")].iter()
+    .filter(|(can, _)| *can)
+    .map(|(_, name)| *name)
+    .collect();
+  if uses.is_empty() {
+    format!("subkey {}", sub.fingerprint)
+  } else {
+    format!("{} subkey {}", uses.join("/"), sub.fingerprint)
+  }
+}
+
+fn unix(time: SystemTime) -> Option<u64> {
+  time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
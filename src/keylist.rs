@@ -0,0 +1,168 @@
+use gpgme::{Context, Protocol};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{import::Candidate, Config};
+
+use std::{
+  ffi::OsString,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+// the keylist format from draft-mccain-keylist: a JSON list of fingerprints, published next to a
+// detached signature over the whole file. fields other tools add are ignored
+#[derive(Serialize, Deserialize)]
+struct Keylist {
+  metadata: Metadata,
+  keys: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+  // where the signature is published. gpg-alias reads and writes it next to the keylist instead
+  signature: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+  fingerprint: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  name: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  email: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  comment: Option<String>,
+  // not in the draft: the alias the key had where the keylist was exported, so it keeps its name
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  alias: Option<String>,
+}
+
+pub fn signature_path(path: &Path) -> PathBuf {
+  let mut sig = OsString::from(path.as_os_str());
+  sig.push(".asc");
+  PathBuf::from(sig)
+}
+
+// nothing from a keylist is proposed unless its signature was made by the key it's expected from
+pub fn candidates(config: &Config, path: &Path, signer: &str, signature: Option<&Path>) -> Result<Vec<Candidate>, i32> {
+  let contents = match std::fs::read(path) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let sig_path = signature.map(Path::to_path_buf).unwrap_or_else(|| signature_path(path));
+  let sig = crate::read_signature(&sig_path)?;
+  if crate::verify_detached(signer, sig, &contents).is_err() {
+    error!("the signature in {} over {} does not verify", sig_path.to_string_lossy(), path.to_string_lossy());
+    return Err(1);
+  }
+  let keylist: Keylist = match serde_json::from_slice(&contents) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not parse the keylist in {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  let mut taken: Vec<String> = config.aliases.keys().cloned().collect();
+  let mut candidates = Vec::new();
+  for entry in keylist.keys {
+    let fingerprint = entry.fingerprint.replace(' ', "").to_uppercase();
+    if crate::import::is_aliased(config, &fingerprint) {
+      continue;
+    }
+    if crate::manage::check_key(&fingerprint).is_err() {
+      warn!("skipping {}: it is not in the keyring", fingerprint);
+      continue;
+    }
+
+    let base = entry.alias.clone()
+      .or_else(|| entry.email.as_ref().and_then(|e| e.split('@').next()).filter(|l| !l.is_empty()).map(ToString::to_string))
+      .or_else(|| entry.name.clone())
+      .unwrap_or_else(|| "key".to_string());
+    let name = crate::import::unique(&crate::naming::with_prefix(config, &crate::import::slug(&base)), &taken);
+    taken.push(name.clone());
+
+    let who = match (&entry.name, &entry.email) {
+      (Some(name), Some(email)) => format!("{} <{}>", name, email),
+      (Some(who), None) | (None, Some(who)) => who.clone(),
+      (None, None) => String::new(),
+    };
+    let description = if who.is_empty() { fingerprint.clone() } else { format!("{} ({})", who, fingerprint) };
+    candidates.push(Candidate { name, key: fingerprint, description });
+  }
+
+  Ok(candidates)
+}
+
+// writes the keylist and its detached signature next to it, as keylist tooling expects to find them
+pub fn export(config: &Config, names: &[&String], output: &Path, signature_url: &str) -> Result<usize, i32> {
+  if config.signing.key.is_empty() || config.signing.method == crate::Method::Hmac {
+    error!("keylists are signed, which needs an OpenPGP signing key in [signing]");
+    return Err(1);
+  }
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let mut keys = Vec::new();
+  for name in names {
+    let alias = &config.aliases[name.as_str()];
+    if alias.protocol() == crate::protocol::KeyProtocol::Cms {
+      warn!("leaving out alias `{}`: keylists only hold OpenPGP keys", name);
+      continue;
+    }
+    let key = match ctx.get_key(alias.key()) {
+      Ok(k) => k,
+      Err(e) => {
+        warn!("leaving out alias `{}`: could not find its key: {}", name, e);
+        continue;
+      },
+    };
+    let uid = key.user_ids().next();
+    keys.push(Entry {
+      fingerprint: key.fingerprint().unwrap_or(alias.key()).to_string(),
+      name: uid.as_ref().and_then(|u| u.name().ok()).filter(|n| !n.is_empty()).map(ToString::to_string),
+      email: uid.as_ref().and_then(|u| u.email().ok()).filter(|e| !e.is_empty()).map(ToString::to_string),
+      comment: alias.entry().and_then(|e| e.comment.clone()),
+      alias: Some(name.to_string()),
+    });
+  }
+
+  let keylist = Keylist {
+    metadata: Metadata {
+      signature: signature_url.to_string(),
+      comment: None,
+    },
+    keys,
+  };
+  let mut contents = match serde_json::to_string_pretty(&keylist) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not serialise the keylist: {}", e);
+      return Err(1);
+    },
+  };
+  contents.push('\n');
+
+  // the signature covers exactly the bytes written
+  let signature = crate::sign_detached(config, contents.as_bytes())?;
+  let sig_path = signature_path(output);
+  for (path, data) in [(output, contents.as_bytes()), (sig_path.as_path(), &signature[..])] {
+    let res = crate::files::create_file(path).and_then(|mut f| f.write_all(data));
+    if let Err(e) = res {
+      error!("could not write {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    }
+  }
+
+  Ok(keylist.keys.len())
+}
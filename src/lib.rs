@@ -0,0 +1,1664 @@
+#[macro_use] extern crate log;
+
+use clap::ArgMatches;
+use gpgme::{Context, Protocol, SignatureNotationFlags, SignatureSummary, results::Signature};
+use serde_derive::Deserialize;
+
+use std::{
+  borrow::Cow,
+  collections::{HashMap, HashSet},
+  fs::File,
+  path::{Path, PathBuf},
+  io::{Read, Write},
+  time::SystemTime,
+};
+
+mod logger;
+mod cli;
+mod selftest;
+mod files;
+mod fetch;
+mod verify;
+mod duplicates;
+mod explain;
+mod quote;
+mod run;
+mod tty;
+mod info;
+mod for_email;
+mod check;
+mod lsign;
+mod timings;
+mod locate;
+mod recipient_files;
+mod edit;
+mod conflict;
+mod prompt_status;
+mod messages;
+mod due;
+mod audit;
+mod integrate;
+mod capability;
+mod progress;
+mod fsck;
+mod native_host;
+mod batch;
+mod mac;
+mod sign_all;
+mod mock;
+mod manage;
+mod wrap;
+mod key_changes;
+mod seal;
+mod validity;
+mod groups;
+mod rfc822;
+mod generation;
+mod adopt;
+mod pin;
+mod output;
+mod store;
+mod workspace;
+mod provenance;
+mod import;
+mod unverified;
+mod crypt;
+mod define;
+mod protocol;
+mod retire;
+mod directory;
+mod metadata;
+mod roster;
+mod fresh;
+mod naming;
+mod validate;
+mod filter;
+mod strength;
+mod attestation;
+mod options;
+mod keylist;
+mod dedup;
+mod rescue;
+mod cache;
+mod argfile;
+mod metrics;
+mod group;
+mod card;
+mod whoami;
+mod serve;
+mod simulate;
+
+pub use crate::store::{AliasStore, Error, ResolvedKey};
+
+const DEFAULT_CONFIG: &str = include_str!("../config.example.toml");
+const SYSTEM_DIR: &str = "/etc/gpg-alias";
+const NOTATION_ALIAS: &str = "alias@gpg-alias.kyleclemens.com";
+const NOTATION_VERSION: &str = "version@gpg-alias.kyleclemens.com";
+const RECIPIENTS_VAR: &str = "GPG_ALIAS_RECIPIENTS";
+const CONFIG_VAR: &str = "GPG_ALIAS_CONFIG";
+
+// exit statuses scripts can tell apart. any other failure, including bad arguments, exits with 1
+const EXIT_UNKNOWN_ALIAS: i32 = 3;
+const EXIT_UNVERIFIED: i32 = 4;
+const EXIT_KEY_PROBLEM: i32 = 5;
+
+// the command line, as run by the gpg-alias binary. returns the status to exit with
+pub fn cli_main() -> i32 {
+  let exit = inner();
+  simulate::clean_up();
+  timings::report();
+  exit
+}
+
+fn inner() -> i32 {
+  let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+  let matches = if wrap::invoked_as_gpg(&args) {
+    self::cli::app().get_matches_from(wrap::as_wrap_args(args))
+  } else {
+    match argfile::expand(args) {
+      Ok(args) => self::cli::app().get_matches_from(args),
+      Err(exit) => return exit,
+    }
+  };
+
+  let verbosity = matches.occurrences_of("verbose") as i64 - matches.occurrences_of("quiet") as i64;
+  if let Err(e) = logger::set_up_logger(verbosity) {
+    eprintln!("could not set up logger: {}", e);
+    return 1;
+  }
+
+  if matches.is_present("timings") {
+    timings::enable();
+  }
+  tty::set_mode(matches.is_present("batch"), matches.is_present("yes"));
+
+  // independent of the user's environment, so nothing of it is loaded
+  if let ("verify-attestation", Some(sub)) = matches.subcommand() {
+    return self::attestation::run(sub);
+  }
+
+  let config = match timings::time("config load", || load_config(&matches)) {
+    Ok(c) => c,
+    Err(exit) => return exit,
+  };
+  // a relative log file is taken to be next to the config, wherever gpg-alias is run from
+  if let Some(path) = &config.logging.file {
+    let path = config.path.parent().unwrap_or_else(|| Path::new(".")).join(path);
+    if let Err(exit) = logger::log_to_file(&path) {
+      return exit;
+    }
+  }
+
+  // answered from the cache before gpgme is initialised, so prompts stay fast
+  if let ("prompt-status", Some(_)) = matches.subcommand() {
+    return self::prompt_status::run(&config);
+  }
+
+  let gpgme = timings::time("gpgme init", gpgme::init);
+  if let Err(e) = gpgme.check_engine_version(Protocol::OpenPgp) {
+    if !config.signing.unverified_fallback {
+      error!("gpg cannot be used: {}", e);
+      return 1;
+    }
+    return self::unverified::run(&config, &matches);
+  }
+
+  trace!("{:?}", config);
+
+  match matches.subcommand() {
+    ("selftest", Some(sub)) => return self::selftest::run(&config, sub),
+    ("fetch", Some(sub)) => return self::fetch::run(&config, sub),
+    ("verify-all", Some(sub)) => return self::verify::run(&config, sub),
+    ("verify-directory", Some(sub)) => return self::directory::run(&config, sub),
+    ("explain", Some(sub)) => return self::explain::run(&config, sub),
+    ("run", Some(sub)) => return self::run::run(&config, sub),
+    ("info", Some(_)) => return self::info::run(&config),
+    ("for-email", Some(sub)) => return self::for_email::run(&config, sub),
+    ("check", Some(sub)) => return self::check::run(&config, sub),
+    ("recipient-files", Some(sub)) => return self::recipient_files::run(&config, sub),
+    ("due", Some(sub)) => return self::due::run(&config, sub),
+    ("audit", Some(sub)) => return self::audit::run(&config, sub),
+    ("integrate", Some(sub)) => return self::integrate::run(&config, sub),
+    ("rescue", Some(sub)) => return self::rescue::run(&config, sub),
+    ("metrics", Some(sub)) => return self::metrics::run(&config, sub),
+    ("group", Some(sub)) => return self::group::run(&config, sub),
+    ("card", Some(sub)) => return self::card::run(&config, sub),
+    ("whoami", Some(_)) => return self::whoami::run(&config),
+    ("fsck", Some(_)) => return self::fsck::run(&config),
+    ("validate", Some(sub)) => return self::validate::run(&config, sub),
+    ("native-host", Some(_)) => return self::native_host::run(&config),
+    ("serve", Some(sub)) => return self::serve::run(config, &matches, sub),
+    ("add", Some(sub)) => return self::manage::add(&config, sub),
+    ("remove", Some(sub)) => return self::manage::remove(&config, sub),
+    ("rename", Some(sub)) => return self::manage::rename(&config, sub),
+    ("retire", Some(sub)) => return self::retire::retire(&config, sub),
+    ("prune", Some(_)) => return self::retire::prune(&config),
+    ("list", Some(sub)) => return self::manage::list(&config, sub),
+    ("wrap", Some(sub)) => return self::wrap::run(&config, sub),
+    ("filter", Some(sub)) => return self::filter::run(&config, sub),
+    ("seal", Some(_)) => return self::seal::run(&config),
+    ("adopt", Some(sub)) => return self::adopt::run(&config, sub),
+    ("import", Some(sub)) => return self::import::run(&config, sub),
+    ("export", Some(sub)) => return self::import::export(&config, sub),
+    ("encrypt", Some(sub)) => return self::crypt::encrypt(&config, sub),
+    ("decrypt", Some(sub)) => return self::crypt::decrypt(&config, sub),
+    ("sign", Some(sub)) => return self::crypt::sign(&config, sub),
+    _ => {},
+  }
+
+  if matches.is_present("sign-all") {
+    return self::sign_all::run(&config, &matches);
+  }
+
+  let store = AliasStore::new(config);
+  let config = store.config();
+
+  let from_env;
+  let mut aliases: Vec<&str> = matches.values_of("alias").into_iter().flatten().collect();
+  if matches.is_present("from-env") {
+    from_env = match std::env::var(RECIPIENTS_VAR) {
+      Ok(v) => v,
+      Err(e) => {
+        error!("--from-env was given but {} could not be read: {}", RECIPIENTS_VAR, e);
+        return 1;
+      },
+    };
+    aliases.extend(from_env.split_whitespace());
+  }
+  if aliases.is_empty() {
+    if let Some((dir, names)) = workspace::defaults(config) {
+      info!("using the default recipients for workspace {}", dir);
+      aliases.extend(names.iter().map(String::as_str));
+    }
+  }
+  if aliases.is_empty() {
+    error!("no aliases given");
+    return 1;
+  }
+  debug!("aliases requested: {:?}", aliases);
+
+  if matches.is_present("tar") {
+    return self::integrate::print(config, "tar", &aliases, integrate::PASSPHRASE_FILE);
+  }
+  if matches.is_present("borg") {
+    return self::integrate::print(config, "borg", &aliases, integrate::PASSPHRASE_FILE);
+  }
+
+  let shell = match matches.value_of("shell-quote") {
+    Some(name) => quote::Shell::from_name(name),
+    None => config.output.shell_quote,
+  };
+
+  let only_verified = matches.is_present("only-verified");
+  let fetch_missing = matches.is_present("fetch") || config.fetch.missing;
+  let capability = matches.value_of("require-capability").and_then(capability::Capability::from_name);
+  timings::time("keyring change check", || key_changes::check(config, &aliases));
+
+  let annotate = matches.is_present("annotate");
+  let rfc822_to = matches.value_of("output") == Some("rfc822-to");
+  let template = matches.value_of("template");
+  let format = match matches.value_of("output-format").and_then(output::Format::from_name) {
+    Some(f) => f,
+    None if matches.is_present("recipients") => output::Format::Recipients,
+    None => output::Format::Plain,
+  };
+  let recipients = format == output::Format::Recipients;
+  let preview = matches.is_present("preview");
+  let mut out = output::Stdout::default();
+  let mut previews = Vec::new();
+  let mut entries = Vec::new();
+  let mut header_keys = Vec::new();
+  let mut printed = 0;
+  let mut recipients_protocol: Option<protocol::KeyProtocol> = None;
+  // aliases that failed, why, and the status to exit with. the rest are still tried, so one run
+  // reports everything that needs fixing
+  let mut failures: Vec<(&str, String, i32)> = Vec::new();
+
+  'aliases: for (i, alias) in aliases.iter().enumerate() {
+    debug!("{} - {}", i, alias);
+    batch::set_pending(aliases[i..].iter()
+      .filter(|a| config.aliases.contains_key(**a) || config.groups.contains_key(**a))
+      .map(ToString::to_string)
+      .collect());
+
+    // fetched before resolving, so a new attestation pins the key that was fetched
+    if fetch_missing && fetch::missing(config, alias).is_err() {
+      failures.push((alias, "its key could not be fetched".to_string(), EXIT_KEY_PROBLEM));
+      continue;
+    }
+
+    let mut status = if config.signing.enabled { "verified" } else { "unsigned" };
+    let keys = match lookup(config, alias) {
+      Name::Group => match timings::time(format!("expand `{}`", alias), || groups::expand(config, alias, only_verified)) {
+        Ok(keys) => keys.into_iter().map(Cow::Owned).collect(),
+        Err(_) if only_verified => {
+          warn!("skipping group `{}`: it could not be verified", alias);
+          continue;
+        },
+        Err(_) => {
+          failures.push((alias, failure_reason(EXIT_UNVERIFIED), EXIT_UNVERIFIED));
+          continue;
+        },
+      },
+      Name::Defined(key) => {
+        if only_verified {
+          warn!("skipping `{}`: it was defined on the command line, so it cannot be verified", alias);
+          continue;
+        }
+        status = "defined";
+        vec![Cow::Borrowed(key)]
+      },
+      Name::Address => {
+        // checked before locating, so a key that would be thrown away is never looked up
+        if only_verified {
+          warn!("skipping `{}`: it is not an alias, so it cannot be verified", alias);
+          continue;
+        }
+        match locate::locate(alias) {
+          Ok(fingerprint) => {
+            status = "located";
+            vec![Cow::Owned(fingerprint)]
+          },
+          Err(exit) => {
+            failures.push((alias, "it could not be located".to_string(), exit));
+            continue;
+          },
+        }
+      },
+      Name::Alias => {
+        let res = timings::time(format!("resolve `{}`", alias), || if only_verified {
+          store.verified_key(alias).map_err(|e| e.exit_code())
+        } else {
+          resolve(config, alias)
+        });
+        match res {
+          Ok(k) => vec![k],
+          Err(_) if only_verified && config.aliases.contains_key(*alias) => {
+            warn!("skipping alias `{}`: it could not be verified", alias);
+            continue;
+          },
+          // anything that stops a configured alias resolving is about its attestation
+          Err(_) if config.aliases.contains_key(*alias) => {
+            failures.push((alias, failure_reason(EXIT_UNVERIFIED), EXIT_UNVERIFIED));
+            continue;
+          },
+          Err(exit) => {
+            failures.push((alias, failure_reason(exit), exit));
+            continue;
+          },
+        }
+      },
+    };
+
+    debug!("resolved `{}` to {} ({})", alias, keys.join(", "), status);
+    if config.audit.resolutions {
+      audit::record(config, &format!("resolved `{}` to {}", alias, keys.join(", ")));
+    }
+
+    for key_id in keys {
+      if validity::check(config, alias, &key_id).is_err() {
+        failures.push((alias, failure_reason(EXIT_KEY_PROBLEM), EXIT_KEY_PROBLEM));
+        continue 'aliases;
+      }
+
+      if let Some(capability) = capability {
+        match capability.check(&key_id) {
+          Ok(true) => {},
+          Ok(false) => {
+            warn!("skipping `{}` from `{}`: the key cannot {}", key_id, alias, capability.verb());
+            continue;
+          },
+          Err(_) => {
+            failures.push((alias, failure_reason(EXIT_KEY_PROBLEM), EXIT_KEY_PROBLEM));
+            continue 'aliases;
+          },
+        }
+      }
+
+      // gpg and gpgsm are separate programs, so one set of `-r` arguments can only be for one
+      let key_protocol = protocol::of(&key_id);
+      match recipients_protocol {
+        Some(p) if recipients && p != key_protocol => {
+          let reason = format!("it is an {} key, but earlier recipients are {}: encrypt to them separately", key_protocol.name(), p.name());
+          failures.push((alias, reason, 1));
+          continue 'aliases;
+        },
+        _ => recipients_protocol = Some(key_protocol),
+      }
+
+      if preview {
+        previews.push(preview_of(config, alias, &key_id, status));
+      }
+
+      if rfc822_to {
+        header_keys.push(key_id.to_string());
+        // only the header is wanted unless `-r` asks for the recipients too
+        if !recipients && !annotate {
+          continue;
+        }
+      }
+
+      if format == output::Format::Json || template.is_some() {
+        let entry = output::Entry {
+          alias: alias.to_string(),
+          key: key_id.to_string(),
+          fingerprint: pin::fingerprint(&key_id),
+          protocol: key_protocol.name(),
+          status,
+        };
+        match template {
+          Some(template) => out.println(&output::render(template, &entry)),
+          None => entries.push(entry),
+        }
+        continue;
+      }
+
+      let output = match shell {
+        Some(shell) if recipients => format!("-r {}", shell.quote(&key_id)),
+        _ if recipients => format!("-r {}", key_id),
+        _ => key_id.into_owned(),
+      };
+
+      // annotated output is one line per key, so scripts can tell which alias each came from
+      if annotate {
+        out.println(&format!("{}: {}", alias, output));
+      } else if recipients {
+        if printed > 0 {
+          out.print(" ");
+        }
+        out.print(&output);
+        printed += 1;
+      } else {
+        out.println(&output);
+      }
+    }
+  }
+
+  if !failures.is_empty() {
+    error!("{} of {} alias(es) could not be resolved, so nothing was output:", failures.len(), aliases.len());
+    for (alias, reason, _) in &failures {
+      error!("  `{}`: {}", alias, reason);
+    }
+    return failures[0].2;
+  }
+
+  if format == output::Format::Json && template.is_none() {
+    match serde_json::to_string_pretty(&entries) {
+      Ok(json) => out.println(&json),
+      Err(e) => {
+        error!("could not serialise output: {}", e);
+        return 1;
+      },
+    }
+  }
+
+  if rfc822_to {
+    let header = match rfc822::to_header(&header_keys) {
+      Ok(h) => h,
+      Err(exit) => return exit,
+    };
+    if printed > 0 {
+      out.println("");
+    }
+    out.println(&header);
+  }
+
+  // on stderr, so it never ends up in what a script captures
+  if preview {
+    eprintln!("encrypting to: {}", previews.join(", "));
+  }
+  if let Err(e) = out.release() {
+    error!("could not write to stdout: {}", e);
+    return 1;
+  }
+
+  0
+}
+
+// what a name to resolve stands for. everything that turns names into keys looks them up in this
+// order, so a name means the same to every command
+enum Name<'a> {
+  Group,
+  Defined(&'a str),
+  Address,
+  Alias,
+}
+
+fn lookup<'a>(config: &'a Config, name: &str) -> Name<'a> {
+  if config.groups.contains_key(name) {
+    Name::Group
+  } else if let Some(key) = define::key(config, name) {
+    Name::Defined(key)
+  } else if locate::applies(config, name) {
+    Name::Address
+  } else {
+    Name::Alias
+  }
+}
+
+// what each exit status says about an alias, for the summary of those that failed
+fn failure_reason(exit: i32) -> String {
+  match exit {
+    EXIT_UNKNOWN_ALIAS => "it is not configured",
+    EXIT_UNVERIFIED => "its attestation is missing, doesn't verify, or wasn't confirmed",
+    EXIT_KEY_PROBLEM => "its key is missing from the keyring, unusable, or fails the validity policy",
+    _ => "it could not be resolved",
+  }.to_string()
+}
+
+// who a key belongs to and how it was reached, for people to check before anything is sent
+fn preview_of(config: &Config, alias: &str, key_id: &str, status: &str) -> String {
+  let owner = pin::primary_uid(key_id).unwrap_or_else(|| key_id.to_string());
+  let via = if config.groups.contains_key(alias) {
+    format!("via group {}", alias)
+  } else {
+    match status {
+      "defined" => format!("defined on the command line as {}", alias),
+      "located" => format!("located for {}", alias),
+      _ => format!("alias {}", alias),
+    }
+  };
+  format!("{} ({})", owner, via)
+}
+
+struct LoadOptions {
+  path: Option<PathBuf>,
+  profile: Option<String>,
+  data_dir: Option<PathBuf>,
+  no_write: bool,
+  simulate: bool,
+  mock: bool,
+  check_seal: bool,
+  // only the command line creates a default config when there isn't one
+  create_default: bool,
+}
+
+fn load_config(matches: &ArgMatches) -> Result<Config, i32> {
+  let defined = define::parse(matches)?;
+  let mut config = load(LoadOptions {
+    path: matches.value_of_os("config").map(PathBuf::from),
+    profile: matches.value_of("profile").map(ToString::to_string),
+    data_dir: matches.value_of_os("data-dir").map(PathBuf::from),
+    no_write: matches.is_present("no-write"),
+    simulate: matches.is_present("simulate"),
+    mock: matches.value_of("backend") == Some("mock"),
+    // sealing is how a changed config is accepted again, so it can't require the old seal
+    check_seal: matches.subcommand_name() != Some("seal"),
+    create_default: true,
+  })?;
+
+  config.defined = defined;
+  config.force = matches.is_present("force");
+  config.fresh = matches.is_present("fresh");
+  define::check(&config)?;
+  Ok(config)
+}
+
+fn load(options: LoadOptions) -> Result<Config, i32> {
+  let no_write = options.no_write;
+  let profile = options.profile.as_deref();
+  let valid_profile = |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if let Some(p) = profile.filter(|p| !valid_profile(p)) {
+    error!("invalid profile name `{}`: use letters, digits, `-` and `_`", p);
+    return Err(1);
+  }
+
+  // an explicit --profile beats a config path from the environment
+  let from_env = std::env::var_os(CONFIG_VAR).filter(|p| !p.is_empty() && profile.is_none());
+  let path = options.path.clone().or_else(|| from_env.map(PathBuf::from));
+  let config_path = match path {
+    Some(path) => {
+      if !path.exists() {
+        error!("config file {} does not exist", path.to_string_lossy());
+        return Err(1);
+      }
+      path
+    },
+    None => default_config_path(profile, no_write || !options.create_default)?,
+  };
+
+  let config_file = match std::fs::read_to_string(&config_path) {
+    Ok(s) => s,
+    Err(e) => {
+      error!("could not read {}: {}", config_path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  let simulate = options.simulate;
+  let data_dir = match options.data_dir.clone() {
+    Some(path) => path,
+    // simulations start from an empty data directory of their own, so nothing real is touched
+    None if simulate => simulate::data_dir()?,
+    None => match dirs::data_dir() {
+      // each profile's attestations are kept apart, so the same alias in two profiles can't clash
+      Some(d) => match profile {
+        Some(p) => d.join("gpg-alias").join("profiles").join(p),
+        None => d.join("gpg-alias"),
+      },
+      None => {
+        error!("could not find data dir: pass --data-dir");
+        return Err(1);
+      },
+    },
+  };
+
+  if simulate || options.mock {
+    // mock signatures in the real data directory would replace real ones as they're re-attested
+    if !simulate && options.data_dir.is_none() {
+      error!("the mock backend needs its own data directory: pass --data-dir or use --simulate");
+      return Err(1);
+    }
+    mock::enable();
+  }
+
+  if options.check_seal {
+    timings::time("seal check", || seal::verify(&config_path, &data_dir, config_file.as_bytes()))?;
+  }
+
+  let duplicates = duplicates::find(&config_file);
+  if !duplicates.is_empty() {
+    for dup in &duplicates {
+      let defs: Vec<String> = dup.definitions.iter()
+        .map(|(line, value)| format!("line {} (`{}`)", line, value))
+        .collect();
+      error!("alias `{}` is defined more than once: {}", dup.name, defs.join(", "));
+    }
+    return Err(1);
+  }
+  for dup in duplicates::case_variants(&config_file) {
+    let defs: Vec<String> = dup.names.iter().zip(&dup.definitions)
+      .map(|(name, (line, value))| format!("`{}` on line {} (`{}`)", name, line, value))
+      .collect();
+    warn!("aliases differ only in case, so one is probably a mistake: {}", defs.join(", "));
+  }
+
+  let mut config: Config = match toml::from_str(&config_file) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not parse config file: {}", e);
+      return Err(1);
+    },
+  };
+  config.path = config_path;
+  config.no_write = no_write;
+  config.simulate = simulate;
+  config.data_dir = data_dir;
+
+  let config_dir = config.path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+  config.prompts.load(&config_dir)?;
+  audit::check_sinks(&config.audit)?;
+  config.naming.compile()?;
+
+  load_system_config(&mut config)?;
+  protocol::register_config(&config);
+  metadata::check_config(&config)?;
+
+  // local certifications are an OpenPGP thing, so they can't attest S/MIME aliases
+  if config.signing.method == Method::Lsign {
+    let mut cms: Vec<&String> = config.aliases.iter()
+      .filter(|(_, a)| a.protocol() == protocol::KeyProtocol::Cms)
+      .map(|(n, _)| n)
+      .collect();
+    if !cms.is_empty() {
+      cms.sort();
+      for name in cms {
+        error!("alias `{}` uses S/MIME, which the lsign method can't attest: use method = \"file\" or \"hmac\"", name);
+      }
+      return Err(1);
+    }
+  }
+
+  let mut clashes: Vec<&String> = config.groups.keys().filter(|g| config.aliases.contains_key(*g)).collect();
+  if !clashes.is_empty() {
+    clashes.sort();
+    for name in clashes {
+      error!("`{}` is both an alias and a group", name);
+    }
+    return Err(1);
+  }
+
+  Ok(config)
+}
+
+fn default_config_path(profile: Option<&str>, no_write: bool) -> Result<PathBuf, i32> {
+  let config_dir = match dirs::config_dir() {
+    Some(c) => c.join("gpg-alias"),
+    None => {
+      error!("could not find a config directory: pass --config");
+      return Err(1);
+    },
+  };
+  let config_path = match profile {
+    Some(p) => config_dir.join(format!("gpg-alias.{}.toml", p)),
+    None => config_dir.join("gpg-alias.toml"),
+  };
+
+  if no_write {
+    if !config_path.exists() {
+      error!("no config at {} and --no-write prevents creating one: create it or pass --config", config_path.to_string_lossy());
+      return Err(1);
+    }
+    return Ok(config_path);
+  }
+
+  if let Err(e) = files::create_dir_all(&config_dir) {
+    error!("could not create {}: {}", config_dir.to_string_lossy(), e);
+    sandbox_hint();
+    return Err(1);
+  }
+
+  if !config_path.exists() {
+    let mut config_file = match files::create_file(&config_path) {
+      Ok(f) => f,
+      Err(e) => {
+        error!("could not create {}: {}", config_path.to_string_lossy(), e);
+        sandbox_hint();
+        return Err(1);
+      },
+    };
+    if let Err(e) = config_file.write_all(DEFAULT_CONFIG.as_bytes()) {
+      error!("could not write default config: {}", e);
+      return Err(1);
+    }
+  }
+
+  Ok(config_path)
+}
+
+fn sandbox() -> Option<&'static str> {
+  if std::env::var_os("FLATPAK_ID").is_some() || Path::new("/.flatpak-info").exists() {
+    return Some("Flatpak");
+  }
+  if std::env::var_os("SNAP").is_some() {
+    return Some("Snap");
+  }
+  None
+}
+
+fn sandbox_hint() {
+  match sandbox() {
+    Some(name) => error!("gpg-alias appears to be running inside {}: pass --config and --data-dir with paths the sandbox can access, and --no-write to avoid creating anything", name),
+    None => error!("pass --config and --data-dir to use other locations, or --no-write to avoid creating anything"),
+  }
+}
+
+// aliases from the system config sit beneath the user's own, which take precedence
+fn load_system_config(config: &mut Config) -> Result<(), i32> {
+  let path = Path::new(SYSTEM_DIR).join("gpg-alias.toml");
+  if !path.exists() {
+    return Ok(());
+  }
+
+  let system_file = match std::fs::read_to_string(&path) {
+    Ok(s) => s,
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let system: SystemConfig = match toml::from_str(&system_file) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not parse system config file: {}", e);
+      return Err(1);
+    },
+  };
+
+  config.system.key = system.signing.map(|s| s.key);
+  for (name, alias) in system.aliases {
+    if config.aliases.contains_key(&name) {
+      debug!("user alias `{}` shadows the system alias", name);
+      config.system.shadowed.insert(name, alias.key().to_string());
+      continue;
+    }
+    config.system.aliases.insert(name.clone());
+    config.aliases.insert(name, alias);
+  }
+  config.system.path = Some(path);
+
+  Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+  #[serde(skip)]
+  path: PathBuf,
+  #[serde(skip)]
+  data_dir: PathBuf,
+  #[serde(skip)]
+  no_write: bool,
+  #[serde(skip)]
+  simulate: bool,
+  // aliases given with --define for this run only
+  #[serde(skip)]
+  defined: HashMap<String, String>,
+  // expired aliases are used anyway, with a warning
+  #[serde(skip)]
+  force: bool,
+  // every alias is verified afresh, as if it had require_fresh set
+  #[serde(skip)]
+  fresh: bool,
+  #[serde(skip)]
+  system: System,
+  signing: Signing,
+  #[serde(default)]
+  output: Output,
+  #[serde(default)]
+  locate: locate::Locate,
+  #[serde(default)]
+  prompts: messages::Prompts,
+  #[serde(default)]
+  validity: validity::Validity,
+  #[serde(default)]
+  audit: audit::Audit,
+  #[serde(default)]
+  fetch: fetch::Fetch,
+  #[serde(default)]
+  logging: logger::Logging,
+  #[serde(default)]
+  naming: naming::Naming,
+  #[serde(default)]
+  deduplication: dedup::Deduplication,
+  #[serde(default)]
+  cache: cache::Cache,
+  aliases: HashMap<String, Alias>,
+  // group name to member aliases, groups, key IDs or `@name`s given with --define
+  #[serde(default)]
+  groups: HashMap<String, Vec<String>>,
+  // group name to gpg options encrypting to the group needs, as aliases have in their tables
+  #[serde(default)]
+  group_options: HashMap<String, Vec<String>>,
+  // directory to the aliases and groups resolved when none are given there
+  #[serde(default)]
+  workspaces: HashMap<String, Vec<String>>,
+}
+
+impl Config {
+  // loads the user's config from where the command line would find it, without ever creating one
+  pub fn load() -> Result<Config, Error> {
+    Config::load_options(None, None)
+  }
+
+  pub fn load_from(path: &Path) -> Result<Config, Error> {
+    Config::load_options(Some(path.to_path_buf()), None)
+  }
+
+  // as --data-dir does, for tools that keep attestations apart from the command line's
+  pub fn load_with_data_dir(path: &Path, data_dir: &Path) -> Result<Config, Error> {
+    Config::load_options(Some(path.to_path_buf()), Some(data_dir.to_path_buf()))
+  }
+
+  fn load_options(path: Option<PathBuf>, data_dir: Option<PathBuf>) -> Result<Config, Error> {
+    let options = LoadOptions {
+      path,
+      profile: None,
+      data_dir,
+      no_write: false,
+      simulate: false,
+      mock: false,
+      check_seal: true,
+      create_default: false,
+    };
+    load(options).map_err(|_| Error::Config)
+  }
+}
+
+#[derive(Debug, Default)]
+struct System {
+  path: Option<PathBuf>,
+  key: Option<String>,
+  aliases: HashSet<String>,
+  // system aliases hidden by a user alias of the same name, with the key they point to
+  shadowed: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SystemConfig {
+  signing: Option<SystemSigning>,
+  #[serde(default)]
+  aliases: HashMap<String, Alias>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SystemSigning {
+  key: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Output {
+  shell_quote: Option<quote::Shell>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Alias {
+  Key(String),
+  Entry(Box<AliasEntry>),
+}
+
+#[derive(Debug, Deserialize)]
+struct AliasEntry {
+  #[serde(alias = "id")]
+  key: String,
+  // S/MIME aliases name an X.509 certificate, looked up with gpgsm
+  protocol: Option<protocol::KeyProtocol>,
+  keyserver: Option<String>,
+  wkd: Option<String>,
+  reverify_days: Option<u64>,
+  on_problem: Option<validity::Policy>,
+  verified_via: Option<provenance::Via>,
+  verified_on: Option<String>,
+  verified_by: Option<String>,
+  // set by `retire`: the alias is removed by `prune` once this date has passed
+  retire_after: Option<String>,
+  comment: Option<String>,
+  #[serde(default)]
+  tags: Vec<String>,
+  // the alias itself stops resolving after this date, whatever its key's expiry
+  expires: Option<String>,
+  #[serde(default)]
+  require_fresh: bool,
+  // gpg options encrypting to this alias needs, such as `--throw-keyids`
+  #[serde(default)]
+  options: Vec<String>,
+  // the address signing as this alias records as the signer's, which its key must have a user ID for
+  sender: Option<String>,
+}
+
+impl Alias {
+  fn key(&self) -> &str {
+    match self {
+      Alias::Key(k) => k,
+      Alias::Entry(e) => &e.key,
+    }
+  }
+
+  fn entry(&self) -> Option<&AliasEntry> {
+    match self {
+      Alias::Key(_) => None,
+      Alias::Entry(e) => Some(e.as_ref()),
+    }
+  }
+
+  fn protocol(&self) -> protocol::KeyProtocol {
+    self.entry().and_then(|e| e.protocol).unwrap_or_default()
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct Signing {
+  enabled: bool,
+  // not needed by the hmac method
+  #[serde(default)]
+  key: String,
+  #[serde(default)]
+  method: Method,
+  #[serde(default)]
+  offer_lsign: bool,
+  policy_url: Option<String>,
+  #[serde(default)]
+  require_notations: bool,
+  max_age_days: Option<u64>,
+  // print unverified aliases, with warnings, when gpg can't be used at all
+  #[serde(default)]
+  unverified_fallback: bool,
+}
+
+fn resolve<'a>(config: &'a Config, alias: &str) -> Result<Cow<'a, str>, i32> {
+  let key_id = match config.aliases.get(alias) {
+    Some(a) => a.key(),
+    None => {
+      error!("no such alias found");
+      return Err(EXIT_UNKNOWN_ALIAS);
+    },
+  };
+  retire::warn(config, alias);
+  metadata::check(config, alias)?;
+  fresh::verify(config, alias)?;
+
+  if config.signing.enabled {
+    // the config was reverted to the attested key while resolving a conflict
+    if let Some(attested) = check_signature(config, alias, key_id)? {
+      return Ok(Cow::Owned(pin::pinned(&attested).into_owned()));
+    }
+  }
+
+  Ok(pin::pinned(key_id))
+}
+
+// the fingerprint the key was attested as, where there is one, so gpg can't be handed another key
+// matching the same short ID
+fn resolve_verified<'a>(config: &'a Config, alias: &str) -> Result<Cow<'a, str>, i32> {
+  let key_id = match config.aliases.get(alias) {
+    Some(a) => a.key(),
+    None => {
+      error!("no such alias found");
+      return Err(EXIT_UNKNOWN_ALIAS);
+    },
+  };
+  retire::warn(config, alias);
+  metadata::check(config, alias)?;
+  fresh::verify(config, alias)?;
+
+  if config.signing.enabled {
+    verify_attestation(config, alias, key_id)?;
+  }
+
+  Ok(pin::pinned(key_id))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Method {
+  File,
+  Lsign,
+  Hmac,
+}
+
+impl Default for Method {
+  fn default() -> Self {
+    Method::File
+  }
+}
+
+fn gpg_program() -> Result<String, i32> {
+  let ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+
+  match ctx.engine_info().path() {
+    Ok(p) => Ok(p.to_string()),
+    Err(_) => {
+      error!("could not determine the path to gpg");
+      Err(1)
+    },
+  }
+}
+
+fn data_dir(config: &Config) -> Result<&Path, i32> {
+  if config.data_dir.is_dir() {
+    return Ok(&config.data_dir);
+  }
+
+  if config.no_write {
+    error!("data directory {} does not exist and --no-write prevents creating it: create it or pass --data-dir", config.data_dir.to_string_lossy());
+    return Err(1);
+  }
+
+  if let Err(e) = files::create_dir_all(&config.data_dir) {
+    error!("could not create {}: {}", config.data_dir.to_string_lossy(), e);
+    sandbox_hint();
+    return Err(1);
+  }
+
+  Ok(&config.data_dir)
+}
+
+fn signature_path(config: &Config, alias: &str) -> Result<PathBuf, i32> {
+  naming::check_safe(alias)?;
+  Ok(data_dir(config)?.join(format!("{}.asc", alias)))
+}
+
+fn attestation_exists(config: &Config, alias: &str, id: &str) -> Result<bool, i32> {
+  if config.system.aliases.contains(alias) {
+    return Ok(system_signature_path(alias).exists());
+  }
+
+  match config.signing.method {
+    Method::File => Ok(signature_path(config, alias)?.exists()),
+    Method::Lsign => lsign::is_certified(config, id),
+    Method::Hmac => Ok(mac::attestation_path(config, alias)?.exists()),
+  }
+}
+
+// when the alias was last attested, or None if it never was
+fn attested_at(config: &Config, alias: &str, id: &str) -> Result<Option<SystemTime>, i32> {
+  let path = if config.system.aliases.contains(alias) {
+    system_signature_path(alias)
+  } else {
+    match config.signing.method {
+      Method::File => signature_path(config, alias)?,
+      Method::Lsign => return lsign::certified_at(config, id),
+      Method::Hmac => mac::attestation_path(config, alias)?,
+    }
+  };
+
+  match std::fs::metadata(&path).and_then(|m| m.modified()) {
+    Ok(t) => Ok(Some(t)),
+    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => {
+      error!("could not read the modification time of {}: {}", path.to_string_lossy(), e);
+      Err(1)
+    },
+  }
+}
+
+fn verify_attestation(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  if config.system.aliases.contains(alias) {
+    return verify_system_attestation(config, alias, id);
+  }
+
+  match config.signing.method {
+    Method::File => {
+      let sig_path = signature_path(config, alias)?;
+      if !sig_path.exists() {
+        error!("no signature for alias `{}`", alias);
+        return Err(1);
+      }
+      cache::verified(config, alias, id, &sig_path, || {
+        check_existing_signature(&config.signing.key, id, metadata::expires(config, alias), required_notation(config, alias), sig_path.clone()).map(|_| ())
+      })?;
+    },
+    Method::Lsign => if !lsign::is_certified(config, id)? {
+      error!("key `{}` for alias `{}` is not locally signed by the signing key", id, alias);
+      return Err(1);
+    },
+    Method::Hmac => {
+      if !mac::attestation_path(config, alias)?.exists() {
+        error!("no attestation for alias `{}`", alias);
+        return Err(1);
+      }
+      let attested = mac::attested_value(config, alias)?;
+      if attested.id != id {
+        error!("invalid attestation: key does not match (`{}` != `{}`)", attested.id, id);
+        return Err(1);
+      }
+      pin::check_expiry(attested.expires.as_deref(), metadata::expires(config, alias))?;
+    },
+  }
+
+  pin::require_unchanged(alias, id)
+}
+
+// system aliases are signed by the administrator, so they can only ever be verified
+fn verify_system_attestation(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  let key = match &config.system.key {
+    Some(k) => k,
+    None => {
+      error!("system alias `{}` cannot be verified: the system config has no signing key", alias);
+      return Err(1);
+    },
+  };
+
+  let sig_path = system_signature_path(alias);
+  if !sig_path.exists() {
+    error!("no system signature for alias `{}`: ask your administrator to sign it", alias);
+    return Err(1);
+  }
+
+  check_existing_signature(key, id, metadata::expires(config, alias), None, sig_path)?;
+  pin::require_unchanged(alias, id)
+}
+
+fn system_signature_path(alias: &str) -> PathBuf {
+  Path::new(SYSTEM_DIR).join("signatures").join(format!("{}.asc", alias))
+}
+
+// returns the attested key if the config disagreed with it and was reverted
+fn check_signature(config: &Config, alias: &str, id: &str) -> Result<Option<String>, i32> {
+  if config.system.aliases.contains(alias) {
+    return verify_system_attestation(config, alias, id).map(|_| None);
+  }
+
+  if config.signing.method == Method::Lsign {
+    if !lsign::is_certified(config, id)? {
+      create_certification(config, alias, id)?;
+    }
+    return Ok(None);
+  }
+
+  if config.signing.method == Method::Hmac {
+    if !mac::attestation_path(config, alias)?.exists() {
+      confirm_new_attestation(config, alias, id)?;
+      mac::attest(config, alias, id)?;
+      return Ok(None);
+    }
+
+    let attested = mac::attested_value(config, alias)?;
+    if attested.id == id {
+      reconfirm_expiry(config, alias, id, attested.expires.as_deref())?;
+      pin::reconfirm(config, alias, id)?;
+      return Ok(None);
+    }
+    return conflict::resolve(config, alias, id, &attested.id);
+  }
+
+  let alias_sig = signature_path(config, alias)?;
+  if !alias_sig.exists() {
+    return create_signature(config, alias, id, alias_sig).map(|_| None);
+  }
+
+  let attested = signed_content(&config.signing.key, read_signature(&alias_sig)?, required_notation(config, alias))?;
+  if attested.id == id {
+    reconfirm_expiry(config, alias, id, attested.expires.as_deref())?;
+    pin::reconfirm(config, alias, id)?;
+    return Ok(None);
+  }
+
+  conflict::resolve(config, alias, id, &attested.id)
+}
+
+// an alias's expiry changed in the config since it was attested, so the new one is confirmed and
+// attested before it's trusted
+fn reconfirm_expiry(config: &Config, alias: &str, id: &str, attested: Option<&str>) -> Result<(), i32> {
+  let configured = metadata::expires(config, alias);
+  if attested == configured {
+    return Ok(());
+  }
+
+  let (old, new) = (attested.unwrap_or("never"), configured.unwrap_or("never"));
+  warn!("the expiry of alias `{}` was attested as {}, but is now {}", alias, old, new);
+  let args = [("alias", alias), ("old", old), ("new", new)];
+  if !confirm_batch(config, "confirm_expiry_change", &args)? {
+    error!("the new expiry of alias `{}` was not confirmed", alias);
+    return Err(1);
+  }
+  attest(config, alias, id)
+}
+
+fn required_notation<'a>(config: &Config, alias: &'a str) -> Option<&'a str> {
+  if config.signing.require_notations {
+    Some(alias)
+  } else {
+    None
+  }
+}
+
+fn check_existing_signature(signing_key: &str, id: &str, expires: Option<&str>, alias: Option<&str>, sig_path: PathBuf) -> Result<bool, i32> {
+  verify_signed(signing_key, read_signature(&sig_path)?, id, expires, alias)
+}
+
+fn read_signature(sig_path: &Path) -> Result<Vec<u8>, i32> {
+  let mut file = match File::open(sig_path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not open signature file {}: {}", sig_path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  let mut signed = Vec::new();
+  if let Err(e) = file.read_to_end(&mut signed) {
+    error!("could not read signature file: {}", e);
+    return Err(1);
+  }
+
+  Ok(signed)
+}
+
+fn verify_signed(signing_key: &str, signed: Vec<u8>, expected: &str, expires: Option<&str>, alias: Option<&str>) -> Result<bool, i32> {
+  let content = signed_content(signing_key, signed, alias)?;
+
+  if content.id != expected {
+    error!("invalid signed content: key does not match (`{}` != `{}`)", content.id, expected);
+    return Err(1);
+  }
+  pin::check_expiry(content.expires.as_deref(), expires)?;
+
+  Ok(true)
+}
+
+// checks the signature and returns what it attests, leaving the comparison to the caller
+fn signed_content(signing_key: &str, signed: Vec<u8>, alias: Option<&str>) -> Result<pin::Vouched, i32> {
+  let verified = verify_signature(signed)?;
+
+  if let Some(alias) = alias {
+    match verified.alias {
+      Some(ref n) if n == alias => {},
+      Some(n) => {
+        error!("signature was made for alias `{}`, not `{}`", n, alias);
+        return Err(1);
+      },
+      None => {
+        error!("signature is missing the required alias notation");
+        return Err(1);
+      },
+    }
+  }
+
+  check_signer(signing_key, &verified.signer)?;
+
+  let attested = pin::parse(&verified.content);
+  pin::check(&attested)?;
+  Ok(attested.vouched())
+}
+
+// checks a detached signature over data, such as the sealed config
+fn verify_detached(signing_key: &str, signature: Vec<u8>, data: &[u8]) -> Result<(), i32> {
+  let signer = if mock::is_enabled() {
+    mock::verify_detached(&signature, data)?
+  } else {
+    let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+      Ok(c) => c,
+      Err(e) => {
+        error!("could not create gpgme context: {}", e);
+        return Err(1);
+      },
+    };
+    let verify_res = match ctx.verify_detached(signature, data) {
+      Ok(res) => res,
+      Err(e) => {
+        error!("could not verify signature: {}", e);
+        return Err(1);
+      },
+    };
+    let sigs: Vec<Signature> = verify_res.signatures().collect();
+    signature_fingerprint(&sigs)?
+  };
+
+  check_signer(signing_key, &signer)
+}
+
+fn check_signer(signing_key: &str, signer: &str) -> Result<(), i32> {
+  let expected = if mock::is_enabled() {
+    vec![mock::fingerprint(signing_key)]
+  } else {
+    timings::time("signing key lookup", || key_fingerprints(signing_key))?
+  };
+  if !expected.iter().any(|f| f == signer) {
+    error!("signature made by wrong key (got {})", signer);
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+fn verify_signature(signed: Vec<u8>) -> Result<Verified, i32> {
+  if mock::is_enabled() {
+    mock::verify(&signed)
+  } else {
+    verify_opaque(signed)
+  }
+}
+
+// what a single valid signature attests, before checking who made it
+struct Verified {
+  content: String,
+  signer: String,
+  alias: Option<String>,
+}
+
+fn verify_opaque(signed: Vec<u8>) -> Result<Verified, i32> {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not created gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let mut plaintext = Vec::new();
+  let verify_res = match ctx.verify_opaque(signed, &mut plaintext) {
+    Ok(res) => res,
+    Err(e) => {
+      error!("could not verify signature: {}", e);
+      return Err(1);
+    },
+  };
+
+  let plaintext_str = match std::str::from_utf8(&plaintext) {
+    Ok(s) => s.trim_end(),
+    Err(e) => {
+      error!("could not create utf-8 string from signed data: {}", e);
+      return Err(1);
+    },
+  };
+
+  let sigs: Vec<Signature> = verify_res.signatures().collect();
+  let fingerprint = signature_fingerprint(&sigs)?;
+
+  Ok(Verified {
+    content: plaintext_str.to_string(),
+    signer: fingerprint,
+    alias: sigs[0].notations()
+      .find(|n| n.name() == Ok(NOTATION_ALIAS))
+      .and_then(|n| n.value().ok().map(ToString::to_string)),
+  })
+}
+
+// the fingerprint of the key that made the one valid signature there must be
+fn signature_fingerprint(sigs: &[Signature]) -> Result<String, i32> {
+  if sigs.len() != 1 {
+    error!("invalid number of signatures: expected 1, got {}", sigs.len());
+    return Err(1);
+  }
+
+  if !sigs[0].summary().contains(SignatureSummary::VALID) {
+    error!("invalid signature");
+    return Err(1);
+  }
+
+  match sigs[0].fingerprint() {
+    Ok(f) => Ok(f.to_string()),
+    Err(_) => {
+      error!("invalid fingerprint on key signature was made by");
+      Err(1)
+    },
+  }
+}
+
+// the fingerprints of a key and all of its subkeys, any of which may have made a signature
+fn key_fingerprints(key_id: &str) -> Result<Vec<String>, i32> {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let key = match ctx.get_key(key_id) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not get signing key: {}", e);
+      return Err(1);
+    },
+  };
+
+  Ok(key.fingerprint().ok().into_iter()
+    .chain(key.subkeys().filter_map(|k| k.fingerprint().ok()))
+    .map(ToString::to_string)
+    .collect())
+}
+
+fn confirm_new_attestation(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  let args = [("alias", alias), ("id", id)];
+  warn!("no signature for alias `{}`", alias);
+  info!("{}", config.prompts.text("new_attestation_notice", &args));
+  info!("{}", config.prompts.text("new_attestation_hint", &args));
+  warn!("{}", config.prompts.text("new_attestation_target", &args));
+
+  if !confirm_batch(config, "confirm_key", &args)? {
+    error!("no signature found for alias `{}` and creating a new signature was not authorised", alias);
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+fn create_certification(config: &Config, alias: &str, id: &str) -> Result<bool, i32> {
+  confirm_new_attestation(config, alias, id)?;
+  attest(config, alias, id)?;
+
+  Ok(true)
+}
+
+// attests with the configured method without asking, for callers that already had the key confirmed
+fn attest(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  match config.signing.method {
+    Method::File => {
+      if config.no_write {
+        error!("--no-write prevents signing alias `{}`", alias);
+        return Err(1);
+      }
+      write_signature(config, alias, id, signature_path(config, alias)?).map(|_| ())
+    },
+    Method::Lsign => {
+      // a local certification is written into the keyring, which --no-write covers as well
+      if config.no_write {
+        error!("--no-write prevents locally signing the key for alias `{}`", alias);
+        return Err(1);
+      }
+      info!("locally signing key for alias `{}`. you may need to enter your pgp passphrase", alias);
+      tty::ensure_gpg_tty();
+      lsign::certify(config, id)
+    },
+    Method::Hmac => mac::attest(config, alias, id),
+  }
+}
+
+fn create_signature(config: &Config, alias: &str, id: &str, sig_path: PathBuf) -> Result<bool, i32> {
+  if config.no_write {
+    error!("no signature for alias `{}` and --no-write prevents creating one", alias);
+    return Err(1);
+  }
+
+  confirm_new_attestation(config, alias, id)?;
+
+  write_signature(config, alias, id, sig_path)
+}
+
+fn write_signature(config: &Config, alias: &str, id: &str, sig_path: PathBuf) -> Result<bool, i32> {
+  info!("creating signature for alias `{}`. you may need to enter your pgp passphrase", alias);
+
+  let signed = sign(config, &pin::payload(id, metadata::expires(config, alias)), Some(alias))?;
+
+  let mut file = match files::create_file(&sig_path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not create {}: {}", sig_path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  if let Err(e) = file.write_all(&signed) {
+    error!("could not write signature file: {}", e);
+    return Err(1);
+  }
+  audit::record(config, &format!("attested alias `{}` for key `{}` with a signature", alias, id));
+
+  if config.signing.offer_lsign {
+    offer_lsign(config, id)?;
+  }
+
+  Ok(true)
+}
+
+fn confirm(config: &Config, question: &str) -> Result<bool, i32> {
+  if let Some(answer) = preset_answer(question) {
+    return Ok(answer);
+  }
+
+  let (yes, no) = (config.prompts.yes(), config.prompts.no());
+  let resp = tty::ask(&format!("{} [{}/{}] ", question, yes, no.to_uppercase()))?;
+  Ok(resp == yes.to_lowercase())
+}
+
+fn preset_answer(question: &str) -> Option<bool> {
+  let answer = tty::preset_answer()?;
+  if answer {
+    info!("{} yes (--yes was given)", question);
+  } else {
+    warn!("{} no (not asking without a terminal or with --batch: pass --yes to accept)", question);
+  }
+  Some(answer)
+}
+
+// like confirm, but while working through several aliases also offers to answer yes to this
+// question for the rest of the run, or to list what is still to come
+fn confirm_batch(config: &Config, kind: &'static str, args: &[(&str, &str)]) -> Result<bool, i32> {
+  let question = config.prompts.text(kind, args);
+  if batch::is_accepted(kind) {
+    info!("{} {}", question, config.prompts.yes());
+    return Ok(true);
+  }
+  if let Some(answer) = preset_answer(&question) {
+    return Ok(answer);
+  }
+  if !batch::is_active() {
+    return confirm(config, &question);
+  }
+
+  let (yes, no, all) = (config.prompts.yes(), config.prompts.no(), config.prompts.all());
+  loop {
+    let resp = tty::ask(&format!("{} [{}/{}/{}/?] ", question, yes, no.to_uppercase(), all))?;
+    if resp == "?" {
+      info!("{}", config.prompts.text("remaining", &[("items", &batch::pending().join(", "))]));
+      continue;
+    }
+    if resp == all.to_lowercase() {
+      batch::accept_all(kind);
+      audit::record(config, &format!("answered yes to all `{}` prompts for the rest of the run", kind));
+      return Ok(true);
+    }
+    return Ok(resp == yes.to_lowercase());
+  }
+}
+
+// a local certification makes gpg itself consider the key valid, keeping its validity model in
+// line with the alias we just attested
+fn offer_lsign(config: &Config, id: &str) -> Result<(), i32> {
+  if !confirm_batch(config, "offer_lsign", &[("id", id)])? {
+    return Ok(());
+  }
+
+  lsign::certify(config, id)
+}
+
+// embed what the signature attests to in the signature itself, so tools inspecting it can see
+fn notations(alias: &str) -> [(&str, &str); 2] {
+  [
+    (NOTATION_ALIAS, alias),
+    (NOTATION_VERSION, clap::crate_version!()),
+  ]
+}
+
+fn add_notations(config: &Config, ctx: &mut Context, alias: &str) -> Result<(), i32> {
+  for &(name, value) in &notations(alias) {
+    if let Err(e) = ctx.add_signature_notation(name, value, SignatureNotationFlags::HUMAN_READABLE) {
+      error!("could not add signature notation `{}`: {}", name, e);
+      return Err(1);
+    }
+  }
+
+  if let Some(url) = &config.signing.policy_url {
+    if let Err(e) = ctx.add_signature_policy_url(url, false) {
+      error!("could not add signature policy url: {}", e);
+      return Err(1);
+    }
+  }
+
+  Ok(())
+}
+
+fn signing_context(config: &Config) -> Result<Context, i32> {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not created gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  ctx.clear_signers();
+  let key = match ctx.get_key(&config.signing.key) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("missing signing key: {}", e);
+      return Err(1);
+    },
+  };
+  if let Err(e) = ctx.add_signer(&key) {
+    error!("could not add signing key as a signer: {}", e);
+    return Err(1);
+  }
+
+  Ok(ctx)
+}
+
+fn sign(config: &Config, payload: &str, alias: Option<&str>) -> Result<Vec<u8>, i32> {
+  if mock::is_enabled() {
+    let notations = alias.map(notations);
+    return Ok(mock::sign_clear(&config.signing.key, payload, notations.as_ref().map_or(&[][..], |n| &n[..])));
+  }
+
+  let has_tty = tty::ensure_gpg_tty();
+
+  let mut ctx = signing_context(config)?;
+  if let Some(alias) = alias {
+    add_notations(config, &mut ctx, alias)?;
+  }
+  let mut signed = Vec::new();
+  if let Err(e) = ctx.sign_clear(payload, &mut signed) {
+    signing_failed(e, has_tty);
+    return Err(1);
+  }
+
+  Ok(signed)
+}
+
+fn sign_detached(config: &Config, payload: &[u8]) -> Result<Vec<u8>, i32> {
+  if mock::is_enabled() {
+    return Ok(mock::sign_detached(&config.signing.key, payload));
+  }
+
+  let has_tty = tty::ensure_gpg_tty();
+
+  let mut ctx = signing_context(config)?;
+  ctx.set_armor(true);
+  let mut signature = Vec::new();
+  if let Err(e) = ctx.sign_detached(payload, &mut signature) {
+    signing_failed(e, has_tty);
+    return Err(1);
+  }
+
+  Ok(signature)
+}
+
+fn signing_failed(e: gpgme::Error, has_tty: bool) {
+  error!("could not create signature: {}", e);
+  if !has_tty {
+    error!("no terminal is available for pinentry: run from a terminal, set GPG_TTY or configure a graphical pinentry");
+  }
+}
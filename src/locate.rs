@@ -0,0 +1,70 @@
+use gpgme::{Context, KeyListMode, Protocol};
+use serde_derive::Deserialize;
+
+use crate::Config;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Locate {
+  #[serde(default)]
+  pub enabled: bool,
+}
+
+// whether the name is an address for gpg to locate, rather than an unknown alias
+pub fn applies(config: &Config, name: &str) -> bool {
+  config.locate.enabled && !config.aliases.contains_key(name) && looks_like_email(name)
+}
+
+fn looks_like_email(name: &str) -> bool {
+  match name.find('@') {
+    Some(i) => i > 0 && name[i + 1..].contains('.'),
+    None => false,
+  }
+}
+
+// asks gpg to locate the key for an address, which `applies` has said this is
+pub fn locate(email: &str) -> Result<String, i32> {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  if let Err(e) = ctx.set_key_list_mode(KeyListMode::LOCATE) {
+    error!("could not enable key location: {}", e);
+    return Err(1);
+  }
+
+  info!("`{}` is not an alias, asking gpg to locate a key for it", email);
+  let keys = match ctx.find_keys(vec![email]) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not locate a key for {}: {}", email, e);
+      return Err(1);
+    },
+  };
+
+  let key = keys
+    .filter_map(Result::ok)
+    .filter(|k| !k.is_revoked() && !k.is_expired() && !k.is_disabled() && !k.is_invalid())
+    .find(|k| k.user_ids().filter_map(|u| u.email().ok()).any(|e| e.eq_ignore_ascii_case(email)));
+
+  let key = match key {
+    Some(k) => k,
+    None => {
+      error!("gpg could not locate a usable key for {}", email);
+      return Err(1);
+    },
+  };
+
+  let fingerprint = match key.fingerprint() {
+    Ok(f) => f.to_string(),
+    Err(_) => {
+      error!("located key for {} has an invalid fingerprint", email);
+      return Err(1);
+    },
+  };
+
+  warn!("using located key {} for {}: it is not an alias and has not been attested", fingerprint, email);
+  Ok(fingerprint)
+}
@@ -1,22 +1,111 @@
-use log::Level;
+use log::{Level, LevelFilter};
 use ansi_term::Colour;
+use serde_derive::Deserialize;
 
-pub fn set_up_logger() -> Result<(), fern::InitError> {
-  fern::Dispatch::new()
+use std::{
+  fs::{File, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Logging {
+  // everything down to debug is appended here with a timestamp, whatever -q says
+  pub file: Option<PathBuf>,
+}
+
+// the file sink is set up with the logger, but only has somewhere to write once the config is read
+static FILE_ENABLED: AtomicBool = AtomicBool::new(false);
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+// verbosity is the number of -V flags less the number of -q flags
+pub fn set_up_logger(verbosity: i64) -> Result<(), fern::InitError> {
+  let level = match verbosity {
+    i64::MIN..=-3 => LevelFilter::Off,
+    -2 => LevelFilter::Error,
+    -1 => LevelFilter::Warn,
+    0 => LevelFilter::Info,
+    1 => LevelFilter::Debug,
+    _ => LevelFilter::Trace,
+  };
+  let colour = use_colour();
+
+  let stderr = fern::Dispatch::new()
+    .format(move |out, message, record| {
+      if colour {
+        out.finish(format_args!("[{}] {}", coloured_level(record.level()), message))
+      } else {
+        out.finish(format_args!("[{}] {}", record.level(), message))
+      }
+    })
+    .level(level)
+    .chain(std::io::stderr());
+
+  let file = fern::Dispatch::new()
+    .filter(|_| FILE_ENABLED.load(Ordering::Relaxed))
     .format(|out, message, record| {
-      out.finish(format_args!(
-        "[{}] {}",
-        coloured_level(record.level()),
-        message,
-      ))
+      out.finish(format_args!("{} [{}] {}", timestamp(), record.level(), message))
     })
+    .level(level.max(LevelFilter::Debug))
+    .chain(fern::Output::call(write_to_file));
+
+  fern::Dispatch::new()
     .filter(|meta| meta.target().starts_with("gpg_alias"))
-    .level(log::LevelFilter::Info)
-    .chain(std::io::stderr())
+    .chain(stderr)
+    .chain(file)
     .apply()?;
   Ok(())
 }
 
+pub fn log_to_file(path: &Path) -> Result<(), i32> {
+  let exists = path.exists();
+  let file = match OpenOptions::new().create(true).append(true).open(path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not open log file {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  if !exists {
+    if let Err(e) = crate::files::make_private(path) {
+      warn!("could not make {} private: {}", path.to_string_lossy(), e);
+    }
+  }
+
+  if let Ok(mut f) = FILE.lock() {
+    *f = Some(file);
+  }
+  FILE_ENABLED.store(true, Ordering::Relaxed);
+  Ok(())
+}
+
+fn write_to_file(record: &log::Record) {
+  if let Ok(mut f) = FILE.lock() {
+    if let Some(file) = f.as_mut() {
+      // nowhere left to report a failure to log
+      let _ = writeln!(file, "{}", record.args());
+    }
+  }
+}
+
+// https://no-color.org, and no escape codes for anything that isn't a terminal either
+fn use_colour() -> bool {
+  let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+  !no_color && crate::tty::stderr_is_tty()
+}
+
+// UTC, like the rest of gpg-alias's dates
+fn timestamp() -> String {
+  let now = SystemTime::now();
+  let secs = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) % 86400;
+  format!("{}T{:02}:{:02}:{:02}Z", crate::provenance::date(now), secs / 3600, secs / 60 % 60, secs % 60)
+}
+
 fn coloured_level(level: Level) -> ansi_term::ANSIGenericString<'static, str> {
   match level {
     Level::Trace => Colour::Fixed(243).paint("TRACE"),
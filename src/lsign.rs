@@ -0,0 +1,101 @@
+use gpgme::{Context, KeyListMode, KeySigningFlags, Protocol};
+
+use std::time::SystemTime;
+
+use crate::Config;
+
+fn context() -> Result<Context, i32> {
+  if crate::mock::is_enabled() {
+    error!("the lsign method can't be simulated, since local certifications live in the real keyring");
+    return Err(1);
+  }
+
+  match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => Ok(c),
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      Err(1)
+    },
+  }
+}
+
+// a certification is of the key alone, so any alias pointing at a certified key passes
+pub fn is_certified(config: &Config, id: &str) -> Result<bool, i32> {
+  Ok(certified_at(config, id)?.is_some())
+}
+
+// when the newest valid local certification was made. one without a creation time counts as the
+// oldest possible, so it is always due for re-verification
+pub fn certified_at(config: &Config, id: &str) -> Result<Option<SystemTime>, i32> {
+  let mut ctx = context()?;
+
+  let signer = match ctx.get_key(&config.signing.key) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not get signing key: {}", e);
+      return Err(1);
+    },
+  };
+  let signer_id = match signer.id() {
+    Ok(i) => i.to_string(),
+    Err(_) => {
+      error!("signing key has an invalid key ID");
+      return Err(1);
+    },
+  };
+
+  if let Err(e) = ctx.set_key_list_mode(KeyListMode::LOCAL | KeyListMode::SIGS) {
+    error!("could not list key signatures: {}", e);
+    return Err(1);
+  }
+  let target = match ctx.get_key(id) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not find key `{}`: {}", id, e);
+      return Err(1);
+    },
+  };
+
+  let certified = target.user_ids()
+    .filter(|u| !u.is_revoked() && !u.is_invalid())
+    .flat_map(|u| u.signatures())
+    .filter(|s| !s.is_revoked() && !s.is_expired() && !s.is_invalid() && s.status().is_ok())
+    .filter(|s| s.signer_key_id().map(|i| i.eq_ignore_ascii_case(&signer_id)).unwrap_or(false))
+    .map(|s| s.creation_time().unwrap_or(SystemTime::UNIX_EPOCH))
+    .max();
+
+  Ok(certified)
+}
+
+pub fn certify(config: &Config, id: &str) -> Result<(), i32> {
+  let mut ctx = context()?;
+
+  let signer = match ctx.get_key(&config.signing.key) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("missing signing key: {}", e);
+      return Err(1);
+    },
+  };
+  let target = match ctx.get_key(id) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not find key `{}` to sign: {}", id, e);
+      return Err(1);
+    },
+  };
+
+  ctx.clear_signers();
+  if let Err(e) = ctx.add_signer(&signer) {
+    error!("could not add signing key as a signer: {}", e);
+    return Err(1);
+  }
+  if let Err(e) = ctx.sign_key_with_flags(&target, Vec::<&str>::new(), None, KeySigningFlags::LOCAL) {
+    error!("could not locally sign key `{}`: {}", id, e);
+    return Err(1);
+  }
+
+  info!("locally signed key `{}`", id);
+  crate::audit::record(config, &format!("locally signed key `{}`", id));
+  Ok(())
+}
@@ -0,0 +1,155 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::Config;
+
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SECRET_FILE: &str = "hmac.key";
+const SECRET_LEN: usize = 32;
+// keeps these MACs from being valid for anything else the secret might ever be used for
+const DOMAIN: &[u8] = b"gpg-alias alias attestation v1";
+
+pub fn attestation_path(config: &Config, alias: &str) -> Result<PathBuf, i32> {
+  crate::naming::check_safe(alias)?;
+  Ok(crate::data_dir(config)?.join(format!("{}.mac", alias)))
+}
+
+fn secret_path(config: &Config) -> Result<PathBuf, i32> {
+  Ok(crate::data_dir(config)?.join(SECRET_FILE))
+}
+
+fn secret(config: &Config, create: bool) -> Result<Vec<u8>, i32> {
+  let path = secret_path(config)?;
+  if !path.exists() {
+    if !create {
+      error!("hmac secret {} does not exist, so no attestation can be verified", path.to_string_lossy());
+      return Err(1);
+    }
+    return create_secret(config, &path);
+  }
+
+  // anyone who can read the secret can forge attestations
+  match crate::files::is_private(&path) {
+    Ok(true) => {},
+    Ok(false) => {
+      error!("hmac secret {} can be read by other users: run `gpg-alias fsck` to fix it", path.to_string_lossy());
+      return Err(1);
+    },
+    Err(e) => {
+      error!("could not read the permissions of {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  }
+
+  match std::fs::read(&path) {
+    Ok(s) if s.len() == SECRET_LEN => Ok(s),
+    Ok(_) => {
+      error!("hmac secret {} is corrupt", path.to_string_lossy());
+      Err(1)
+    },
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      Err(1)
+    },
+  }
+}
+
+fn create_secret(config: &Config, path: &Path) -> Result<Vec<u8>, i32> {
+  if config.no_write {
+    error!("hmac secret does not exist and --no-write prevents creating it");
+    return Err(1);
+  }
+
+  let mut secret = vec![0; SECRET_LEN];
+  if let Err(e) = getrandom::getrandom(&mut secret) {
+    error!("could not generate hmac secret: {}", e);
+    return Err(1);
+  }
+
+  let res = crate::files::create_file(path).and_then(|mut f| f.write_all(&secret));
+  if let Err(e) = res {
+    error!("could not write hmac secret {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  info!("created hmac secret {}: back it up, as attestations can't be verified without it", path.to_string_lossy());
+  Ok(secret)
+}
+
+fn mac(secret: &[u8], alias: &str, id: &str) -> HmacSha256 {
+  let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts keys of any length");
+  mac.update(DOMAIN);
+  mac.update(b"\0");
+  mac.update(alias.as_bytes());
+  mac.update(b"\0");
+  mac.update(id.as_bytes());
+  mac
+}
+
+// the attested key is stored in the clear next to its mac, like the content of a clearsigned
+// signature, so conflicts with the config can show what was attested
+pub fn attested_value(config: &Config, alias: &str) -> Result<crate::pin::Vouched, i32> {
+  let path = attestation_path(config, alias)?;
+  let contents = match std::fs::read_to_string(&path) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  // the mac covers the key ID and anything pinned on the lines after the mac
+  let mut lines = contents.lines();
+  let (id, tag) = match (lines.next(), lines.next().and_then(|t| hex::decode(t.trim()).ok())) {
+    (Some(id), Some(tag)) => (id.trim(), tag),
+    _ => {
+      error!("attestation {} is corrupt", path.to_string_lossy());
+      return Err(1);
+    },
+  };
+  let payload = std::iter::once(id).chain(lines.map(str::trim)).collect::<Vec<_>>().join("\n");
+
+  let secret = secret(config, false)?;
+  if mac(&secret, alias, &payload).verify_slice(&tag).is_err() {
+    error!("invalid attestation for alias `{}`: the mac does not match", alias);
+    return Err(1);
+  }
+
+  let attested = crate::pin::parse(&payload);
+  crate::pin::check(&attested)?;
+  Ok(attested.vouched())
+}
+
+pub fn attest(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  if config.no_write {
+    error!("no attestation for alias `{}` and --no-write prevents creating one", alias);
+    return Err(1);
+  }
+
+  let secret = secret(config, true)?;
+  let payload = crate::pin::payload(id, crate::metadata::expires(config, alias));
+  let tag = hex::encode(mac(&secret, alias, &payload).finalize().into_bytes());
+
+  let mut lines = payload.lines();
+  let mut contents = format!("{}\n{}\n", lines.next().unwrap_or(id), tag);
+  for line in lines {
+    contents.push_str(line);
+    contents.push('\n');
+  }
+
+  let path = attestation_path(config, alias)?;
+  let res = crate::files::create_file(&path).and_then(|mut f| f.write_all(contents.as_bytes()));
+  if let Err(e) = res {
+    error!("could not write {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+  crate::audit::record(config, &format!("attested alias `{}` for key `{}` with an hmac", alias, id));
+
+  Ok(())
+}
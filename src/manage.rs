@@ -0,0 +1,285 @@
+use clap::ArgMatches;
+use gpgme::Context;
+
+use crate::{protocol::KeyProtocol, Config, Method};
+
+use std::path::Path;
+
+// attestations are made before the config is edited and removed again if the edit fails, so an
+// alias is never left changed but unattested
+pub fn add(config: &Config, matches: &ArgMatches) -> i32 {
+  let alias = matches.value_of("alias").expect("required clap argument");
+  let key_id = matches.value_of("key").expect("required clap argument");
+  let description = format!("add alias `{}`", alias);
+
+  if config.aliases.contains_key(alias) {
+    error!("alias `{}` already exists: remove or rename it first", alias);
+    return 1;
+  }
+  // a group's attestation has the same file name, so it would be removed below
+  if config.groups.contains_key(alias) {
+    error!("`{}` already exists as a group", alias);
+    return 1;
+  }
+  if crate::naming::check(config, alias).is_err() {
+    return 1;
+  }
+  let protocol = matches.value_of("protocol").and_then(KeyProtocol::from_name).unwrap_or_default();
+  if protocol == KeyProtocol::Cms {
+    if config.signing.method == Method::Lsign {
+      error!("the lsign method can't attest S/MIME aliases: use method = \"file\" or \"hmac\"");
+      return 1;
+    }
+    crate::protocol::register(key_id);
+  }
+
+  if check_writable(config, &description).is_err() || check_key(key_id).is_err() {
+    return 1;
+  }
+  let mut fields = match provenance(matches) {
+    Ok(f) => f,
+    Err(_) => return 1,
+  };
+  if protocol != KeyProtocol::OpenPgp {
+    fields.insert(0, ("protocol", protocol.name().to_string()));
+  }
+
+  if config.signing.enabled {
+    // a leftover attestation from an earlier alias of the same name would vouch for the wrong key
+    if let Err(exit) = remove_attestations(config, alias) {
+      return exit;
+    }
+    if let Err(exit) = crate::sign_all::attest(config, &[(alias, key_id)]) {
+      error!("alias `{}` was not added, since it was not attested", alias);
+      return exit;
+    }
+  }
+
+  if let Err(exit) = edit_config(config, &description, |path| crate::edit::add_alias(path, alias, key_id, &fields)) {
+    let _ = remove_attestations(config, alias);
+    return exit;
+  }
+
+  info!("added alias `{}` for key `{}`", alias, key_id);
+  0
+}
+
+pub fn remove(config: &Config, matches: &ArgMatches) -> i32 {
+  let alias = matches.value_of("alias").expect("required clap argument");
+  let description = format!("remove alias `{}`", alias);
+
+  if check_editable(config, alias).is_err() || check_writable(config, &description).is_err() {
+    return 1;
+  }
+
+  if let Err(exit) = edit_config(config, &description, |path| crate::edit::remove_alias(path, alias)) {
+    return exit;
+  }
+  if let Err(exit) = remove_attestations(config, alias) {
+    return exit;
+  }
+  if config.signing.method == Method::Lsign {
+    info!("the local signature on the key for `{}` was kept, since other aliases may rely on it", alias);
+  }
+
+  info!("removed alias `{}`", alias);
+  0
+}
+
+pub fn rename(config: &Config, matches: &ArgMatches) -> i32 {
+  let old = matches.value_of("old").expect("required clap argument");
+  let new = matches.value_of("new").expect("required clap argument");
+  let description = format!("rename alias `{}` to `{}`", old, new);
+
+  if check_editable(config, old).is_err() || check_writable(config, &description).is_err() {
+    return 1;
+  }
+  if config.aliases.contains_key(new) {
+    error!("alias `{}` already exists", new);
+    return 1;
+  }
+  if config.groups.contains_key(new) {
+    error!("`{}` already exists as a group", new);
+    return 1;
+  }
+  if crate::naming::check(config, new).is_err() {
+    return 1;
+  }
+  let key_id = config.aliases[old].key();
+
+  // attestations name the alias they're for, so one is made for the new name: without asking if
+  // the old name's verifies, since that key was already confirmed. local signatures are on the key
+  // and need nothing
+  let reattest = config.signing.enabled && config.signing.method != Method::Lsign;
+  if reattest {
+    let verified = crate::attestation_exists(config, old, key_id).unwrap_or(false)
+      && crate::verify_attestation(config, old, key_id).is_ok();
+    if let Err(exit) = remove_attestations(config, new) {
+      return exit;
+    }
+    let res = if verified {
+      info!("carrying the verified attestation over to `{}`", new);
+      crate::attest(config, new, key_id)
+    } else {
+      crate::sign_all::attest(config, &[(new, key_id)])
+    };
+    if let Err(exit) = res {
+      error!("alias `{}` was not renamed, since `{}` was not attested", old, new);
+      return exit;
+    }
+  }
+
+  if let Err(exit) = edit_config(config, &description, |path| crate::edit::rename_alias(path, old, new)) {
+    if reattest {
+      let _ = remove_attestations(config, new);
+    }
+    return exit;
+  }
+  if reattest {
+    if let Err(exit) = remove_attestations(config, old) {
+      return exit;
+    }
+  }
+
+  info!("renamed alias `{}` to `{}`", old, new);
+  0
+}
+
+pub fn list(config: &Config, matches: &ArgMatches) -> i32 {
+  let tags: Vec<&str> = matches.values_of("tag").into_iter().flatten().collect();
+  let mut names: Vec<&String> = config.aliases.keys()
+    .filter(|name| tags.iter().all(|t| crate::metadata::tags(config, name).iter().any(|have| have == t)))
+    .collect();
+  names.sort();
+
+  let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+  for name in names {
+    let key_id = config.aliases[name].key();
+    let mut notes = Vec::new();
+    if config.system.aliases.contains(name.as_str()) {
+      notes.push("system".to_string());
+    }
+    if config.signing.enabled && !crate::attestation_exists(config, name, key_id).unwrap_or(false) {
+      notes.push("not attested".to_string());
+    }
+    if let Some(after) = config.aliases[name].entry().and_then(|e| e.retire_after.as_ref()) {
+      notes.push(format!("retired after {}", after));
+    }
+    match crate::metadata::expires(config, name) {
+      Some(e) if crate::metadata::is_expired(e) => notes.push(format!("expired on {}", e)),
+      Some(e) => notes.push(format!("expires {}", e)),
+      None => {},
+    }
+
+    let mut line = format!("{:<width$}  {}", name, key_id, width = width);
+    if !notes.is_empty() {
+      line.push_str(&format!("  ({})", notes.join(", ")));
+    }
+    let alias_tags = crate::metadata::tags(config, name);
+    if !alias_tags.is_empty() {
+      line.push_str(&format!("  [{}]", alias_tags.join(", ")));
+    }
+    if let Some(comment) = crate::metadata::comment(config, name) {
+      line.push_str(&format!("  # {}", comment));
+    }
+    println!("{}", line);
+  }
+
+  0
+}
+
+pub fn check_editable(config: &Config, alias: &str) -> Result<(), ()> {
+  if config.system.aliases.contains(alias) {
+    error!("alias `{}` is defined by the system config and can only be changed there", alias);
+    return Err(());
+  }
+  if !config.aliases.contains_key(alias) {
+    error!("no such alias `{}`", alias);
+    return Err(());
+  }
+
+  Ok(())
+}
+
+// only keys already in the keyring can be attested, so anything else is refused up front
+pub fn check_key(key_id: &str) -> Result<(), ()> {
+  if crate::mock::is_enabled() {
+    return Ok(());
+  }
+
+  let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(());
+    },
+  };
+  if let Err(e) = ctx.get_key(key_id) {
+    error!("could not find key `{}` in the local keyring: {}", key_id, e);
+    info!("import the key first, then try again");
+    return Err(());
+  }
+
+  Ok(())
+}
+
+// provenance is written as given; only the date is checked, and defaults to today
+fn provenance(matches: &ArgMatches) -> Result<Vec<(&'static str, String)>, ()> {
+  let via = match matches.value_of("verified-via").and_then(crate::provenance::Via::from_name) {
+    Some(v) => v,
+    None => return Ok(Vec::new()),
+  };
+  let on = matches.value_of("verified-on").map(ToString::to_string).unwrap_or_else(crate::provenance::today);
+  if !crate::provenance::is_date(&on) {
+    error!("--verified-on must be a date like 2024-01-31, not `{}`", on);
+    return Err(());
+  }
+
+  let mut fields = vec![("verified_via", via.name().to_string()), ("verified_on", on)];
+  if let Some(by) = matches.value_of("verified-by") {
+    fields.push(("verified_by", by.to_string()));
+  }
+  Ok(fields)
+}
+
+pub fn check_writable(config: &Config, description: &str) -> Result<(), ()> {
+  if config.no_write {
+    error!("--no-write prevents editing the config to {}", description);
+    return Err(());
+  }
+
+  Ok(())
+}
+
+pub fn edit_config<F>(config: &Config, description: &str, edit: F) -> Result<(), i32>
+  where F: FnOnce(&Path) -> Result<(), i32>,
+{
+  if config.simulate {
+    info!("simulating: the config would be edited to {}", description);
+    return Ok(());
+  }
+
+  edit(&config.path)?;
+  crate::edit::finish(config)
+}
+
+pub fn remove_attestations(config: &Config, alias: &str) -> Result<(), i32> {
+  let paths = [
+    crate::signature_path(config, alias)?,
+    crate::mac::attestation_path(config, alias)?,
+  ];
+
+  for path in paths.iter().filter(|p| p.exists()) {
+    if config.no_write {
+      error!("--no-write prevents removing {}", path.to_string_lossy());
+      return Err(1);
+    }
+    if let Err(e) = std::fs::remove_file(path) {
+      error!("could not remove {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    }
+    debug!("removed {}", path.to_string_lossy());
+  }
+
+  Ok(())
+}
@@ -0,0 +1,193 @@
+use serde_derive::Deserialize;
+
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+};
+
+// every string shown around a security-critical question, so none can be left untranslated
+const DEFAULTS: &[(&str, &str)] = &[
+  ("new_attestation_notice", "Please stop to read this message. gpg-alias did not find a signature for the alias called `{alias}`."),
+  ("new_attestation_hint", "If you just added this alias, this is normal, and you will need to verify the key ID for the alias."),
+  ("new_attestation_target", "Alias `{alias}` points to key ID `{id}`."),
+  ("confirm_key", "Is this correct?"),
+  ("confirm_sign_all", "Attest all {count} aliases listed above?"),
+  ("confirm_group_change", "Attest group `{group}` with the keys listed above?"),
+  ("confirm_seal", "Seal the config as it is now?"),
+  ("confirm_adopt_signer", "Trust aliases attested by key `{signer}`?"),
+  ("confirm_adopt", "Adopt alias `{alias}` for key ID `{id}`?"),
+  ("confirm_fetched", "Use the key just fetched, {fingerprint}, for alias `{alias}`?"),
+  ("confirm_uid_change", "The key for alias `{alias}` now says it belongs to `{new}`, not `{old}`. Is it still the right key?"),
+  ("confirm_expiry_change", "Alias `{alias}` was attested to expire {old}, but now expires {new}. Accept the new expiry?"),
+  ("remaining", "Still to come: {items}"),
+  ("offer_lsign", "Locally sign key `{id}` so gpg also considers it valid?"),
+  ("conflict_notice", "Please stop to read this message. The config for alias `{alias}` no longer matches what you attested."),
+  ("conflict_hint", "If you did not change this alias yourself, your config may have been tampered with."),
+  ("conflict_trust", "Trust the attested key `{attested}` and revert the config?"),
+  ("conflict_reattest", "Attest the configured key `{configured}` instead?"),
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Prompts {
+  yes: Option<String>,
+  no: Option<String>,
+  all: Option<String>,
+  file: Option<PathBuf>,
+  #[serde(skip)]
+  translation: Translation,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Translation {
+  yes: Option<String>,
+  no: Option<String>,
+  all: Option<String>,
+  #[serde(default)]
+  messages: HashMap<String, String>,
+}
+
+impl Prompts {
+  pub fn load(&mut self, config_dir: &Path) -> Result<(), i32> {
+    self.read(config_dir)?;
+    self.check_answers()
+  }
+
+  // an explicit file wins, then prompts.<language>.toml next to the config for the current locale
+  fn read(&mut self, config_dir: &Path) -> Result<(), i32> {
+    let path = match &self.file {
+      Some(f) => f.clone(),
+      None => match language() {
+        Some(lang) => config_dir.join(format!("prompts.{}.toml", lang)),
+        None => return Ok(()),
+      },
+    };
+    if self.file.is_none() && !path.exists() {
+      return Ok(());
+    }
+
+    let source = match std::fs::read_to_string(&path) {
+      Ok(s) => s,
+      Err(e) => {
+        error!("could not read {}: {}", path.to_string_lossy(), e);
+        return Err(1);
+      },
+    };
+    self.translation = match toml::from_str(&source) {
+      Ok(t) => t,
+      Err(e) => {
+        error!("could not parse {}: {}", path.to_string_lossy(), e);
+        return Err(1);
+      },
+    };
+
+    if let Some(unknown) = self.translation.messages.keys().find(|k| DEFAULTS.iter().all(|(d, _)| d != k)) {
+      warn!("{} translates unknown message `{}`", path.to_string_lossy(), unknown);
+    }
+
+    Ok(())
+  }
+
+  // answers are compared with what was typed, trimmed and in lower case, so an answer that can't be
+  // typed or that means two things would make a question impossible to answer as intended
+  fn check_answers(&self) -> Result<(), i32> {
+    let answers = [("yes", self.yes()), ("no", self.no()), ("all", self.all())];
+    for (name, answer) in &answers {
+      if answer.trim().is_empty() || answer.trim() != *answer || *answer == "?" {
+        error!("`{}` in [prompts] can't be `{}`: answers are typed, and `?` is taken", name, answer);
+        return Err(1);
+      }
+    }
+    for (i, (name, answer)) in answers.iter().enumerate() {
+      let clash = answers[i + 1..].iter().find(|(_, other)| other.to_lowercase() == answer.to_lowercase());
+      if let Some((other, _)) = clash {
+        error!("`{}` and `{}` in [prompts] are both `{}`, so the answers can't be told apart", name, other, answer);
+        return Err(1);
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn yes(&self) -> &str {
+    self.yes.as_ref().or(self.translation.yes.as_ref()).map_or("y", String::as_str)
+  }
+
+  pub fn no(&self) -> &str {
+    self.no.as_ref().or(self.translation.no.as_ref()).map_or("n", String::as_str)
+  }
+
+  pub fn all(&self) -> &str {
+    self.all.as_ref().or(self.translation.all.as_ref()).map_or("a", String::as_str)
+  }
+
+  pub fn text(&self, key: &str, args: &[(&str, &str)]) -> String {
+    let template = match self.translation.messages.get(key) {
+      Some(t) => t.as_str(),
+      None => DEFAULTS.iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| *v)
+        .expect("unknown message"),
+    };
+
+    substitute(template, args)
+  }
+}
+
+// one pass over the template, so a value that happens to contain `{name}` is left as it is
+fn substitute(template: &str, args: &[(&str, &str)]) -> String {
+  let mut text = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    text.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+    let value = after.find('}')
+      .and_then(|end| args.iter().find(|(name, _)| *name == &after[..end]).map(|(_, value)| (end, *value)));
+    match value {
+      Some((end, value)) => {
+        text.push_str(value);
+        rest = &after[end + 1..];
+      },
+      None => {
+        text.push('{');
+        rest = after;
+      },
+    }
+  }
+  text.push_str(rest);
+  text
+}
+
+fn language() -> Option<String> {
+  ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+    .filter_map(|var| std::env::var(var).ok())
+    .find(|v| !v.is_empty())
+    .map(|v| v.split(['_', '.', '@']).next().unwrap_or("").to_string())
+    .filter(|lang| !lang.is_empty() && lang != "C" && lang != "POSIX")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{substitute, Prompts};
+
+  #[test]
+  fn values_are_not_substituted_again() {
+    let text = substitute("{alias} is {key}", &[("alias", "{key}"), ("key", "ABCD")]);
+    assert_eq!(text, "{key} is ABCD");
+  }
+
+  #[test]
+  fn unknown_placeholders_are_kept() {
+    assert_eq!(substitute("{a} {b} {", &[("a", "1")]), "1 {b} {");
+  }
+
+  #[test]
+  fn answers_must_be_distinct_and_typeable() {
+    let prompts = |yes: &str, all: &str| Prompts { yes: Some(yes.to_string()), all: Some(all.to_string()), ..Default::default() };
+    assert!(prompts("j", "a").check_answers().is_ok());
+    assert!(prompts("j", "J").check_answers().is_err());
+    assert!(prompts("n", "a").check_answers().is_err());
+    assert!(prompts("", "a").check_answers().is_err());
+    assert!(prompts(" j", "a").check_answers().is_err());
+    assert!(prompts("j", "?").check_answers().is_err());
+  }
+}
@@ -0,0 +1,57 @@
+use crate::Config;
+
+pub fn expires<'a>(config: &'a Config, alias: &str) -> Option<&'a str> {
+  config.aliases.get(alias)
+    .and_then(|a| a.entry())
+    .and_then(|e| e.expires.as_deref())
+}
+
+pub fn tags<'a>(config: &'a Config, alias: &str) -> &'a [String] {
+  config.aliases.get(alias)
+    .and_then(|a| a.entry())
+    .map(|e| e.tags.as_slice())
+    .unwrap_or_default()
+}
+
+pub fn comment<'a>(config: &'a Config, alias: &str) -> Option<&'a str> {
+  config.aliases.get(alias)
+    .and_then(|a| a.entry())
+    .and_then(|e| e.comment.as_deref())
+}
+
+// an alias is usable through the whole of the day it expires on
+pub fn is_expired(expires: &str) -> bool {
+  expires < crate::provenance::today().as_str()
+}
+
+// dates are compared as strings, so anything else would expire at the wrong time or never
+pub fn check_config(config: &Config) -> Result<(), i32> {
+  let mut bad: Vec<(&String, &str)> = config.aliases.keys()
+    .filter_map(|name| expires(config, name).map(|e| (name, e)))
+    .filter(|(_, e)| !crate::provenance::is_date(e))
+    .collect();
+  if bad.is_empty() {
+    return Ok(());
+  }
+
+  bad.sort();
+  for (name, expires) in bad {
+    error!("alias `{}` has an invalid expiry `{}`: use a date like 2024-01-31", name, expires);
+  }
+  Err(1)
+}
+
+// an expired alias fails to resolve, unless --force turns that into a warning
+pub fn check(config: &Config, alias: &str) -> Result<(), i32> {
+  let expires = match expires(config, alias) {
+    Some(e) if is_expired(e) => e,
+    _ => return Ok(()),
+  };
+
+  if config.force {
+    warn!("alias `{}` expired on {}, but --force was given", alias, expires);
+    return Ok(());
+  }
+  error!("alias `{}` expired on {}: extend or remove it, or pass --force to use it anyway", alias, expires);
+  Err(1)
+}
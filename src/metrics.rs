@@ -0,0 +1,129 @@
+use clap::ArgMatches;
+use gpgme::Context;
+
+use crate::Config;
+
+use std::{
+  fmt::Write as _,
+  io::Write,
+  path::Path,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DEFAULT_EXPIRY_DAYS: u64 = 30;
+
+#[derive(Default)]
+struct Counts {
+  failing: usize,
+  missing: usize,
+  expired: usize,
+  expiring: usize,
+}
+
+// gauges in the Prometheus text format, for node_exporter's textfile collector or anything else
+// that scrapes it, so monitoring can alert on aliases decaying without a scraper of its own
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let days = match matches.value_of("expiry-days").map(str::parse::<u64>) {
+    Some(Ok(d)) => d,
+    Some(Err(_)) => {
+      error!("--expiry-days must be a number");
+      return 1;
+    },
+    None => DEFAULT_EXPIRY_DAYS,
+  };
+
+  let counts = match count(config, days) {
+    Ok(c) => c,
+    Err(exit) => return exit,
+  };
+  let text = render(config, &counts, days);
+
+  match matches.value_of_os("textfile") {
+    Some(path) => match write(Path::new(path), &text) {
+      Ok(()) => 0,
+      Err(exit) => exit,
+    },
+    None => {
+      print!("{}", text);
+      0
+    },
+  }
+}
+
+fn count(config: &Config, days: u64) -> Result<Counts, i32> {
+  let limit = SystemTime::now() + Duration::from_secs(days * 24 * 60 * 60);
+  let mut counts = Counts::default();
+
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+  for name in names {
+    let key_id = config.aliases[name.as_str()].key();
+    if config.signing.enabled && crate::verify_attestation(config, name, key_id).is_err() {
+      counts.failing += 1;
+    }
+    // mock keys are never in a keyring
+    if crate::mock::is_enabled() {
+      continue;
+    }
+
+    let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+      Ok(c) => c,
+      Err(e) => {
+        error!("could not create gpgme context: {}", e);
+        return Err(1);
+      },
+    };
+    match ctx.get_key(key_id) {
+      Ok(key) if key.is_expired() => counts.expired += 1,
+      Ok(key) => {
+        let expires = key.subkeys().next().and_then(|k| k.expiration_time());
+        if expires.is_some_and(|e| e <= limit) {
+          counts.expiring += 1;
+        }
+      },
+      Err(_) => counts.missing += 1,
+    }
+  }
+
+  Ok(counts)
+}
+
+fn render(config: &Config, counts: &Counts, days: u64) -> String {
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+  let within = format!("{{within_days=\"{}\"}}", days);
+  let gauges: [(&str, &str, &str, u64); 7] = [
+    ("aliases", "aliases configured, including system aliases", "", config.aliases.len() as u64),
+    ("groups", "groups configured", "", config.groups.len() as u64),
+    ("attestations_failing", "aliases whose attestation is missing or doesn't verify", "", counts.failing as u64),
+    ("keys_missing", "aliases whose key is not in the keyring", "", counts.missing as u64),
+    ("keys_expired", "aliases whose key has expired", "", counts.expired as u64),
+    ("keys_expiring", "aliases whose key expires within the number of days in the label", &within, counts.expiring as u64),
+    ("last_run_timestamp_seconds", "when these metrics were collected", "", now),
+  ];
+
+  let mut text = String::new();
+  for (name, help, labels, value) in &gauges {
+    let _ = writeln!(text, "# HELP gpg_alias_{} {}", name, help);
+    let _ = writeln!(text, "# TYPE gpg_alias_{} gauge", name);
+    let _ = writeln!(text, "gpg_alias_{}{} {}", name, labels, value);
+  }
+  text
+}
+
+// written beside the target and renamed over it, so the collector never reads half a file. unlike
+// gpg-alias's own files it's left readable by others, since the exporter usually runs as its own user
+fn write(path: &Path, text: &str) -> Result<(), i32> {
+  let mut tmp = path.as_os_str().to_owned();
+  tmp.push(".tmp");
+  let tmp = Path::new(&tmp);
+
+  let res = std::fs::File::create(tmp)
+    .and_then(|mut f| f.write_all(text.as_bytes()))
+    .and_then(|_| std::fs::rename(tmp, path));
+  if let Err(e) = res {
+    error!("could not write {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  Ok(())
+}
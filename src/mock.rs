@@ -0,0 +1,132 @@
+use sha2::{Digest, Sha256};
+
+use crate::Verified;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// an in-memory stand-in for gpg covering attestation: every key exists, and signatures are
+// checksummed text that is tamper-evident but proves nothing about who made it. deterministic so
+// the same inputs always give the same files
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const BEGIN: &str = "-----BEGIN PGP SIGNED MESSAGE-----\nHash: MOCK\n\n";
+const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n";
+const END: &str = "-----END PGP SIGNATURE-----\n";
+
+pub fn enable() {
+  ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+  ENABLED.load(Ordering::SeqCst)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+  hex::encode_upper(Sha256::digest(data))
+}
+
+// full fingerprints are kept, anything else gets a stable made-up one
+pub fn fingerprint(key_id: &str) -> String {
+  if key_id.len() == 40 && key_id.chars().all(|c| c.is_ascii_hexdigit()) {
+    return key_id.to_ascii_uppercase();
+  }
+  sha256_hex(key_id.as_bytes())[..40].to_string()
+}
+
+fn checksum(signer: &str, notations: &[(String, String)], payload: &[u8]) -> String {
+  let mut data = Vec::new();
+  data.extend_from_slice(signer.as_bytes());
+  for (name, value) in notations {
+    data.extend_from_slice(format!("\0{}={}", name, value).as_bytes());
+  }
+  data.push(0);
+  data.extend_from_slice(payload);
+  sha256_hex(&data)
+}
+
+fn signature_block(signer: &str, notations: &[(String, String)], payload: &[u8]) -> String {
+  let mut block = String::from(SIGNATURE);
+  block.push_str(&format!("signer: {}\n", signer));
+  for (name, value) in notations {
+    block.push_str(&format!("notation: {}={}\n", name, value));
+  }
+  block.push_str(&format!("checksum: {}\n", checksum(signer, notations, payload)));
+  block.push_str(END);
+  block
+}
+
+pub fn sign_clear(signing_key: &str, payload: &str, notations: &[(&str, &str)]) -> Vec<u8> {
+  let notations: Vec<(String, String)> = notations.iter().map(|&(n, v)| (n.to_string(), v.to_string())).collect();
+  let payload = format!("{}\n", payload.trim_end());
+  format!("{}{}{}", BEGIN, payload, signature_block(&fingerprint(signing_key), &notations, payload.as_bytes())).into_bytes()
+}
+
+pub fn sign_detached(signing_key: &str, payload: &[u8]) -> Vec<u8> {
+  signature_block(&fingerprint(signing_key), &[], payload).into_bytes()
+}
+
+pub fn verify(signed: &[u8]) -> Result<Verified, i32> {
+  let text = utf8(signed)?;
+  let (payload, block) = match text.strip_prefix(BEGIN).and_then(|t| t.find(SIGNATURE).map(|i| t.split_at(i))) {
+    Some(parts) => parts,
+    None => {
+      error!("not a mock signature");
+      return Err(1);
+    },
+  };
+
+  let (signer, notations) = check_block(block, payload.as_bytes())?;
+
+  Ok(Verified {
+    content: payload.trim_end().to_string(),
+    signer,
+    alias: notations.into_iter().find(|(n, _)| n == crate::NOTATION_ALIAS).map(|(_, v)| v),
+  })
+}
+
+// returns who made the signature over data
+pub fn verify_detached(signature: &[u8], data: &[u8]) -> Result<String, i32> {
+  let block = utf8(signature)?;
+  if !block.starts_with(SIGNATURE) {
+    error!("not a mock signature");
+    return Err(1);
+  }
+
+  check_block(block, data).map(|(signer, _)| signer)
+}
+
+fn utf8(data: &[u8]) -> Result<&str, i32> {
+  match std::str::from_utf8(data) {
+    Ok(t) => Ok(t),
+    Err(_) => {
+      error!("mock signature is not utf-8");
+      Err(1)
+    },
+  }
+}
+
+fn check_block(block: &str, payload: &[u8]) -> Result<(String, Vec<(String, String)>), i32> {
+  let mut signer = None;
+  let mut notations = Vec::new();
+  let mut sum = None;
+  for line in block.lines() {
+    if let Some(s) = line.strip_prefix("signer: ") {
+      signer = Some(s.to_string());
+    } else if let Some(n) = line.strip_prefix("notation: ") {
+      let mut parts = n.splitn(2, '=');
+      if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+        notations.push((name.to_string(), value.to_string()));
+      }
+    } else if let Some(c) = line.strip_prefix("checksum: ") {
+      sum = Some(c.to_string());
+    }
+  }
+
+  match (signer, sum) {
+    (Some(signer), Some(sum)) if sum == checksum(&signer, &notations, payload) => Ok((signer, notations)),
+    _ => {
+      error!("invalid signature");
+      Err(1)
+    },
+  }
+}
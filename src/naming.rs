@@ -0,0 +1,92 @@
+use regex::Regex;
+use serde_derive::Deserialize;
+
+use crate::Config;
+
+// rules new alias names have to follow, so a shared config keeps one naming style
+#[derive(Debug, Default, Deserialize)]
+pub struct Naming {
+  pub pattern: Option<String>,
+  pub max_length: Option<usize>,
+  // e.g. "infra:", so every team's aliases are namespaced
+  pub prefix: Option<String>,
+  #[serde(skip)]
+  regex: Option<Regex>,
+}
+
+impl Naming {
+  pub fn compile(&mut self) -> Result<(), i32> {
+    if let Some(pattern) = &self.pattern {
+      match Regex::new(pattern) {
+        Ok(r) => self.regex = Some(r),
+        Err(e) => {
+          error!("invalid alias name pattern `{}`: {}", pattern, e);
+          return Err(1);
+        },
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// attestations are kept in files named after their alias, so whatever the rules say, a name must
+// never reach outside the data directory
+pub fn unsafe_part(name: &str) -> Option<&'static str> {
+  if name.contains('/') {
+    Some("contains `/`")
+  } else if name.contains('\\') {
+    Some("contains `\\`")
+  } else if name.contains("..") {
+    Some("contains `..`")
+  } else if name.contains('\0') {
+    Some("contains a NUL character")
+  } else {
+    None
+  }
+}
+
+pub fn check_safe(name: &str) -> Result<(), i32> {
+  match unsafe_part(name) {
+    Some(part) => {
+      error!("alias name `{}` {}, which can't be part of a file name", name.escape_default(), part);
+      Err(1)
+    },
+    None => Ok(()),
+  }
+}
+
+// why a name breaks the rules, if it does
+pub fn problem(config: &Config, name: &str) -> Option<String> {
+  let naming = &config.naming;
+  if let Some(prefix) = naming.prefix.as_deref().filter(|p| !name.starts_with(*p)) {
+    return Some(format!("does not start with `{}`", prefix));
+  }
+  if let Some(max) = naming.max_length.filter(|max| name.chars().count() > *max) {
+    return Some(format!("is longer than {} characters", max));
+  }
+  if let Some(regex) = naming.regex.as_ref().filter(|r| !r.is_match(name)) {
+    return Some(format!("does not match `{}`", regex.as_str()));
+  }
+
+  None
+}
+
+pub fn check(config: &Config, name: &str) -> Result<(), i32> {
+  check_safe(name)?;
+  match problem(config, name) {
+    Some(problem) => {
+      error!("alias name `{}` {}, as the [naming] rules require", name, problem);
+      Err(1)
+    },
+    None => Ok(()),
+  }
+}
+
+// names proposed for keys, such as by import, start out with the required prefix
+pub fn with_prefix(config: &Config, name: &str) -> String {
+  match &config.naming.prefix {
+    Some(prefix) if !name.starts_with(prefix.as_str()) => format!("{}{}", prefix, name),
+    _ => name.to_string(),
+  }
+}
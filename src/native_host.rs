@@ -0,0 +1,141 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Config;
+
+use std::io::{Read, Write};
+
+// browsers refuse larger messages from the host, and nothing we're sent should come close
+const MAX_MESSAGE: usize = 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Request {
+  #[serde(default)]
+  id: serde_json::Value,
+  method: String,
+  alias: String,
+}
+
+#[derive(Default, Serialize)]
+struct Response {
+  id: serde_json::Value,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  key: Option<String>,
+  // a group's members' keys, in place of `key`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  keys: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  verified: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+// stdin carries the protocol, so nothing here may ever prompt
+pub fn run(config: &Config) -> i32 {
+  let stdin = std::io::stdin();
+  let stdout = std::io::stdout();
+  let mut input = stdin.lock();
+  let mut output = stdout.lock();
+  let stamp = crate::generation::current(config);
+
+  loop {
+    let message = match read_message(&mut input) {
+      Ok(Some(m)) => m,
+      // the browser closes stdin when the extension disconnects
+      Ok(None) => return 0,
+      Err(e) => {
+        error!("could not read native message: {}", e);
+        return 1;
+      },
+    };
+
+    // answering from a config that has since been edited could hand out a mapping that is no longer
+    // wanted, or one that was never verified. browsers start the host again on the next connection
+    if crate::generation::current(config) != stamp {
+      let response = Response {
+        error: Some("the config has changed: reconnect to use it".to_string()),
+        ..Default::default()
+      };
+      let _ = write_message(&mut output, &response);
+      info!("the config has changed, so the native host is exiting");
+      return 0;
+    }
+
+    let response = match serde_json::from_slice::<Request>(&message) {
+      Ok(req) => handle(config, req),
+      Err(e) => Response {
+        error: Some(format!("invalid request: {}", e)),
+        ..Default::default()
+      },
+    };
+
+    if let Err(e) = write_message(&mut output, &response) {
+      error!("could not write native message: {}", e);
+      return 1;
+    }
+  }
+}
+
+fn handle(config: &Config, req: Request) -> Response {
+  let mut response = Response {
+    id: req.id,
+    ..Default::default()
+  };
+
+  if config.groups.contains_key(&req.alias) {
+    // only verified members are expanded, so this never prompts either
+    let keys = crate::groups::expand(config, &req.alias, true);
+    match req.method.as_str() {
+      "resolve" => match keys {
+        Ok(keys) => response.keys = Some(keys),
+        Err(_) => response.error = Some(format!("group `{}` could not be verified", req.alias)),
+      },
+      "verify" => response.verified = Some(config.signing.enabled && keys.is_ok()),
+      other => response.error = Some(format!("unknown method: {}", other)),
+    }
+    return response;
+  }
+
+  let key_id = match config.aliases.get(&req.alias) {
+    Some(a) => a.key(),
+    None => {
+      response.error = Some(format!("no such alias: {}", req.alias));
+      return response;
+    },
+  };
+  let verified = config.signing.enabled && crate::verify_attestation(config, &req.alias, key_id).is_ok();
+
+  match req.method.as_str() {
+    "resolve" if verified || !config.signing.enabled => response.key = Some(key_id.to_string()),
+    "resolve" => response.error = Some(format!("alias `{}` could not be verified", req.alias)),
+    "verify" => response.verified = Some(verified),
+    other => response.error = Some(format!("unknown method: {}", other)),
+  }
+
+  response
+}
+
+// each message is its length as a native-endian u32 followed by that much utf-8 json
+fn read_message<R: Read>(input: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+  let mut len = [0; 4];
+  match input.read_exact(&mut len) {
+    Ok(()) => {},
+    Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+    Err(e) => return Err(e),
+  }
+
+  let len = u32::from_ne_bytes(len) as usize;
+  if len > MAX_MESSAGE {
+    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("message of {} bytes is too large", len)));
+  }
+
+  let mut message = vec![0; len];
+  input.read_exact(&mut message)?;
+  Ok(Some(message))
+}
+
+fn write_message<W: Write>(output: &mut W, response: &Response) -> std::io::Result<()> {
+  let json = serde_json::to_vec(response)?;
+  output.write_all(&(json.len() as u32).to_ne_bytes())?;
+  output.write_all(&json)?;
+  output.flush()
+}
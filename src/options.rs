@@ -0,0 +1,64 @@
+use gpgme::EncryptFlags;
+
+use crate::Config;
+
+// the gpg options the named aliases and groups ask for, such as `--throw-keyids` for a recipient
+// who mustn't be identifiable from the ciphertext. a group asks for its own options and those of
+// its members. each alias's or group's options are passed once, in the order they're first named
+pub fn for_names(config: &Config, names: &[&str]) -> Vec<String> {
+  let mut seen = Vec::new();
+  let mut lists: Vec<&[String]> = Vec::new();
+  for name in names {
+    collect(config, name, &mut seen, &mut lists);
+  }
+
+  lists.into_iter().flatten().cloned().collect()
+}
+
+fn collect<'a>(config: &'a Config, name: &'a str, seen: &mut Vec<&'a str>, lists: &mut Vec<&'a [String]>) {
+  // groups that include themselves are reported when they're expanded
+  if seen.contains(&name) {
+    return;
+  }
+  seen.push(name);
+
+  let own = match config.groups.get(name) {
+    Some(_) => config.group_options.get(name).map(Vec::as_slice),
+    None => config.aliases.get(name).and_then(|a| a.entry()).map(|e| e.options.as_slice()),
+  };
+  if let Some(own) = own.filter(|o| !o.is_empty() && !lists.contains(o)) {
+    lists.push(own);
+  }
+
+  if let Some(members) = config.groups.get(name) {
+    for member in members {
+      collect(config, member, seen, lists);
+    }
+  }
+}
+
+// `encrypt` goes through gpgme rather than gpg's command line, so only options gpgme has a flag for
+// can be honoured. the rest are refused rather than dropped, since they're requirements
+pub fn encrypt_flags(options: &[String]) -> Result<EncryptFlags, i32> {
+  let mut flags = EncryptFlags::empty();
+  let mut options = options.iter().map(String::as_str);
+  while let Some(option) = options.next() {
+    match option {
+      "--throw-keyids" | "--throw-keyid" => flags |= EncryptFlags::THROW_KEYIDS,
+      "--no-encrypt-to" => flags |= EncryptFlags::NO_ENCRYPT_TO,
+      "-z0" | "--compress-algo=none" | "--compress-algo=uncompressed" => flags |= EncryptFlags::NO_COMPRESS,
+      "-z" | "--compress-algo" => match options.next() {
+        Some("0") | Some("none") | Some("uncompressed") => flags |= EncryptFlags::NO_COMPRESS,
+        value => return Err(unsupported(&format!("{} {}", option, value.unwrap_or("")))),
+      },
+      _ => return Err(unsupported(option)),
+    }
+  }
+
+  Ok(flags)
+}
+
+fn unsupported(option: &str) -> i32 {
+  error!("gpg option `{}` asked for by a recipient can't be passed to gpgme: use `gpg-alias run` to encrypt with gpg instead", option.trim_end());
+  1
+}
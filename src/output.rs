@@ -0,0 +1,69 @@
+use serde_derive::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Plain,
+  Recipients,
+  Json,
+}
+
+impl Format {
+  pub fn from_name(name: &str) -> Option<Format> {
+    match name {
+      "plain" => Some(Format::Plain),
+      "recipients" => Some(Format::Recipients),
+      "json" => Some(Format::Json),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+  pub alias: String,
+  pub key: String,
+  pub fingerprint: Option<String>,
+  // "openpgp", or "cms" for S/MIME
+  pub protocol: &'static str,
+  // "verified", "unsigned" when signing is disabled, "located" for keys found by --locate,
+  // "defined" for --define, or "unverified" when gpg is unavailable
+  pub status: &'static str,
+}
+
+// stdout for resolution output, held back until every alias has resolved, so a failure partway
+// through never leaves a script with some of the recipients. anything that has to be shown first,
+// such as a preview of who the recipients are, goes to stderr before it's released
+#[derive(Default)]
+pub struct Stdout {
+  held: String,
+}
+
+impl Stdout {
+  pub fn print(&mut self, s: &str) {
+    self.held.push_str(s);
+  }
+
+  pub fn println(&mut self, s: &str) {
+    self.print(s);
+    self.print("\n");
+  }
+
+  // written and flushed in one go
+  pub fn release(self) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(self.held.as_bytes())?;
+    stdout.flush()
+  }
+}
+
+pub fn render(template: &str, entry: &Entry) -> String {
+  template
+    .replace("{alias}", &entry.alias)
+    .replace("{key}", &entry.key)
+    .replace("{fingerprint}", entry.fingerprint.as_deref().unwrap_or(""))
+    .replace("{status}", entry.status)
+    .replace("{protocol}", entry.protocol)
+}
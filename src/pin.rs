@@ -0,0 +1,232 @@
+use gpgme::{Context, Key};
+
+use crate::Config;
+
+use std::{
+  borrow::Cow,
+  collections::HashMap,
+  sync::Mutex,
+};
+
+// attestations record the full fingerprint the configured key ID resolved to when it was attested,
+// so a short ID that later matches a different key (a colliding key imported since, say) is caught
+// instead of silently attesting the new key
+const FINGERPRINT: &str = "fingerprint ";
+// and the key's primary user ID, so a key whose name or e-mail has been swapped is confirmed again
+const UID: &str = "uid ";
+// and the alias's own expiry, if it has one, so it can't be extended without being attested again
+const EXPIRES: &str = "expires ";
+
+// keys whose primary user ID has changed since they were attested, noticed while verifying and
+// acted on by whoever asked for the verification: (key ID, attested user ID, current user ID)
+static UID_CHANGES: Mutex<Vec<(String, String, String)>> = Mutex::new(Vec::new());
+// the fingerprint each key ID was verified as, so resolving hands gpg the key that was attested
+// rather than whichever key the ID happens to match by the time gpg looks it up
+static VERIFIED: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+pub struct Attested<'a> {
+  pub id: &'a str,
+  pub fingerprint: Option<&'a str>,
+  pub uid: Option<&'a str>,
+  pub expires: Option<&'a str>,
+}
+
+// what a verified attestation vouches for
+pub struct Vouched {
+  pub id: String,
+  pub expires: Option<String>,
+}
+
+impl<'a> Attested<'a> {
+  pub fn vouched(&self) -> Vouched {
+    Vouched {
+      id: self.id.to_string(),
+      expires: self.expires.map(ToString::to_string),
+    }
+  }
+}
+
+// attestations made before fingerprints were pinned are just the key ID, and still verify
+pub fn parse(payload: &str) -> Attested<'_> {
+  let mut lines = payload.lines();
+  let id = lines.next().unwrap_or("").trim();
+  let rest: Vec<&str> = lines.collect();
+  Attested {
+    id,
+    fingerprint: rest.iter().find_map(|l| l.strip_prefix(FINGERPRINT)).map(str::trim),
+    uid: rest.iter().find_map(|l| l.strip_prefix(UID)).map(str::trim),
+    expires: rest.iter().find_map(|l| l.strip_prefix(EXPIRES)).map(str::trim),
+  }
+}
+
+// what to attest for a key ID. ids that aren't a single key, such as a group's key list, have no
+// fingerprint to pin
+pub fn payload(id: &str, expires: Option<&str>) -> String {
+  let mut payload = id.to_string();
+  if let Some(fpr) = fingerprint(id) {
+    payload.push_str(&format!("\n{}{}", FINGERPRINT, fpr));
+  }
+  if let Some(uid) = primary_uid(id) {
+    payload.push_str(&format!("\n{}{}", UID, uid));
+  }
+  if let Some(expires) = expires {
+    payload.push_str(&format!("\n{}{}", EXPIRES, expires));
+  }
+  payload
+}
+
+pub fn check_expiry(attested: Option<&str>, configured: Option<&str>) -> Result<(), i32> {
+  if attested == configured {
+    return Ok(());
+  }
+
+  error!("invalid attestation: expiry does not match (`{}` != `{}`)", attested.unwrap_or("none"), configured.unwrap_or("none"));
+  Err(1)
+}
+
+pub fn check(attested: &Attested) -> Result<(), i32> {
+  let pinned = match attested.fingerprint {
+    Some(f) => f,
+    None => return Ok(()),
+  };
+
+  // a key missing from the keyring can't have been substituted, and is reported elsewhere. an ID
+  // matching several keys is, since gpg could pick any of them
+  let current = fingerprints(attested.id);
+  if current.iter().any(|f| !f.eq_ignore_ascii_case(pinned)) {
+    error!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+    error!("@    WARNING: THE KEY FOR KEY ID `{}` HAS CHANGED!", attested.id);
+    error!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+    error!("key ID `{}` was attested as {}, but now matches {}", attested.id, pinned, current.join(", "));
+    error!("someone may have imported a key that collides with the attested one. check the keyring, and configure the full fingerprint {} instead", pinned);
+    return Err(1);
+  }
+
+  if !current.is_empty() {
+    remember(attested.id, pinned);
+  }
+  check_uid(attested);
+  Ok(())
+}
+
+pub fn remember(id: &str, fingerprint: &str) {
+  if let Ok(mut verified) = VERIFIED.lock() {
+    verified.get_or_insert_with(HashMap::new).insert(id.to_string(), fingerprint.to_string());
+  }
+}
+
+// the fingerprint a key ID was verified as, or the ID itself if it wasn't pinned. an ID pinning a
+// subkey with `!` is already exact, and a fingerprint would name the primary key instead
+pub fn pinned(id: &str) -> Cow<'_, str> {
+  if id.ends_with('!') {
+    return Cow::Borrowed(id);
+  }
+  let verified = VERIFIED.lock().ok().and_then(|v| v.as_ref().and_then(|v| v.get(id)).cloned());
+  match verified {
+    Some(f) => Cow::Owned(f),
+    None => Cow::Borrowed(id),
+  }
+}
+
+fn check_uid(attested: &Attested) {
+  let (old, new) = match (attested.uid, primary_uid(attested.id)) {
+    (Some(old), Some(new)) if old != new => (old, new),
+    _ => return,
+  };
+
+  warn!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+  warn!("@    WARNING: THE USER ID OF KEY `{}` HAS CHANGED!", attested.id);
+  warn!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+  warn!("key `{}` was attested as `{}`, but its primary user ID is now `{}`", attested.id, old, new);
+  if let Ok(mut changes) = UID_CHANGES.lock() {
+    changes.push((attested.id.to_string(), old.to_string(), new));
+  }
+}
+
+fn take_uid_change(id: &str) -> Option<(String, String)> {
+  let mut changes = UID_CHANGES.lock().ok()?;
+  let i = changes.iter().position(|(changed, _, _)| changed == id)?;
+  let (_, old, new) = changes.remove(i);
+  Some((old, new))
+}
+
+// after a verification that may have noticed a changed user ID: asks for the key to be confirmed
+// again and attests it with its new user ID
+pub fn reconfirm(config: &Config, alias: &str, id: &str) -> Result<(), i32> {
+  let (old, new) = match take_uid_change(id) {
+    Some(c) => c,
+    None => return Ok(()),
+  };
+
+  let args = [("alias", alias), ("id", id), ("old", &old), ("new", &new)];
+  if !crate::confirm_batch(config, "confirm_uid_change", &args)? {
+    error!("the new user ID of the key for alias `{}` was not confirmed", alias);
+    return Err(1);
+  }
+  crate::attest(config, alias, id)
+}
+
+// the same, for callers that can't prompt: a changed user ID fails verification until confirmed
+pub fn require_unchanged(alias: &str, id: &str) -> Result<(), i32> {
+  if take_uid_change(id).is_some() {
+    error!("the user ID of the key for alias `{}` has changed since it was attested, so it has to be confirmed again", alias);
+    return Err(1);
+  }
+  Ok(())
+}
+
+// only for an ID matching a single key: one matching several names no key in particular
+pub fn fingerprint(id: &str) -> Option<String> {
+  match fingerprints(id).as_slice() {
+    [f] => Some(f.clone()),
+    _ => None,
+  }
+}
+
+// every key the ID matches, since gpgme's single-key lookup gives up on an ambiguous ID, which
+// would look the same as a missing key
+fn fingerprints(id: &str) -> Vec<String> {
+  if crate::mock::is_enabled() {
+    return vec![crate::mock::fingerprint(id)];
+  }
+
+  keys(id).iter().filter_map(|k| k.fingerprint().ok().map(ToString::to_string)).collect()
+}
+
+// mock keys have no user IDs
+pub fn primary_uid(id: &str) -> Option<String> {
+  if crate::mock::is_enabled() {
+    return None;
+  }
+
+  let key = key(id)?;
+  let uid = key.user_ids().next()?;
+  uid.id().ok().map(ToString::to_string)
+}
+
+fn key(id: &str) -> Option<Key> {
+  let mut keys = keys(id);
+  if keys.len() == 1 {
+    keys.pop()
+  } else {
+    None
+  }
+}
+
+// only key IDs and fingerprints are looked up, as gpgme's single-key lookup did: a search would
+// also match user IDs, and a group's list of keys isn't one key
+fn keys(id: &str) -> Vec<Key> {
+  let hex = id.trim_start_matches("0x").trim_end_matches('!');
+  if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Vec::new();
+  }
+
+  let mut ctx = match Context::from_protocol(crate::protocol::gpgme(id)) {
+    Ok(c) => c,
+    Err(_) => return Vec::new(),
+  };
+  match ctx.find_keys(vec![id]) {
+    Ok(keys) => keys.filter_map(Result::ok).collect(),
+    Err(_) => Vec::new(),
+  }
+}
@@ -0,0 +1,74 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_derive::Serialize;
+
+use std::time::{Duration, Instant};
+
+const JSON_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize)]
+struct Line<'a> {
+  operation: &'a str,
+  done: usize,
+  total: usize,
+  item: Option<&'a str>,
+}
+
+// a bar on terminals (indicatif hides it otherwise), or json lines on stdout for scripts
+pub enum Progress {
+  Bar(ProgressBar),
+  Json {
+    operation: &'static str,
+    total: usize,
+    done: usize,
+    last: Option<Instant>,
+  },
+}
+
+impl Progress {
+  pub fn new(operation: &'static str, total: usize, json: bool) -> Progress {
+    if json {
+      return Progress::Json { operation, total, done: 0, last: None };
+    }
+
+    let bar = ProgressBar::new(total as u64);
+    let style = ProgressStyle::default_bar()
+      .template("{prefix} [{bar:30}] {pos}/{len} {msg}")
+      .unwrap_or_else(|_| ProgressStyle::default_bar());
+    bar.set_style(style.progress_chars("=> "));
+    bar.set_prefix(operation);
+    Progress::Bar(bar)
+  }
+
+  pub fn step(&mut self, item: &str) {
+    match self {
+      Progress::Bar(bar) => {
+        bar.set_message(item.to_string());
+        bar.inc(1);
+      },
+      Progress::Json { operation, total, done, last } => {
+        *done += 1;
+        // throttled so huge databases don't flood the reader, but the last step is always shown
+        if *done < *total && last.is_some_and(|l| l.elapsed() < JSON_INTERVAL) {
+          return;
+        }
+        *last = Some(Instant::now());
+        print_line(&Line { operation, done: *done, total: *total, item: Some(item) });
+      },
+    }
+  }
+
+  pub fn finish(self) {
+    match self {
+      Progress::Bar(bar) => bar.finish_and_clear(),
+      Progress::Json { operation, total, done, .. } => if done < total {
+        print_line(&Line { operation, done, total, item: None });
+      },
+    }
+  }
+}
+
+fn print_line(line: &Line) {
+  if let Ok(json) = serde_json::to_string(line) {
+    println!("{}", json);
+  }
+}
@@ -0,0 +1,74 @@
+use crate::Config;
+
+use std::{
+  io::Write,
+  path::Path,
+  time::{Duration, SystemTime},
+};
+
+// keyring changes and expiring keys don't touch anything we can cheaply watch, so recheck this often
+const MAX_AGE: Duration = Duration::from_secs(5 * 60);
+pub const CACHE_FILE: &str = "prompt-status";
+
+pub fn run(config: &Config) -> i32 {
+  // a prompt has no room for log output
+  log::set_max_level(log::LevelFilter::Off);
+
+  if !config.signing.enabled {
+    return 0;
+  }
+
+  let cache = config.data_dir.join(CACHE_FILE);
+  if !config.fresh && is_fresh(config, &cache) {
+    if let Ok(token) = std::fs::read_to_string(&cache) {
+      print!("{}", token);
+      return 0;
+    }
+  }
+
+  gpgme::init();
+
+  let mut names: Vec<&String> = config.aliases.keys().collect();
+  names.sort();
+  let failing = names.iter()
+    .filter(|name| {
+      let alias = &config.aliases[name.as_str()];
+      crate::verify_attestation(config, name, alias.key()).is_err()
+    })
+    .count();
+
+  // nothing at all when every alias verifies, so the prompt only changes when there is a problem
+  let token = if failing == 0 {
+    String::new()
+  } else {
+    format!("gpg-alias:{}!", failing)
+  };
+  print!("{}", token);
+
+  if !config.no_write && config.data_dir.is_dir() {
+    if let Ok(mut file) = crate::files::create_file(&cache) {
+      let _ = file.write_all(token.as_bytes());
+    }
+  }
+
+  0
+}
+
+fn is_fresh(config: &Config, cache: &Path) -> bool {
+  let cached = match std::fs::metadata(cache).and_then(|m| m.modified()) {
+    Ok(t) => t,
+    Err(_) => return false,
+  };
+
+  match SystemTime::now().duration_since(cached) {
+    Ok(age) if age < MAX_AGE => {},
+    _ => return false,
+  }
+
+  // editing the config or adding a signature invalidates the cache straight away
+  [config.path.as_path(), config.data_dir.as_path()].iter()
+    .all(|p| match std::fs::metadata(p).and_then(|m| m.modified()) {
+      Ok(t) => t <= cached,
+      Err(_) => false,
+    })
+}
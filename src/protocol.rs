@@ -0,0 +1,74 @@
+use gpgme::Protocol;
+use serde_derive::Deserialize;
+
+use crate::Config;
+
+use std::{
+  collections::HashSet,
+  sync::Mutex,
+};
+
+// key IDs of aliases using S/MIME, so key lookups that only have the key ID use gpgsm for them
+static CMS_KEYS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProtocol {
+  #[default]
+  OpenPgp,
+  Cms,
+}
+
+impl KeyProtocol {
+  pub fn from_name(name: &str) -> Option<KeyProtocol> {
+    match name {
+      "openpgp" => Some(KeyProtocol::OpenPgp),
+      "cms" => Some(KeyProtocol::Cms),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      KeyProtocol::OpenPgp => "openpgp",
+      KeyProtocol::Cms => "cms",
+    }
+  }
+}
+
+pub fn register_config(config: &Config) {
+  for alias in config.aliases.values() {
+    if alias.protocol() == KeyProtocol::Cms {
+      register(alias.key());
+    }
+  }
+}
+
+pub fn register(key_id: &str) {
+  if let Ok(mut keys) = CMS_KEYS.lock() {
+    keys.get_or_insert_with(HashSet::new).insert(normalise(key_id));
+  }
+}
+
+pub fn of(key_id: &str) -> KeyProtocol {
+  let cms = CMS_KEYS.lock().ok()
+    .and_then(|keys| keys.as_ref().map(|k| k.contains(&normalise(key_id))))
+    .unwrap_or(false);
+  if cms {
+    KeyProtocol::Cms
+  } else {
+    KeyProtocol::OpenPgp
+  }
+}
+
+// the gpgme protocol to look a key up with
+pub fn gpgme(key_id: &str) -> Protocol {
+  match of(key_id) {
+    KeyProtocol::OpenPgp => Protocol::OpenPgp,
+    KeyProtocol::Cms => Protocol::Cms,
+  }
+}
+
+fn normalise(key_id: &str) -> String {
+  key_id.trim_start_matches("0x").to_uppercase()
+}
@@ -0,0 +1,91 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::AliasEntry;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// how the key behind an alias was checked to belong to its owner, for compliance records
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Via {
+  InPerson,
+  Wkd,
+  KeysigningParty,
+}
+
+impl Via {
+  pub fn from_name(name: &str) -> Option<Via> {
+    match name {
+      "in-person" => Some(Via::InPerson),
+      "wkd" => Some(Via::Wkd),
+      "keysigning-party" => Some(Via::KeysigningParty),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Via::InPerson => "in-person",
+      Via::Wkd => "wkd",
+      Via::KeysigningParty => "keysigning-party",
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Provenance<'a> {
+  pub via: Via,
+  pub on: Option<&'a str>,
+  pub by: Option<&'a str>,
+}
+
+impl Provenance<'_> {
+  // such as `in-person on 2024-05-01 by alice`
+  pub fn describe(&self) -> String {
+    let mut described = self.via.name().to_string();
+    if let Some(on) = self.on {
+      described.push_str(&format!(" on {}", on));
+    }
+    if let Some(by) = self.by {
+      described.push_str(&format!(" by {}", by));
+    }
+    described
+  }
+}
+
+pub fn of(entry: &AliasEntry) -> Option<Provenance<'_>> {
+  entry.verified_via.map(|via| Provenance {
+    via,
+    on: entry.verified_on.as_deref(),
+    by: entry.verified_by.as_deref(),
+  })
+}
+
+// dates are kept as written, but have to at least look like YYYY-MM-DD
+pub fn is_date(date: &str) -> bool {
+  let parts: Vec<&str> = date.split('-').collect();
+  parts.len() == 3
+    && [4, 2, 2].iter().zip(&parts).all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+// today's date in UTC as YYYY-MM-DD
+pub fn today() -> String {
+  date(SystemTime::now())
+}
+
+pub fn date(time: SystemTime) -> String {
+  let days = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86400).unwrap_or(0) as i64;
+
+  // days since the epoch to a civil date, from Howard Hinnant's date algorithms
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z - era * 146_097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,35 @@
+use serde_derive::Deserialize;
+
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+  Posix,
+  Fish,
+}
+
+impl Shell {
+  pub fn from_name(name: &str) -> Option<Shell> {
+    match name {
+      "posix" => Some(Shell::Posix),
+      "fish" => Some(Shell::Fish),
+      _ => None,
+    }
+  }
+
+  pub fn quote<'a>(&self, s: &'a str) -> Cow<'a, str> {
+    if !s.is_empty() && s.chars().all(is_safe) {
+      return Cow::Borrowed(s);
+    }
+
+    match self {
+      Shell::Posix => Cow::Owned(format!("'{}'", s.replace('\'', "'\\''"))),
+      Shell::Fish => Cow::Owned(format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))),
+    }
+  }
+}
+
+fn is_safe(c: char) -> bool {
+  c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c)
+}
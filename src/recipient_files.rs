@@ -0,0 +1,99 @@
+use clap::ArgMatches;
+use gpgme::{Context, ExportMode, Protocol};
+
+use crate::Config;
+
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+  ctx.set_armor(true);
+
+  let mut exported = Vec::new();
+  for alias in matches.values_of("alias").expect("required clap argument") {
+    let key_id = match crate::resolve(config, alias) {
+      Ok(k) => k,
+      Err(exit) => return exit,
+    };
+    let key = match ctx.get_key(&*key_id) {
+      Ok(k) => k,
+      Err(e) => {
+        error!("could not find key `{}` for alias `{}`: {}", key_id, alias, e);
+        return 1;
+      },
+    };
+
+    let mut data = Vec::new();
+    if let Err(e) = ctx.export_keys(&[key], ExportMode::MINIMAL, &mut data) {
+      error!("could not export key for alias `{}`: {}", alias, e);
+      return 1;
+    }
+    exported.push((alias, data));
+  }
+
+  let paths = match (matches.value_of_os("dir"), matches.value_of_os("output")) {
+    (Some(dir), _) => {
+      let dir = Path::new(dir);
+      if let Err(e) = crate::files::create_dir_all(dir) {
+        error!("could not create {}: {}", dir.to_string_lossy(), e);
+        return 1;
+      }
+      let mut paths = Vec::new();
+      for (alias, data) in &exported {
+        let path = dir.join(format!("{}.asc", alias));
+        if write(&path, data).is_err() {
+          return 1;
+        }
+        paths.push(path);
+      }
+      paths
+    },
+    (None, Some(output)) => {
+      let path = PathBuf::from(output);
+      let data: Vec<u8> = exported.into_iter().flat_map(|(_, d)| d).collect();
+      if write(&path, &data).is_err() {
+        return 1;
+      }
+      vec![path]
+    },
+    (None, None) => unreachable!("clap requires one of dir or output"),
+  };
+
+  let args: Vec<String> = paths.iter()
+    .map(|p| {
+      let path = p.to_string_lossy();
+      match config.output.shell_quote {
+        Some(shell) => format!("-f {}", shell.quote(&path)),
+        None => format!("-f {}", path),
+      }
+    })
+    .collect();
+  println!("{}", args.join(" "));
+
+  0
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), ()> {
+  let mut file = match crate::files::create_file(path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not create {}: {}", path.to_string_lossy(), e);
+      return Err(());
+    },
+  };
+  if let Err(e) = file.write_all(data) {
+    error!("could not write {}: {}", path.to_string_lossy(), e);
+    return Err(());
+  }
+
+  Ok(())
+}
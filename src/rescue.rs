@@ -0,0 +1,224 @@
+use clap::ArgMatches;
+use serde_derive::Deserialize;
+
+use crate::{Config, Method};
+
+use std::path::{Path, PathBuf};
+
+// the parts of an `audit export` report a rescue needs
+#[derive(Deserialize)]
+struct Manifest {
+  aliases: Vec<ManifestEntry>,
+}
+
+#[derive(Deserialize)]
+struct ManifestEntry {
+  alias: String,
+  key: String,
+  #[serde(default)]
+  system: bool,
+  #[serde(default)]
+  verified: bool,
+}
+
+// an alias that can be put back in the config, with its expiry if it had one
+struct Recovered {
+  name: String,
+  key: String,
+  expires: Option<String>,
+}
+
+// rebuilds what it can after the config or data directory was partly lost. attestation files still
+// verify, so the aliases they vouch for can go back into the config. an `audit export` report
+// signed by the signing key vouches for the aliases it lists as verified, so they can be put back
+// and attested again
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  if crate::manage::check_writable(config, "rescue aliases").is_err() {
+    return 1;
+  }
+  if !config.signing.enabled {
+    error!("rescuing aliases needs signing enabled, since only what was attested can be trusted");
+    return 1;
+  }
+
+  let mut recovered = match from_attestations(config) {
+    Ok(r) => r,
+    Err(exit) => return exit,
+  };
+  let mut to_attest = Vec::new();
+  if let Some(path) = matches.value_of_os("manifest") {
+    let entries = match manifest(config, Path::new(path)) {
+      Ok(e) => e,
+      Err(exit) => return exit,
+    };
+    for entry in entries {
+      match config.aliases.get(&entry.alias) {
+        Some(alias) if alias.key() != entry.key => {
+          warn!("leaving `{}` alone: it points to `{}` here, but `{}` in the manifest", entry.alias, alias.key(), entry.key);
+          continue;
+        },
+        Some(_) => {},
+        None => match recovered.iter().find(|r| r.name == entry.alias) {
+          Some(r) if r.key != entry.key => {
+            warn!("leaving `{}` out: its attestation is for `{}`, but the manifest says `{}`", entry.alias, r.key, entry.key);
+            continue;
+          },
+          Some(_) => continue,
+          None => recovered.push(Recovered { name: entry.alias.clone(), key: entry.key.clone(), expires: None }),
+        },
+      }
+      match crate::attestation_exists(config, &entry.alias, &entry.key) {
+        Ok(false) => to_attest.push(entry),
+        Ok(true) => {},
+        Err(exit) => return exit,
+      }
+    }
+  }
+
+  if recovered.is_empty() && to_attest.is_empty() {
+    info!("nothing to rescue");
+    return 0;
+  }
+
+  for r in &recovered {
+    warn!("  `{}` points to key ID `{}`", r.name, r.key);
+  }
+  for entry in &to_attest {
+    warn!("  `{}` needs attesting again for key ID `{}`", entry.alias, entry.key);
+  }
+  let question = format!("Restore {} alias(es) to the config and attest {} again?", recovered.len(), to_attest.len());
+  match crate::confirm(config, &question) {
+    Ok(true) => {},
+    Ok(false) => {
+      error!("rescuing the aliases was not authorised");
+      return 1;
+    },
+    Err(exit) => return exit,
+  }
+
+  if !recovered.is_empty() {
+    let res = crate::manage::edit_config(config, &format!("restore {} alias(es)", recovered.len()), |path| {
+      for r in &recovered {
+        let fields: Vec<(&str, String)> = r.expires.iter().map(|e| ("expires", e.clone())).collect();
+        crate::edit::add_alias(path, &r.name, &r.key, &fields)?;
+      }
+      Ok(())
+    });
+    if let Err(exit) = res {
+      return exit;
+    }
+  }
+
+  let mut failed = 0;
+  for entry in &to_attest {
+    if config.simulate {
+      info!("simulating: alias `{}` would be attested again", entry.alias);
+    } else if crate::attest(config, &entry.alias, &entry.key).is_err() {
+      failed += 1;
+    }
+  }
+  if failed > 0 {
+    error!("{} alias(es) could not be attested again", failed);
+    return 1;
+  }
+
+  info!("restored {} alias(es) and attested {} again", recovered.len(), to_attest.len());
+  0
+}
+
+// attestation files carry the key ID and, since signatures had notations, the alias name. files
+// for aliases still in the config are left to the usual checks
+fn from_attestations(config: &Config) -> Result<Vec<Recovered>, i32> {
+  if config.signing.method != Method::File {
+    debug!("attestations are not kept in files, so none can be rescued from the data directory");
+    return Ok(Vec::new());
+  }
+
+  let entries = match std::fs::read_dir(&config.data_dir) {
+    Ok(e) => e,
+    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => {
+      error!("could not read {}: {}", config.data_dir.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path()))
+    .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "asc"))
+    .collect();
+  paths.sort();
+
+  let mut recovered = Vec::new();
+  for path in paths {
+    let stem = match path.file_stem().and_then(|s| s.to_str()) {
+      Some(s) => s.to_string(),
+      None => continue,
+    };
+    if config.aliases.contains_key(&stem) || config.groups.contains_key(&stem) {
+      continue;
+    }
+
+    let verified = crate::read_signature(&path)
+      .and_then(crate::verify_signature)
+      .and_then(|v| crate::check_signer(&config.signing.key, &v.signer).map(|_| v));
+    let verified = match verified {
+      Ok(v) => v,
+      Err(_) => {
+        warn!("skipping {}: it does not verify", path.to_string_lossy());
+        continue;
+      },
+    };
+    // older attestations have no alias notation, so their file name is all there is
+    let name = verified.alias.clone().unwrap_or_else(|| stem.clone());
+    if name != stem {
+      warn!("skipping {}: it was made for alias `{}`", path.to_string_lossy(), name);
+      continue;
+    }
+
+    let attested = crate::pin::parse(&verified.content);
+    if attested.id.contains(' ') {
+      warn!("`{}` was a group of {}: its members' aliases can't be recovered, so add it again by hand", name, attested.id);
+      continue;
+    }
+    recovered.push(Recovered {
+      name,
+      key: attested.id.to_string(),
+      expires: attested.expires.map(ToString::to_string),
+    });
+  }
+
+  Ok(recovered)
+}
+
+// only aliases the report says were verified are vouched for
+fn manifest(config: &Config, path: &Path) -> Result<Vec<ManifestEntry>, i32> {
+  let contents = match std::fs::read(path) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+  let sig_path = crate::seal::signature_path(path);
+  let signature = crate::read_signature(&sig_path)?;
+  if crate::verify_detached(&config.signing.key, signature, &contents).is_err() {
+    error!("{} is not signed by the signing key, so it can't be trusted", path.to_string_lossy());
+    return Err(1);
+  }
+
+  let manifest: Manifest = match serde_json::from_slice(&contents) {
+    Ok(m) => m,
+    Err(e) => {
+      error!("could not parse {}: {}", path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  Ok(manifest.aliases.into_iter()
+    .filter(|e| {
+      if !e.verified && !e.system {
+        warn!("skipping `{}`: it was not verified when the manifest was made", e.alias);
+      }
+      e.verified && !e.system
+    })
+    .collect())
+}
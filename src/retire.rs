@@ -0,0 +1,140 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+use std::path::{Path, PathBuf};
+
+// attestations of pruned aliases are moved here rather than deleted, in case one is wanted again
+pub const ARCHIVE_DIR: &str = "retired";
+
+// a retired alias keeps working until it's pruned, but everyone resolving it is told it's going
+pub fn warn(config: &Config, alias: &str) {
+  let after = match retire_after(config, alias) {
+    Some(a) => a,
+    None => return,
+  };
+
+  if is_due(after) {
+    warn!("alias `{}` was retired after {} and will be removed by the next `gpg-alias prune`", alias, after);
+  } else {
+    warn!("alias `{}` is retired and will be removed after {}", alias, after);
+  }
+}
+
+fn retire_after<'a>(config: &'a Config, alias: &str) -> Option<&'a str> {
+  config.aliases.get(alias)
+    .and_then(|a| a.entry())
+    .and_then(|e| e.retire_after.as_deref())
+}
+
+// dates are written as yyyy-mm-dd, so they compare as strings
+fn is_due(after: &str) -> bool {
+  after < crate::provenance::today().as_str()
+}
+
+pub fn retire(config: &Config, matches: &ArgMatches) -> i32 {
+  let alias = matches.value_of("alias").expect("required clap argument");
+  let after = matches.value_of("after").expect("required clap argument");
+  let description = format!("retire alias `{}`", alias);
+
+  if crate::manage::check_editable(config, alias).is_err() || crate::manage::check_writable(config, &description).is_err() {
+    return 1;
+  }
+  if !crate::provenance::is_date(after) {
+    error!("--after must be a date like 2024-01-31, not `{}`", after);
+    return 1;
+  }
+  let old = retire_after(config, alias);
+  if old == Some(after) {
+    info!("alias `{}` is already retired after {}", alias, after);
+    return 0;
+  }
+  if is_due(after) {
+    warn!("{} has already passed, so the next `gpg-alias prune` will remove `{}`", after, alias);
+  }
+
+  if let Err(exit) = crate::manage::edit_config(config, &description, |path| crate::edit::set_field(path, alias, "retire_after", old, after)) {
+    return exit;
+  }
+  crate::audit::record(config, &format!("retired alias `{}` after {}", alias, after));
+
+  info!("alias `{}` will be removed by `gpg-alias prune` after {}", alias, after);
+  0
+}
+
+// removes every alias whose grace period is over, archiving its attestations
+pub fn prune(config: &Config) -> i32 {
+  let mut names: Vec<&str> = config.aliases.keys()
+    .map(String::as_str)
+    .filter(|name| retire_after(config, name).is_some_and(is_due))
+    .collect();
+  names.sort_unstable();
+
+  names.retain(|name| {
+    let system = config.system.aliases.contains(*name);
+    if system {
+      warn!("alias `{}` is defined by the system config, so it can only be removed there", name);
+    }
+    !system
+  });
+  if names.is_empty() {
+    info!("no retired aliases are due for removal");
+    return 0;
+  }
+  if crate::manage::check_writable(config, "prune retired aliases").is_err() {
+    return 1;
+  }
+
+  for name in names {
+    let description = format!("remove retired alias `{}`", name);
+    if let Err(exit) = crate::manage::edit_config(config, &description, |path| crate::edit::remove_alias(path, name)) {
+      return exit;
+    }
+    if let Err(exit) = archive(config, name) {
+      return exit;
+    }
+    crate::audit::record(config, &format!("pruned alias `{}`, retired after {}", name, retire_after(config, name).unwrap_or_default()));
+
+    for (group, members) in &config.groups {
+      if members.iter().any(|m| m == name) {
+        warn!("group `{}` still names `{}`: remove it from the group", group, name);
+      }
+    }
+    info!("removed retired alias `{}`", name);
+  }
+
+  0
+}
+
+fn archive(config: &Config, alias: &str) -> Result<(), i32> {
+  let paths = [
+    crate::signature_path(config, alias)?,
+    crate::mac::attestation_path(config, alias)?,
+  ];
+  let dir = crate::data_dir(config)?.join(ARCHIVE_DIR);
+
+  for path in paths.iter().filter(|p| p.exists()) {
+    let target = archived_path(&dir, path);
+    if config.simulate {
+      info!("simulating: {} would be archived to {}", path.to_string_lossy(), target.to_string_lossy());
+      continue;
+    }
+    if let Err(e) = crate::files::create_dir_all(&dir) {
+      error!("could not create {}: {}", dir.to_string_lossy(), e);
+      return Err(1);
+    }
+    if let Err(e) = std::fs::rename(path, &target) {
+      error!("could not move {} to {}: {}", path.to_string_lossy(), target.to_string_lossy(), e);
+      return Err(1);
+    }
+    debug!("archived {} to {}", path.to_string_lossy(), target.to_string_lossy());
+  }
+
+  Ok(())
+}
+
+// named for the day it was pruned, so an alias retired more than once keeps each attestation
+fn archived_path(dir: &Path, path: &Path) -> PathBuf {
+  let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+  dir.join(format!("{}.{}", crate::provenance::today(), name))
+}
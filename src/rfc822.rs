@@ -0,0 +1,58 @@
+use gpgme::Context;
+
+// builds a `To:` header value naming each key's owner, from the first usable user ID with an
+// e-mail address. keys sharing an address (such as an alias and a group both naming someone) are
+// only listed once
+pub fn to_header(keys: &[String]) -> Result<String, i32> {
+  let mut seen: Vec<String> = Vec::new();
+  let mut mailboxes = Vec::new();
+  for key_id in keys {
+    let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+      Ok(c) => c,
+      Err(e) => {
+        error!("could not create gpgme context: {}", e);
+        return Err(1);
+      },
+    };
+    let key = match ctx.get_key(key_id.as_str()) {
+      Ok(k) => k,
+      Err(e) => {
+        error!("could not find key `{}` in keyring: {}", key_id, e);
+        return Err(1);
+      },
+    };
+
+    let uid = key.user_ids()
+      .filter(|u| !u.is_revoked() && !u.is_invalid())
+      .find(|u| u.email().map(|e| !e.is_empty()).unwrap_or(false));
+    let uid = match uid {
+      Some(u) => u,
+      None => {
+        warn!("key `{}` has no user ID with an e-mail address, so it is left out of the header", key_id);
+        continue;
+      },
+    };
+
+    let email = uid.email().unwrap_or("");
+    if seen.iter().any(|s| s.eq_ignore_ascii_case(email)) {
+      continue;
+    }
+    seen.push(email.to_string());
+
+    mailboxes.push(match uid.name() {
+      Ok(name) if !name.is_empty() => format!("{} <{}>", quote(name), email),
+      _ => email.to_string(),
+    });
+  }
+
+  Ok(mailboxes.join(", "))
+}
+
+// display names with rfc 5322 specials in them have to be quoted
+fn quote(name: &str) -> String {
+  if name.chars().any(|c| "()<>[]:;@\\,.\"".contains(c)) {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+  } else {
+    name.to_string()
+  }
+}
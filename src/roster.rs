@@ -0,0 +1,124 @@
+use gpgme::Context;
+
+use crate::Config;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+  Markdown,
+  Html,
+}
+
+struct Row<'a> {
+  alias: &'a str,
+  owner: String,
+  fingerprint: String,
+  key_expires: String,
+  alias_expires: &'a str,
+  verified: String,
+}
+
+const HEADINGS: [&str; 6] = ["Alias", "Owner", "Fingerprint", "Key expires", "Alias expires", "Verified"];
+
+// a roster for people to read, so owners come from the keys' user IDs and fingerprints are grouped
+// the way gpg prints them
+pub fn render(config: &Config, names: &[&String], format: Format) -> String {
+  let rows: Vec<Row> = names.iter().map(|name| row(config, name)).collect();
+  let generated = crate::provenance::today();
+
+  match format {
+    Format::Markdown => markdown(&rows, &generated),
+    Format::Html => html(&rows, &generated),
+  }
+}
+
+fn row<'a>(config: &'a Config, name: &'a str) -> Row<'a> {
+  let alias = &config.aliases[name];
+  let key = Context::from_protocol(crate::protocol::gpgme(alias.key()))
+    .ok()
+    .and_then(|mut ctx| ctx.get_key(alias.key()).ok());
+
+  let owner = key.as_ref()
+    .and_then(|k| k.user_ids().find(|u| !u.is_revoked() && !u.is_invalid()))
+    .and_then(|u| u.id().ok().map(ToString::to_string))
+    .unwrap_or_default();
+  let key_expires = match key.as_ref().map(|k| k.subkeys().next().and_then(|s| s.expiration_time())) {
+    Some(Some(t)) => crate::provenance::date(t),
+    Some(None) => "never".to_string(),
+    None => String::new(),
+  };
+  let fingerprint = crate::pin::fingerprint(alias.key()).unwrap_or_else(|| alias.key().to_string());
+
+  let verified = alias.entry().and_then(crate::provenance::of).map(|p| p.describe()).unwrap_or_default();
+
+  Row {
+    alias: name,
+    owner,
+    fingerprint: grouped(&fingerprint),
+    key_expires,
+    alias_expires: crate::metadata::expires(config, name).unwrap_or("never"),
+    verified,
+  }
+}
+
+// in blocks of four, with a wider gap halfway, like `gpg --fingerprint`
+pub fn grouped(fingerprint: &str) -> String {
+  let blocks: Vec<String> = fingerprint.as_bytes()
+    .chunks(4)
+    .map(|c| String::from_utf8_lossy(c).into_owned())
+    .collect();
+  let half = blocks.len() / 2;
+  if blocks.len() == 10 {
+    format!("{}  {}", blocks[..half].join(" "), blocks[half..].join(" "))
+  } else {
+    blocks.join(" ")
+  }
+}
+
+fn cells(row: &Row) -> [String; 6] {
+  [
+    row.alias.to_string(),
+    row.owner.clone(),
+    row.fingerprint.clone(),
+    row.key_expires.clone(),
+    row.alias_expires.to_string(),
+    row.verified.clone(),
+  ]
+}
+
+fn markdown(rows: &[Row], generated: &str) -> String {
+  let escape = |s: &str| s.replace('\\', "\\\\").replace('|', "\\|").replace('<', "&lt;").replace('>', "&gt;");
+
+  let mut out = format!("# Key roster\n\nGenerated by gpg-alias on {}.\n\n", generated);
+  out.push_str(&format!("| {} |\n", HEADINGS.join(" | ")));
+  out.push_str(&format!("|{}\n", " --- |".repeat(HEADINGS.len())));
+  for row in rows {
+    let cells: Vec<String> = cells(row).iter().map(|c| escape(c)).collect();
+    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+  }
+  out
+}
+
+fn html(rows: &[Row], generated: &str) -> String {
+  let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+
+  let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Key roster</title>\n</head>\n<body>\n");
+  out.push_str(&format!("<h1>Key roster</h1>\n<p>Generated by gpg-alias on {}.</p>\n<table>\n<thead>\n<tr>", generated));
+  for heading in HEADINGS.iter() {
+    out.push_str(&format!("<th>{}</th>", heading));
+  }
+  out.push_str("</tr>\n</thead>\n<tbody>\n");
+  for row in rows {
+    out.push_str("<tr>");
+    for (i, cell) in cells(row).iter().enumerate() {
+      // fingerprints shouldn't wrap between blocks
+      if i == 2 {
+        out.push_str(&format!("<td><code style=\"white-space: nowrap\">{}</code></td>", escape(cell)));
+      } else {
+        out.push_str(&format!("<td>{}</td>", escape(cell)));
+      }
+    }
+    out.push_str("</tr>\n");
+  }
+  out.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+  out
+}
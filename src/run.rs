@@ -0,0 +1,70 @@
+use clap::ArgMatches;
+
+use crate::{quote::Shell, Config};
+
+use std::process::Command;
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let aliases: Vec<&str> = matches.values_of("to").expect("required clap argument").collect();
+  let recipients = match recipients(config, &aliases) {
+    Ok(r) => r,
+    Err(exit) => return exit,
+  };
+
+  let gpg = match crate::gpg_program() {
+    Ok(g) => g,
+    Err(exit) => return exit,
+  };
+
+  let mut args: Vec<String> = Vec::new();
+  if let Some(gpg_args) = matches.value_of("gpg-args") {
+    args.extend(gpg_args.split_whitespace().map(ToString::to_string));
+  }
+  args.extend(crate::options::for_names(config, &aliases));
+  for recipient in recipients {
+    args.push("-r".to_string());
+    args.push(recipient);
+  }
+  if let Some(rest) = matches.values_of("args") {
+    args.extend(rest.map(ToString::to_string));
+  }
+
+  if matches.is_present("print-cmd") {
+    let shell = config.output.shell_quote.unwrap_or(Shell::Posix);
+    let words: Vec<_> = std::iter::once(&gpg).chain(&args).map(|w| shell.quote(w)).collect();
+    println!("{}", words.join(" "));
+    return 0;
+  }
+
+  let mut cmd = Command::new(gpg);
+  cmd.args(&args);
+  debug!("running {:?}", cmd);
+
+  match cmd.status() {
+    Ok(s) => s.code().unwrap_or(1),
+    Err(e) => {
+      error!("could not run gpg: {}", e);
+      1
+    },
+  }
+}
+
+// the keys for aliases, groups and locatable addresses, resolved and checked like the top-level
+// command resolves them
+pub fn recipients(config: &Config, aliases: &[&str]) -> Result<Vec<String>, i32> {
+  let mut recipients = Vec::new();
+  for alias in aliases {
+    let keys = match crate::lookup(config, alias) {
+      crate::Name::Group => crate::groups::expand(config, alias, false)?,
+      crate::Name::Defined(key) => vec![key.to_string()],
+      crate::Name::Address => vec![crate::locate::locate(alias)?],
+      crate::Name::Alias => vec![crate::resolve(config, alias)?.into_owned()],
+    };
+    for key_id in keys {
+      crate::validity::check(config, alias, &key_id)?;
+      recipients.push(key_id);
+    }
+  }
+
+  Ok(crate::dedup::collapse(config, recipients))
+}
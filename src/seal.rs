@@ -0,0 +1,130 @@
+use crate::Config;
+
+use std::{
+  ffi::OsString,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+// records which key sealed the config. it lives in the data directory rather than the config, so
+// whoever can edit the config can neither unseal it nor choose the key it must be signed by
+pub const MARKER_FILE: &str = "seal";
+
+pub fn signature_path(config_path: &Path) -> PathBuf {
+  let mut path = OsString::from(config_path.as_os_str());
+  path.push(".sig");
+  PathBuf::from(path)
+}
+
+fn sealed_by(data_dir: &Path) -> Result<Option<String>, i32> {
+  let path = data_dir.join(MARKER_FILE);
+  match std::fs::read_to_string(&path) {
+    Ok(key) => Ok(Some(key.trim().to_string())),
+    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => {
+      error!("could not read {}: {}", path.to_string_lossy(), e);
+      Err(1)
+    },
+  }
+}
+
+// checked on the raw file before it is parsed, so nothing in an unverified config is acted on
+pub fn verify(config_path: &Path, data_dir: &Path, contents: &[u8]) -> Result<(), i32> {
+  let key = match sealed_by(data_dir)? {
+    Some(k) => k,
+    None => return Ok(()),
+  };
+  crate::timings::time("gpgme init", gpgme::init);
+
+  let sig_path = signature_path(config_path);
+  if !sig_path.exists() {
+    error!("the config is sealed but {} is missing", sig_path.to_string_lossy());
+    info!("if you meant to change the config, review it and run `gpg-alias seal`");
+    return Err(1);
+  }
+
+  let signature = crate::read_signature(&sig_path)?;
+  if crate::verify_detached(&key, signature, contents).is_err() {
+    error!("{} has changed since it was sealed", config_path.to_string_lossy());
+    info!("if you meant to change the config, review it and run `gpg-alias seal`");
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+pub fn run(config: &Config) -> i32 {
+  match seal(config, true) {
+    Ok(()) => 0,
+    Err(exit) => exit,
+  }
+}
+
+// re-seals after gpg-alias edits the config itself, since those edits were asked for
+pub fn refresh(config: &Config) -> Result<(), i32> {
+  let data_dir = crate::data_dir(config)?;
+  match sealed_by(data_dir)? {
+    Some(ref key) if *key == config.signing.key => seal(config, false),
+    Some(_) => {
+      warn!("the config was sealed with another key: run `gpg-alias seal` to seal it again");
+      Ok(())
+    },
+    None => Ok(()),
+  }
+}
+
+fn seal(config: &Config, interactive: bool) -> Result<(), i32> {
+  let key = &config.signing.key;
+  if key.is_empty() {
+    error!("sealing the config needs a signing key: set `key` in [signing]");
+    return Err(1);
+  }
+  if config.no_write {
+    error!("--no-write prevents sealing the config");
+    return Err(1);
+  }
+  if config.simulate {
+    info!("simulating: the config would be sealed with key `{}`", key);
+    return Ok(());
+  }
+
+  let contents = match std::fs::read(&config.path) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not read {}: {}", config.path.to_string_lossy(), e);
+      return Err(1);
+    },
+  };
+
+  let data_dir = crate::data_dir(config)?;
+  if interactive {
+    match sealed_by(data_dir)? {
+      Some(ref old) if old != key => warn!("the config was sealed with key `{}` and will now be sealed with `{}`", old, key),
+      Some(_) => warn!("sealing vouches for everything in {}: review it first", config.path.to_string_lossy()),
+      None => info!("from now on, the config will be refused if it changes without being sealed again"),
+    }
+    if !crate::confirm(config, &config.prompts.text("confirm_seal", &[]))? {
+      error!("sealing the config was not authorised");
+      return Err(1);
+    }
+  }
+
+  info!("signing config. you may need to enter your pgp passphrase");
+  let signature = crate::sign_detached(config, &contents)?;
+
+  write(&signature_path(&config.path), &signature)?;
+  write(&data_dir.join(MARKER_FILE), key.as_bytes())?;
+
+  info!("sealed {}", config.path.to_string_lossy());
+  Ok(())
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), i32> {
+  let res = crate::files::create_file(path).and_then(|mut f| f.write_all(data));
+  if let Err(e) = res {
+    error!("could not write {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,101 @@
+use clap::ArgMatches;
+use gpgme::{Context, Protocol};
+
+use crate::{Config, Method, timings::format_duration};
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let start = Instant::now();
+  let mut failed = false;
+
+  if config.signing.enabled && config.signing.method == Method::Hmac {
+    warn!("the hmac method doesn't use a signing key, skipping sign and verify stages");
+  } else if config.signing.enabled {
+    let nonce = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_nanos())
+      .unwrap_or_default();
+    let payload = format!("gpg-alias selftest {}", nonce);
+
+    match stage("sign", || crate::sign(config, &payload, None)) {
+      Ok(signed) => if stage("verify", || crate::verify_signed(&config.signing.key, signed, &payload, None, None)).is_err() {
+        failed = true;
+      },
+      Err(_) => failed = true,
+    }
+    if stage("signing key strength", || signing_key_strength(config)).is_err() {
+      failed = true;
+    }
+  } else {
+    warn!("signing is disabled, skipping sign and verify stages");
+  }
+
+  let alias = match matches.value_of("alias") {
+    Some(a) => Some(a),
+    None => {
+      let mut names: Vec<&str> = config.aliases.keys().map(String::as_str).collect();
+      names.sort();
+      names.first().cloned()
+    },
+  };
+
+  match alias {
+    Some(alias) => if stage(&format!("resolve `{}`", alias), || resolve(config, alias)).is_err() {
+      failed = true;
+    },
+    None => warn!("no aliases configured, skipping resolve stage"),
+  }
+
+  if failed {
+    error!("selftest failed after {}", format_duration(start.elapsed()));
+    return 1;
+  }
+
+  info!("selftest passed in {}", format_duration(start.elapsed()));
+  0
+}
+
+fn resolve(config: &Config, alias: &str) -> Result<(), i32> {
+  let key_id = crate::resolve(config, alias)?;
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  if let Err(e) = ctx.get_key(&*key_id) {
+    error!("could not find key `{}` in keyring: {}", key_id, e);
+    return Err(1);
+  }
+
+  Ok(())
+}
+
+// the signing key vouches for every alias, so a weak one matters more than any alias's
+fn signing_key_strength(config: &Config) -> Result<(), i32> {
+  let weaknesses = crate::strength::find(&config.signing.key, config.validity.min_rsa_bits)?;
+  for weakness in &weaknesses {
+    warn!("signing key `{}` is weak: its {}", config.signing.key, weakness);
+  }
+
+  if config.validity.on_weak == crate::validity::Policy::Fail && !weaknesses.is_empty() {
+    return Err(1);
+  }
+  Ok(())
+}
+
+fn stage<T, F>(name: &str, f: F) -> Result<T, i32>
+  where F: FnOnce() -> Result<T, i32>,
+{
+  let start = Instant::now();
+  let res = f();
+  let elapsed = format_duration(start.elapsed());
+  match res {
+    Ok(_) => info!("{}: ok ({})", name, elapsed),
+    Err(_) => error!("{}: failed ({})", name, elapsed),
+  }
+  res
+}
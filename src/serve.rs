@@ -0,0 +1,261 @@
+use clap::ArgMatches;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::Config;
+
+use std::{
+  collections::HashMap,
+  io::{BufRead, Write},
+};
+
+// JSON-RPC's own error codes, for requests that never reached a method
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// this server's own, in the range JSON-RPC leaves to servers. they stand for the exit statuses the
+// command line would have had, which are left for the command line
+const FAILED: i64 = -32000;
+const UNKNOWN_ALIAS: i64 = -32001;
+const UNVERIFIED: i64 = -32002;
+const KEY_PROBLEM: i64 = -32003;
+
+#[derive(Deserialize)]
+struct Request {
+  jsonrpc: Option<String>,
+  // requests without one are notifications, which get no response
+  id: Option<Value>,
+  method: String,
+  #[serde(default)]
+  params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+  jsonrpc: &'static str,
+  id: Value,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  result: Option<Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<Error>,
+}
+
+#[derive(Serialize)]
+struct Error {
+  code: i64,
+  message: String,
+}
+
+impl Error {
+  fn new<S: Into<String>>(code: i64, message: S) -> Error {
+    Error { code, message: message.into() }
+  }
+}
+
+#[derive(Deserialize)]
+struct AliasParams {
+  alias: String,
+}
+
+#[derive(Deserialize)]
+struct AddParams {
+  alias: String,
+  key: String,
+}
+
+// what tools such as editor plugins keep running: one JSON-RPC 2.0 request per line on stdin and
+// one response per line on stdout. stdin carries the protocol, so nothing here may ever prompt.
+// unlike the native host, a changed config is loaded again rather than ending the session
+pub fn run(config: Config, root: &ArgMatches, matches: &ArgMatches) -> i32 {
+  if !matches.is_present("stdio") {
+    error!("only --stdio is supported");
+    return 1;
+  }
+
+  let mut config = config;
+  let mut stamp = crate::generation::current(&config);
+  // keys of the aliases verified since the config was loaded, so only the keys themselves are
+  // checked again
+  let mut verified: HashMap<String, Vec<String>> = HashMap::new();
+
+  let stdin = std::io::stdin();
+  let stdout = std::io::stdout();
+  let mut output = stdout.lock();
+  for line in stdin.lock().lines() {
+    let line = match line {
+      Ok(l) => l,
+      Err(e) => {
+        error!("could not read request: {}", e);
+        return 1;
+      },
+    };
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    if crate::generation::current(&config) != stamp {
+      info!("the config has changed, so it is being loaded again");
+      config = match crate::load_config(root) {
+        Ok(c) => c,
+        Err(exit) => return exit,
+      };
+      stamp = crate::generation::current(&config);
+      verified.clear();
+    }
+
+    let response = match serde_json::from_str::<Request>(&line) {
+      Ok(req) if req.jsonrpc.as_deref() != Some("2.0") => Some(reply(req.id.unwrap_or(Value::Null), Err(Error::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")))),
+      Ok(req) => {
+        let result = handle(&config, &mut verified, &req.method, req.params);
+        req.id.map(|id| reply(id, result))
+      },
+      Err(e) => Some(reply(Value::Null, Err(Error::new(PARSE_ERROR, format!("invalid request: {}", e))))),
+    };
+    let response = match response {
+      Some(r) => r,
+      None => continue,
+    };
+
+    let res = serde_json::to_string(&response)
+      .map_err(std::io::Error::from)
+      .and_then(|json| writeln!(output, "{}", json))
+      .and_then(|_| output.flush());
+    if let Err(e) = res {
+      error!("could not write response: {}", e);
+      return 1;
+    }
+  }
+
+  0
+}
+
+fn reply(id: Value, result: Result<Value, Error>) -> Response {
+  let (result, error) = match result {
+    Ok(r) => (Some(r), None),
+    Err(e) => (None, Some(e)),
+  };
+  Response { jsonrpc: "2.0", id, result, error }
+}
+
+fn handle(config: &Config, verified: &mut HashMap<String, Vec<String>>, method: &str, params: Value) -> Result<Value, Error> {
+  match method {
+    "resolve" => {
+      let params: AliasParams = parse(params)?;
+      resolve(config, verified, &params.alias).map(|keys| json!({ "keys": keys }))
+    },
+    "verify" => {
+      let params: AliasParams = parse(params)?;
+      Ok(json!({ "verified": resolve(config, verified, &params.alias).is_ok() }))
+    },
+    "list" => Ok(list(config)),
+    "add" => {
+      let params: AddParams = parse(params)?;
+      add(config, &params.alias, &params.key).map(|_| json!({ "alias": params.alias, "key": params.key }))
+    },
+    other => Err(Error::new(METHOD_NOT_FOUND, format!("unknown method: {}", other))),
+  }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, Error> {
+  serde_json::from_value(params).map_err(|e| Error::new(INVALID_PARAMS, format!("invalid params: {}", e)))
+}
+
+// like `--only-verified`: only what verifies is ever handed out. errors are told apart the way
+// the command line's exit statuses tell them apart
+fn resolve(config: &Config, verified: &mut HashMap<String, Vec<String>>, alias: &str) -> Result<Vec<String>, Error> {
+  // an attestation stays good while the config is unchanged, but a key can be revoked or expire
+  // at any time
+  if let Some(keys) = verified.get(alias) {
+    let keys = keys.clone();
+    if let Err(e) = check_keys(config, alias, &keys) {
+      verified.remove(alias);
+      return Err(e);
+    }
+    return Ok(keys);
+  }
+
+  let keys = if config.groups.contains_key(alias) {
+    crate::groups::expand(config, alias, true)
+      .map_err(|_| Error::new(UNVERIFIED, format!("group `{}` could not be verified", alias)))?
+  } else {
+    let key = crate::resolve_verified(config, alias).map_err(|_| if config.aliases.contains_key(alias) {
+      Error::new(UNVERIFIED, format!("alias `{}` could not be verified", alias))
+    } else {
+      Error::new(UNKNOWN_ALIAS, format!("no such alias `{}`", alias))
+    })?;
+    vec![key.to_string()]
+  };
+  check_keys(config, alias, &keys)?;
+
+  if !crate::fresh::is_required(config, alias) {
+    verified.insert(alias.to_string(), keys.clone());
+  }
+  Ok(keys)
+}
+
+fn check_keys(config: &Config, alias: &str, keys: &[String]) -> Result<(), Error> {
+  for key in keys {
+    if crate::validity::check(config, alias, key).is_err() {
+      return Err(Error::new(KEY_PROBLEM, format!("the key `{}` for `{}` is unusable", key, alias)));
+    }
+  }
+
+  Ok(())
+}
+
+fn list(config: &Config) -> Value {
+  let mut aliases: Vec<Value> = config.aliases.iter()
+    .map(|(name, alias)| json!({
+      "alias": name,
+      "key": alias.key(),
+      "system": config.system.aliases.contains(name),
+    }))
+    .collect();
+  aliases.sort_by(|a, b| a["alias"].as_str().cmp(&b["alias"].as_str()));
+  let mut groups: Vec<Value> = config.groups.iter()
+    .map(|(name, members)| json!({ "group": name, "members": members }))
+    .collect();
+  groups.sort_by(|a, b| a["group"].as_str().cmp(&b["group"].as_str()));
+
+  json!({ "aliases": aliases, "groups": groups })
+}
+
+// nothing can be asked here, so attesting is only done when `serve` was started with --yes, making
+// the request the confirmation. the config is edited, which the next request notices and loads again
+fn add(config: &Config, alias: &str, key_id: &str) -> Result<(), Error> {
+  let description = format!("add alias `{}`", alias);
+  if config.signing.enabled && crate::tty::preset_answer() != Some(true) {
+    return Err(Error::new(FAILED, "adding an alias attests it, which needs confirming: start `serve` with --yes to let requests confirm it"));
+  }
+  if config.aliases.contains_key(alias) || config.groups.contains_key(alias) {
+    return Err(Error::new(FAILED, format!("`{}` already exists", alias)));
+  }
+  if let Some(part) = crate::naming::unsafe_part(alias) {
+    return Err(Error::new(FAILED, format!("the name `{}` {}, which can't be part of a file name", alias, part)));
+  }
+  if let Some(problem) = crate::naming::problem(config, alias) {
+    return Err(Error::new(FAILED, format!("the name `{}` {}, as the [naming] rules require", alias, problem)));
+  }
+  if crate::manage::check_writable(config, &description).is_err() {
+    return Err(Error::new(FAILED, "the config can't be edited"));
+  }
+  if crate::manage::check_key(key_id).is_err() {
+    return Err(Error::new(KEY_PROBLEM, format!("key `{}` is not in the keyring", key_id)));
+  }
+
+  let failed = |message: &str| Error::new(FAILED, format!("alias `{}` was not added: {}", alias, message));
+  if config.signing.enabled {
+    // a leftover attestation from an earlier alias of the same name would vouch for the wrong key
+    crate::manage::remove_attestations(config, alias).map_err(|_| failed("an old attestation could not be removed"))?;
+    crate::attest(config, alias, key_id).map_err(|_| failed("it could not be attested"))?;
+  }
+  if crate::manage::edit_config(config, &description, |path| crate::edit::add_alias(path, alias, key_id, &[])).is_err() {
+    let _ = crate::manage::remove_attestations(config, alias);
+    return Err(failed("the config could not be edited"));
+  }
+
+  info!("added alias `{}` for key `{}`", alias, key_id);
+  Ok(())
+}
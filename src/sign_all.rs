@@ -0,0 +1,136 @@
+use clap::ArgMatches;
+
+use crate::{progress::Progress, Config, Method};
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let json = matches.value_of("progress-format") == Some("json");
+  if !config.signing.enabled {
+    warn!("signing is disabled, so there is nothing to sign");
+    return 0;
+  }
+
+  let mut names: Vec<&str> = config.aliases.keys()
+    .map(String::as_str)
+    .filter(|name| !config.system.aliases.contains(*name))
+    .collect();
+  names.sort();
+
+  // existing attestations that don't verify are left alone: resolving the alias walks through
+  // what to do about them, and signing over them here would hide the disagreement
+  let mut failed = 0;
+  let mut unsigned = Vec::new();
+  for name in names {
+    let key_id = config.aliases[name].key();
+    match crate::attestation_exists(config, name, key_id) {
+      Ok(false) => unsigned.push((name, key_id)),
+      Ok(true) => if crate::verify_attestation(config, name, key_id).is_err() {
+        error!("alias `{}` has an attestation that does not verify: resolve it to fix it", name);
+        failed += 1;
+      },
+      Err(_) => failed += 1,
+    }
+  }
+
+  if unsigned.is_empty() {
+    info!("every alias is already attested");
+  } else {
+    warn!("{} alias(es) have no attestation:", unsigned.len());
+    if let Err(exit) = attest_all(config, &unsigned, json) {
+      return exit;
+    }
+    info!("attested {} alias(es)", unsigned.len());
+  }
+
+  // after the aliases, since a group's attestation covers its members' verified keys
+  match groups(config, json) {
+    Ok(group_failures) => failed += group_failures,
+    Err(exit) => return exit,
+  }
+  if failed > 0 {
+    error!("{} alias(es) or group(s) could not be verified", failed);
+    return 1;
+  }
+
+  0
+}
+
+// each group's membership is attested as the sorted keys it expands to, so someone editing the
+// config can't add themselves to a group unnoticed. local certifications are on the members' keys,
+// which attesting the members already did
+fn groups(config: &Config, json: bool) -> Result<usize, i32> {
+  if config.groups.is_empty() || config.signing.method == Method::Lsign {
+    return Ok(0);
+  }
+
+  let mut names: Vec<&str> = config.groups.keys().map(String::as_str).collect();
+  names.sort();
+
+  let mut failed = 0;
+  let mut unsigned: Vec<(&str, String)> = Vec::new();
+  for name in names {
+    let id = match crate::groups::attested_id(config, name) {
+      Ok(id) => id,
+      Err(_) => {
+        error!("group `{}` has members that do not verify: attest them first", name);
+        failed += 1;
+        continue;
+      },
+    };
+    match crate::attestation_exists(config, name, &id) {
+      Ok(false) => unsigned.push((name, id)),
+      Ok(true) => if crate::verify_attestation(config, name, &id).is_err() {
+        error!("group `{}` has an attestation that does not verify: resolve it to fix it", name);
+        failed += 1;
+      },
+      Err(_) => failed += 1,
+    }
+  }
+
+  if unsigned.is_empty() {
+    return Ok(failed);
+  }
+  warn!("{} group(s) have no attestation:", unsigned.len());
+  let entries: Vec<(&str, &str)> = unsigned.iter().map(|(n, id)| (*n, id.as_str())).collect();
+  attest_all(config, &entries, json)?;
+  info!("attested {} group(s)", unsigned.len());
+  Ok(failed)
+}
+
+// attests the listed aliases after a single confirmation, for anything that changes or finds
+// several at once. a single alias gets the usual prompt, which says more about what is being asked
+pub fn attest(config: &Config, entries: &[(&str, &str)]) -> Result<(), i32> {
+  attest_all(config, entries, false)
+}
+
+fn attest_all(config: &Config, entries: &[(&str, &str)], json: bool) -> Result<(), i32> {
+  if let [(name, key_id)] = entries {
+    return crate::create_certification(config, name, key_id).map(|_| ());
+  }
+
+  for (name, key_id) in entries {
+    warn!("  `{}` points to key ID `{}`", name, key_id);
+  }
+  let count = entries.len().to_string();
+  if !crate::confirm(config, &config.prompts.text("confirm_sign_all", &[("count", &count)]))? {
+    error!("attesting the aliases was not authorised");
+    return Err(1);
+  }
+
+  let mut failed = 0;
+  let mut progress = Progress::new("sign-all", entries.len(), json);
+  for (i, (name, key_id)) in entries.iter().enumerate() {
+    crate::batch::set_pending(entries[i..].iter().map(|(n, _)| n.to_string()).collect());
+    if crate::attest(config, name, key_id).is_err() {
+      failed += 1;
+    }
+    progress.step(name);
+  }
+  progress.finish();
+
+  if failed > 0 {
+    error!("{} alias(es) could not be attested", failed);
+    return Err(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,39 @@
+use std::{path::PathBuf, sync::Mutex};
+
+// the data directory of this run's simulation, once there is one. a config loaded again (as `serve`
+// does) keeps using it, so what was attested earlier in the run is still there
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// simulations start from an empty data directory of their own, so nothing real is touched. the
+// name is random and the directory private, so no one else can guess it or read what's put there
+pub fn data_dir() -> Result<PathBuf, i32> {
+  let mut dir = DATA_DIR.lock().unwrap_or_else(|e| e.into_inner());
+  if let Some(path) = dir.as_ref() {
+    return Ok(path.clone());
+  }
+
+  let mut random = [0; 8];
+  if let Err(e) = getrandom::getrandom(&mut random) {
+    error!("could not name the simulation's data directory: {}", e);
+    return Err(1);
+  }
+  let path = std::env::temp_dir().join(format!("gpg-alias-simulate-{}", hex::encode(random)));
+  if let Err(e) = crate::files::create_new_dir(&path) {
+    error!("could not create the simulation's data directory {}: {}", path.to_string_lossy(), e);
+    return Err(1);
+  }
+
+  debug!("simulating with data directory {}", path.to_string_lossy());
+  *dir = Some(path.clone());
+  Ok(path)
+}
+
+// nothing a simulation wrote is meant to outlive it
+pub fn clean_up() {
+  let dir = DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()).take();
+  if let Some(path) = dir {
+    if let Err(e) = std::fs::remove_dir_all(&path) {
+      warn!("could not remove the simulation's data directory {}: {}", path.to_string_lossy(), e);
+    }
+  }
+}
@@ -0,0 +1,121 @@
+use crate::Config;
+
+use std::{borrow::Cow, fmt};
+
+// what went wrong is logged through the `log` crate as it happens, so these only say which kind of
+// failure it was and for which alias
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+  // the config (or the system config, or the seal over it) could not be loaded
+  Config,
+  UnknownAlias(String),
+  // the alias's attestation is missing or does not verify
+  Unverified(String),
+  // the alias's key is missing from the keyring or fails the validity policy
+  KeyProblem(String),
+  // a new attestation could not be made
+  Attestation(String),
+}
+
+impl Error {
+  // the status the command line exits with for the same failure
+  pub fn exit_code(&self) -> i32 {
+    match *self {
+      Error::UnknownAlias(_) => crate::EXIT_UNKNOWN_ALIAS,
+      Error::Unverified(_) => crate::EXIT_UNVERIFIED,
+      Error::KeyProblem(_) => crate::EXIT_KEY_PROBLEM,
+      Error::Config | Error::Attestation(_) => 1,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::Config => write!(f, "the config could not be loaded"),
+      Error::UnknownAlias(ref alias) => write!(f, "no such alias `{}`", alias),
+      Error::Unverified(ref alias) => write!(f, "alias `{}` could not be verified", alias),
+      Error::KeyProblem(ref alias) => write!(f, "the key for alias `{}` cannot be used", alias),
+      Error::Attestation(ref alias) => write!(f, "alias `{}` could not be attested", alias),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedKey {
+  pub alias: String,
+  pub key_id: String,
+  pub fingerprint: Option<String>,
+  // false when signing is disabled in the config, so there was no attestation to check
+  pub attested: bool,
+}
+
+// alias resolution for other tools. unlike the command line it never prompts: an alias whose
+// attestation is missing is an error, not a question
+pub struct AliasStore {
+  config: Config,
+}
+
+impl AliasStore {
+  pub fn new(config: Config) -> AliasStore {
+    gpgme::init();
+    AliasStore { config }
+  }
+
+  pub fn open() -> Result<AliasStore, Error> {
+    Config::load().map(AliasStore::new)
+  }
+
+  pub fn aliases(&self) -> Vec<&str> {
+    let mut names: Vec<&str> = self.config.aliases.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names
+  }
+
+  pub fn resolve(&self, alias: &str) -> Result<ResolvedKey, Error> {
+    let key_id = self.verified_key(alias)?;
+    if crate::validity::check(&self.config, alias, &key_id).is_err() {
+      return Err(Error::KeyProblem(alias.to_string()));
+    }
+
+    Ok(ResolvedKey {
+      alias: alias.to_string(),
+      key_id: key_id.to_string(),
+      fingerprint: crate::pin::fingerprint(&key_id),
+      attested: self.config.signing.enabled,
+    })
+  }
+
+  pub fn verify(&self, alias: &str) -> Result<(), Error> {
+    self.verified_key(alias).map(|_| ())
+  }
+
+  // the alias's key, once its attestation verifies. the command line resolves through this too
+  // when only verified aliases may be used, so both always agree
+  pub(crate) fn verified_key(&self, alias: &str) -> Result<Cow<'_, str>, Error> {
+    if !self.config.aliases.contains_key(alias) {
+      return Err(Error::UnknownAlias(alias.to_string()));
+    }
+    crate::resolve_verified(&self.config, alias).map_err(|_| Error::Unverified(alias.to_string()))
+  }
+
+  pub(crate) fn config(&self) -> &Config {
+    &self.config
+  }
+
+  // attests the alias's configured key with the configured method, as `gpg-alias add` does. the
+  // caller is responsible for having the key confirmed first
+  pub fn attest(&self, alias: &str) -> Result<(), Error> {
+    let key_id = match self.config.aliases.get(alias) {
+      Some(a) => a.key(),
+      None => return Err(Error::UnknownAlias(alias.to_string())),
+    };
+    if !self.config.signing.enabled {
+      error!("signing is disabled, so alias `{}` cannot be attested", alias);
+      return Err(Error::Attestation(alias.to_string()));
+    }
+    crate::attest(&self.config, alias, key_id).map_err(|_| Error::Attestation(alias.to_string()))
+  }
+}
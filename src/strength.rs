@@ -0,0 +1,75 @@
+use gpgme::{Context, Key, KeyAlgorithm};
+
+use crate::{validity::Policy, Config};
+
+// RSA and ElGamal keys smaller than this are weak
+pub const DEFAULT_MIN_BITS: usize = 2048;
+
+// keys are usable long after their algorithm stops being safe, so weak ones are reported at
+// resolution, separately from validity, with their own policy
+pub fn check(config: &Config, alias: &str, key_id: &str) -> Result<(), i32> {
+  let policy = config.validity.on_weak;
+  if policy == Policy::Ignore || crate::mock::is_enabled() {
+    return Ok(());
+  }
+
+  let weaknesses = find(key_id, config.validity.min_rsa_bits)?;
+  for weakness in &weaknesses {
+    match policy {
+      Policy::Fail => error!("key `{}` for `{}` is weak: its {}", key_id, alias, weakness),
+      _ => warn!("key `{}` for `{}` is weak: its {}", key_id, alias, weakness),
+    }
+  }
+
+  if policy == Policy::Fail && !weaknesses.is_empty() {
+    return Err(1);
+  }
+  Ok(())
+}
+
+// a key missing from the keyring is validity's problem, not this one's
+pub fn find(key_id: &str, min_bits: Option<usize>) -> Result<Vec<String>, i32> {
+  let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  match ctx.get_key(key_id) {
+    Ok(key) => Ok(weaknesses(&key, min_bits)),
+    Err(_) => Ok(Vec::new()),
+  }
+}
+
+// looks at the primary key and every subkey still in use. gpgme doesn't say which hash
+// self-signatures were made with, so SHA-1 ones can't be caught here
+pub fn weaknesses(key: &Key, min_bits: Option<usize>) -> Vec<String> {
+  let min_bits = min_bits.unwrap_or(DEFAULT_MIN_BITS);
+
+  let mut weaknesses = Vec::new();
+  for (i, subkey) in key.subkeys().enumerate() {
+    if i > 0 && (subkey.is_revoked() || subkey.is_expired() || subkey.is_invalid()) {
+      continue;
+    }
+    let which = if i == 0 {
+      "primary key".to_string()
+    } else {
+      format!("subkey {}", subkey.id().unwrap_or("?"))
+    };
+
+    let bits = subkey.length();
+    match subkey.algorithm() {
+      KeyAlgorithm::Rsa | KeyAlgorithm::RsaEncrypt | KeyAlgorithm::RsaSign if bits < min_bits => {
+        weaknesses.push(format!("{} is {}-bit RSA", which, bits));
+      },
+      KeyAlgorithm::Dsa => weaknesses.push(format!("{} is DSA", which)),
+      KeyAlgorithm::Elgamal | KeyAlgorithm::ElgamalEncrypt if bits < min_bits => {
+        weaknesses.push(format!("{} is {}-bit ElGamal", which, bits));
+      },
+      _ => {},
+    }
+  }
+
+  weaknesses
+}
@@ -0,0 +1,50 @@
+use std::{
+  sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STAGES: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+pub fn enable() {
+  ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn time<S, T, F>(stage: S, f: F) -> T
+  where S: Into<String>,
+        F: FnOnce() -> T,
+{
+  if !ENABLED.load(Ordering::SeqCst) {
+    return f();
+  }
+
+  let start = Instant::now();
+  let res = f();
+  let elapsed = start.elapsed();
+  if let Ok(mut stages) = STAGES.lock() {
+    stages.push((stage.into(), elapsed));
+  }
+  res
+}
+
+pub fn report() {
+  if !ENABLED.load(Ordering::SeqCst) {
+    return;
+  }
+
+  let stages = match STAGES.lock() {
+    Ok(s) => s,
+    Err(_) => return,
+  };
+  let width = stages.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+  for (name, elapsed) in stages.iter() {
+    info!("{:<width$}  {}", name, format_duration(*elapsed), width = width);
+  }
+}
+
+pub fn format_duration(d: Duration) -> String {
+  format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,105 @@
+use std::{
+  fs::OpenOptions,
+  io::{BufRead, BufReader, Write},
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+static BATCH: AtomicBool = AtomicBool::new(false);
+static YES: AtomicBool = AtomicBool::new(false);
+
+// without a terminal on stdin nobody can answer, so prompting would only hang a git hook or make
+// run. such runs are batch runs, declining whatever would have been asked
+pub fn set_mode(batch: bool, yes: bool) {
+  YES.store(yes, Ordering::SeqCst);
+  BATCH.store(!yes && (batch || !stdin_is_tty()), Ordering::SeqCst);
+}
+
+// the answer to give every confirmation without asking, if any
+pub fn preset_answer() -> Option<bool> {
+  if YES.load(Ordering::SeqCst) {
+    Some(true)
+  } else if BATCH.load(Ordering::SeqCst) {
+    Some(false)
+  } else {
+    None
+  }
+}
+
+pub fn ask(prompt: &str) -> Result<String, i32> {
+  read_line(prompt).map(|r| r.to_lowercase())
+}
+
+// prompts go to the terminal itself where there is one, so they still work with stdout captured
+pub fn read_line(prompt: &str) -> Result<String, i32> {
+  let mut resp = String::with_capacity(1);
+  match OpenOptions::new().read(true).write(true).open("/dev/tty") {
+    Ok(tty) => {
+      let mut writer = &tty;
+      write!(writer, "{}", prompt).and_then(|_| writer.flush()).map_err(|_| 1)?;
+      BufReader::new(&tty).read_line(&mut resp).map_err(|_| 1)?;
+    },
+    Err(_) => {
+      print!("{}", prompt);
+      std::io::stdout().flush().map_err(|_| 1)?;
+      std::io::stdin().read_line(&mut resp).map_err(|_| 1)?;
+    },
+  }
+  Ok(resp.trim().to_string())
+}
+
+// pinentry asks gpg-agent which terminal to use, which gpg only knows via GPG_TTY. set it to our
+// controlling terminal if the environment didn't, returning whether a terminal is known at all
+pub fn ensure_gpg_tty() -> bool {
+  if std::env::var_os("GPG_TTY").is_some() {
+    return true;
+  }
+
+  match controlling_tty() {
+    Some(tty) => {
+      debug!("setting GPG_TTY to {}", tty);
+      std::env::set_var("GPG_TTY", tty);
+      true
+    },
+    None => false,
+  }
+}
+
+#[cfg(unix)]
+fn controlling_tty() -> Option<String> {
+  use std::ffi::CStr;
+
+  for fd in 0..3 {
+    let name = unsafe { libc::ttyname(fd) };
+    if !name.is_null() {
+      let name = unsafe { CStr::from_ptr(name) };
+      return Some(name.to_string_lossy().into_owned());
+    }
+  }
+
+  None
+}
+
+#[cfg(not(unix))]
+fn controlling_tty() -> Option<String> {
+  None
+}
+
+#[cfg(unix)]
+fn stdin_is_tty() -> bool {
+  unsafe { libc::isatty(0) == 1 }
+}
+
+#[cfg(not(unix))]
+fn stdin_is_tty() -> bool {
+  true
+}
+
+#[cfg(unix)]
+pub fn stderr_is_tty() -> bool {
+  unsafe { libc::isatty(2) == 1 }
+}
+
+#[cfg(not(unix))]
+pub fn stderr_is_tty() -> bool {
+  true
+}
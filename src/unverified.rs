@@ -0,0 +1,91 @@
+use clap::ArgMatches;
+
+use crate::{output, Config};
+
+// without a usable gpg nothing can be verified, so this only prints what the config says, loudly.
+// it's opt-in, for minimal containers where looking up a mapping is all that's needed
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  // asking for verification means not settling for less, so there's no falling back
+  let aliases: Vec<&str> = matches.values_of("alias").into_iter().flatten().collect();
+  if matches.is_present("only-verified") {
+    error!("gpg cannot be used, so nothing can be verified as --only-verified requires");
+    return crate::EXIT_UNVERIFIED;
+  }
+  if config.fresh {
+    error!("gpg cannot be used, so nothing can be refreshed as --fresh requires");
+    return crate::EXIT_UNVERIFIED;
+  }
+  if let Some(alias) = aliases.iter().find(|a| crate::fresh::is_required(config, a)) {
+    error!("gpg cannot be used, so alias `{}` can't be refreshed as its require_fresh setting requires", alias);
+    return crate::EXIT_UNVERIFIED;
+  }
+
+  warn!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+  warn!("@    WARNING: GPG IS UNAVAILABLE. NOTHING IS VERIFIED!");
+  warn!("@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@");
+
+  if let Some(name) = matches.subcommand_name() {
+    error!("`{}` needs gpg, which is unavailable", name);
+    return 1;
+  }
+
+  if aliases.is_empty() {
+    error!("no aliases given: only aliases on the command line can be printed without gpg");
+    return 1;
+  }
+
+  let format = match matches.value_of("output-format").and_then(output::Format::from_name) {
+    Some(f) => f,
+    None if matches.is_present("recipients") => output::Format::Recipients,
+    None => output::Format::Plain,
+  };
+
+  let mut entries = Vec::with_capacity(aliases.len());
+  for alias in aliases {
+    let (key_id, protocol) = match config.aliases.get(alias) {
+      Some(a) => (a.key(), a.protocol()),
+      None if config.groups.contains_key(alias) => {
+        error!("`{}` is a group, which can't be expanded without gpg", alias);
+        return 1;
+      },
+      None => {
+        error!("no such alias found: `{}`", alias);
+        return crate::EXIT_UNKNOWN_ALIAS;
+      },
+    };
+    warn!("alias `{}` is printed as `{}` without being verified", alias, key_id);
+    entries.push(output::Entry {
+      alias: alias.to_string(),
+      key: key_id.to_string(),
+      fingerprint: None,
+      protocol: protocol.name(),
+      status: "unverified",
+    });
+  }
+
+  if let Some(template) = matches.value_of("template") {
+    for entry in &entries {
+      println!("{}", output::render(template, entry));
+    }
+    return 0;
+  }
+
+  match format {
+    output::Format::Json => match serde_json::to_string_pretty(&entries) {
+      Ok(json) => println!("{}", json),
+      Err(e) => {
+        error!("could not serialise output: {}", e);
+        return 1;
+      },
+    },
+    output::Format::Recipients => {
+      let args: Vec<String> = entries.iter().map(|e| format!("-r {}", e.key)).collect();
+      println!("{}", args.join(" "));
+    },
+    output::Format::Plain => for entry in &entries {
+      println!("{}", entry.key);
+    },
+  }
+
+  0
+}
@@ -0,0 +1,159 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+// checks the config's contents against the rules it sets itself, without touching keys or
+// attestations. system aliases are left to the administrator
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let fix = matches.is_present("fix");
+  if fix && crate::manage::check_writable(config, "fix the problems found").is_err() {
+    return 1;
+  }
+
+  let mut names: Vec<&String> = config.aliases.keys()
+    .filter(|name| !config.system.aliases.contains(name.as_str()))
+    .collect();
+  names.sort();
+
+  let mut problems = 0;
+  for name in &names {
+    if let Some(part) = crate::naming::unsafe_part(name) {
+      warn!("alias name `{}` {}, so it can't be attested", name.escape_default(), part);
+      problems += 1;
+      continue;
+    }
+    if let Some(problem) = crate::naming::problem(config, name) {
+      warn!("alias name `{}` {}", name, problem);
+      problems += 1;
+    }
+  }
+  problems += missing_members(config);
+  problems += duplicate_keys(config, &names);
+  let (shadowing, redundant) = shadowed(config, &names);
+  problems += shadowing;
+  unreferenced(config, &names);
+
+  if fix && !redundant.is_empty() {
+    let res = crate::manage::edit_config(config, &format!("remove {} alias(es) repeating system aliases", redundant.len()), |path| {
+      redundant.iter().try_for_each(|name| crate::edit::remove_alias(path, name))
+    });
+    if let Err(exit) = res {
+      return exit;
+    }
+    if !config.simulate {
+      for name in &redundant {
+        if let Err(exit) = crate::manage::remove_attestations(config, name) {
+          return exit;
+        }
+      }
+      info!("removed {} alias(es): the system aliases of the same name are used instead", redundant.len());
+    }
+    problems -= redundant.len();
+  }
+
+  if problems > 0 {
+    error!("{} problem(s) found in {}", problems, config.path.to_string_lossy());
+    return 1;
+  }
+  info!("no problems found in {}", config.path.to_string_lossy());
+  0
+}
+
+// a member that is no alias, group, `@name` or key ID is most likely a misspelt or removed alias,
+// which gpg would otherwise be asked to find as a key
+fn missing_members(config: &Config) -> usize {
+  let mut groups: Vec<&String> = config.groups.keys().collect();
+  groups.sort();
+
+  let mut problems = 0;
+  for group in groups {
+    for member in &config.groups[group.as_str()] {
+      if !is_known_member(config, member) {
+        warn!("group `{}` includes `{}`, which is not an alias, group or key ID", group, member);
+        info!("  add an alias named `{}`, or remove it from the group and confirm the group again", member);
+        problems += 1;
+      }
+    }
+  }
+  problems
+}
+
+pub fn is_known_member(config: &Config, member: &str) -> bool {
+  config.aliases.contains_key(member)
+    || config.groups.contains_key(member)
+    || member.starts_with('@')
+    || is_key_id(member)
+}
+
+pub fn is_key_id(member: &str) -> bool {
+  let id = member.trim_start_matches("0x").trim_end_matches('!');
+  id.len() >= 8 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// key IDs are compared as suffixes of each other, so a short ID and the fingerprint it ends are
+// the same key. which name to keep is for whoever uses them to decide
+fn duplicate_keys(config: &Config, names: &[&String]) -> usize {
+  let normalised: Vec<String> = names.iter()
+    .map(|n| config.aliases[n.as_str()].key().trim_start_matches("0x").trim_end_matches('!').to_uppercase())
+    .collect();
+
+  let mut problems = 0;
+  let mut reported = vec![false; names.len()];
+  for i in 0..names.len() {
+    if reported[i] || normalised[i].is_empty() {
+      continue;
+    }
+    let same: Vec<usize> = (0..names.len())
+      .filter(|j| !normalised[*j].is_empty() && (normalised[i].ends_with(&normalised[*j]) || normalised[*j].ends_with(&normalised[i])))
+      .collect();
+    if same.len() < 2 {
+      continue;
+    }
+    for j in &same {
+      reported[*j] = true;
+    }
+    let listed: Vec<String> = same.iter().map(|j| format!("`{}`", names[*j])).collect();
+    warn!("{} all point to key `{}`", listed.join(", "), config.aliases[names[i].as_str()].key());
+    info!("  keep one of them, and `gpg-alias rename` or `gpg-alias remove` the others");
+    problems += 1;
+  }
+  problems
+}
+
+// user aliases take precedence over system aliases of the same name. one that points to the same
+// key only repeats it, so it's safe to remove; one that doesn't hides what the administrator set
+fn shadowed(config: &Config, names: &[&String]) -> (usize, Vec<String>) {
+  let mut problems = 0;
+  let mut redundant = Vec::new();
+  for name in names {
+    let system_key = match config.system.shadowed.get(name.as_str()) {
+      Some(k) => k,
+      None => continue,
+    };
+    let key = config.aliases[name.as_str()].key();
+    if key == system_key {
+      warn!("alias `{}` repeats the system alias of the same name", name);
+      info!("  `gpg-alias validate --fix` removes it, so the system alias is used");
+      redundant.push(name.to_string());
+    } else {
+      warn!("alias `{}` shadows the system alias of the same name, which points to `{}` rather than `{}`", name, system_key, key);
+      info!("  `gpg-alias rename` it if both are wanted");
+    }
+    problems += 1;
+  }
+  (problems, redundant)
+}
+
+// only worth knowing once groups are in use, and not a problem in itself
+fn unreferenced(config: &Config, names: &[&String]) {
+  if config.groups.is_empty() {
+    return;
+  }
+  let unused: Vec<String> = names.iter()
+    .filter(|n| !config.groups.values().any(|members| members.contains(n)))
+    .map(|n| format!("`{}`", n))
+    .collect();
+  if !unused.is_empty() {
+    info!("not in any group: {}", unused.join(", "));
+  }
+}
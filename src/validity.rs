@@ -0,0 +1,112 @@
+use gpgme::Context;
+use serde_derive::Deserialize;
+
+use crate::Config;
+
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Deserialize)]
+pub struct Validity {
+  #[serde(default)]
+  pub on_problem: Policy,
+  pub expiry_window_days: Option<u64>,
+  #[serde(default = "default_on_weak")]
+  pub on_weak: Policy,
+  pub min_rsa_bits: Option<usize>,
+}
+
+impl Default for Validity {
+  fn default() -> Self {
+    Validity {
+      on_problem: Policy::default(),
+      expiry_window_days: None,
+      on_weak: default_on_weak(),
+      min_rsa_bits: None,
+    }
+  }
+}
+
+// weak keys still work, so they only warn unless asked otherwise
+fn default_on_weak() -> Policy {
+  Policy::Warn
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+  #[default]
+  #[serde(alias = "deny")]
+  Fail,
+  Warn,
+  Ignore,
+}
+
+// looks up the key an alias resolved to, so a key gpg would refuse is caught here rather than when
+// gpg fails later
+pub fn check(config: &Config, alias: &str, key_id: &str) -> Result<(), i32> {
+  crate::strength::check(config, alias, key_id)?;
+
+  // a key that has to be verified afresh gets no leeway
+  let policy = if crate::fresh::is_required(config, alias) {
+    Policy::Fail
+  } else {
+    config.aliases.get(alias)
+      .and_then(|a| a.entry())
+      .and_then(|e| e.on_problem)
+      .unwrap_or(config.validity.on_problem)
+  };
+  // mock keys don't exist in the keyring to be checked
+  if policy == Policy::Ignore || crate::mock::is_enabled() {
+    return Ok(());
+  }
+
+  let problems = problems(key_id, config.validity.expiry_window_days)?;
+  for problem in &problems {
+    match policy {
+      Policy::Fail => error!("key `{}` for `{}` {}", key_id, alias, problem),
+      _ => warn!("key `{}` for `{}` {}", key_id, alias, problem),
+    }
+  }
+
+  if policy == Policy::Fail && !problems.is_empty() {
+    return Err(1);
+  }
+  Ok(())
+}
+
+pub fn problems(key_id: &str, window_days: Option<u64>) -> Result<Vec<String>, i32> {
+  let mut ctx = match Context::from_protocol(crate::protocol::gpgme(key_id)) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return Err(1);
+    },
+  };
+  let key = match ctx.get_key(key_id) {
+    Ok(k) => k,
+    Err(_) => return Ok(vec!["is not in the keyring".to_string()]),
+  };
+
+  let mut problems = Vec::new();
+  if key.is_revoked() {
+    problems.push("has been revoked".to_string());
+  }
+  if key.is_disabled() {
+    problems.push("has been disabled".to_string());
+  }
+  if key.is_invalid() {
+    problems.push("is invalid".to_string());
+  }
+  if key.is_expired() {
+    problems.push("has expired".to_string());
+  } else if let Some(window) = window_days {
+    let expires = key.subkeys().next().and_then(|k| k.expiration_time());
+    let limit = SystemTime::now() + Duration::from_secs(window * 24 * 60 * 60);
+    if let Some(expires) = expires.filter(|e| *e <= limit) {
+      let days = expires.duration_since(SystemTime::now()).map(|d| d.as_secs() / (24 * 60 * 60)).unwrap_or(0);
+      problems.push(format!("expires in {} day(s)", days));
+    }
+  }
+
+  Ok(problems)
+}
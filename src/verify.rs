@@ -0,0 +1,228 @@
+use clap::ArgMatches;
+
+use crate::{progress::Progress, Config};
+
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+};
+
+pub enum Report {
+  Mail(String),
+  File(PathBuf),
+}
+
+impl Report {
+  fn parse(s: &str) -> Option<Report> {
+    if let Some(addr) = s.strip_prefix("mail:") {
+      return Some(Report::Mail(addr.to_string()));
+    }
+    if let Some(path) = s.strip_prefix("file:") {
+      return Some(Report::File(PathBuf::from(path)));
+    }
+    None
+  }
+}
+
+pub fn reports(matches: &ArgMatches) -> Result<Vec<Report>, i32> {
+  let mut reports = Vec::new();
+  for report in matches.values_of("report").into_iter().flatten() {
+    match Report::parse(report) {
+      Some(r) => reports.push(r),
+      None => {
+        error!("invalid report target `{}`: expected `mail:ADDRESS` or `file:PATH`", report);
+        return Err(1);
+      },
+    }
+  }
+
+  Ok(reports)
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let reports = match reports(matches) {
+    Ok(r) => r,
+    Err(exit) => return exit,
+  };
+
+  if !config.signing.enabled {
+    warn!("signing is disabled, so there is nothing to verify");
+    return 0;
+  }
+
+  // groups are checked too, both their own attestation and each member's
+  let mut names: Vec<&String> = config.aliases.keys().chain(config.groups.keys()).collect();
+  names.sort();
+
+  let mut progress = Progress::new("verify-all", names.len(), matches.value_of("format") == Some("json"));
+  let mut failures = Vec::new();
+  for name in &names {
+    if let Err(reason) = verify(config, name) {
+      failures.push((name.as_str(), reason));
+    }
+    progress.step(name);
+  }
+  progress.finish();
+
+  if failures.is_empty() {
+    info!("all {} aliases verified", names.len());
+  } else {
+    error!("{} of {} aliases failed verification", failures.len(), names.len());
+  }
+
+  let report = format_report(names.len(), &failures);
+  let subject = format!("{} alias verification failure(s)", failures.len());
+  let delivered = deliver(&reports, &subject, failures.is_empty(), &report);
+
+  if !failures.is_empty() || !delivered {
+    return 1;
+  }
+
+  0
+}
+
+// returns whether the report reached every target
+pub fn deliver(reports: &[Report], subject: &str, all_ok: bool, report: &str) -> bool {
+  let mut delivered = true;
+  for target in reports {
+    let res = match target {
+      Report::File(path) => write_file(path, report),
+      // only mail when something needs attention, so cron runs stay quiet otherwise
+      Report::Mail(_) if all_ok => Ok(()),
+      Report::Mail(addr) => send_mail(addr, subject, report),
+    };
+    if res.is_err() {
+      delivered = false;
+    }
+  }
+
+  delivered
+}
+
+fn verify(config: &Config, name: &str) -> Result<(), &'static str> {
+  if config.groups.contains_key(name) {
+    return verify_group(config, name);
+  }
+
+  let key_id = config.aliases[name].key();
+  match crate::attestation_exists(config, name, key_id) {
+    Ok(true) => {},
+    Ok(false) => {
+      error!("no attestation for alias `{}`", name);
+      return Err("no attestation");
+    },
+    Err(_) => return Err("could not check for an attestation"),
+  }
+
+  match crate::verify_attestation(config, name, key_id) {
+    Ok(_) => Ok(()),
+    Err(_) => {
+      error!("attestation for alias `{}` did not verify", name);
+      Err("attestation did not verify")
+    },
+  }
+}
+
+fn verify_group(config: &Config, name: &str) -> Result<(), &'static str> {
+  let id = match crate::groups::attested_id(config, name) {
+    Ok(id) => id,
+    Err(_) => {
+      error!("group `{}` has members that did not verify", name);
+      return Err("members did not verify");
+    },
+  };
+  // local certifications are on the members' keys, which were just verified
+  if config.signing.method == crate::Method::Lsign {
+    return Ok(());
+  }
+
+  match crate::attestation_exists(config, name, &id) {
+    Ok(true) => {},
+    Ok(false) => {
+      error!("no attestation for group `{}`", name);
+      return Err("no attestation");
+    },
+    Err(_) => return Err("could not check for an attestation"),
+  }
+
+  match crate::verify_attestation(config, name, &id) {
+    Ok(_) => Ok(()),
+    Err(_) => {
+      error!("attestation for group `{}` did not verify: its members may have changed", name);
+      Err("attestation did not verify")
+    },
+  }
+}
+
+fn format_report(checked: usize, failures: &[(&str, &str)]) -> String {
+  let mut report = format!(
+    "gpg-alias verification report\n\nchecked: {}\nfailed: {}\n",
+    checked,
+    failures.len(),
+  );
+
+  if !failures.is_empty() {
+    report.push('\n');
+  }
+  for (name, reason) in failures {
+    report.push_str(&format!("FAILED {}: {}\n", name, reason));
+  }
+
+  report
+}
+
+fn write_file(path: &Path, report: &str) -> Result<(), ()> {
+  let mut file = match crate::files::create_file(path) {
+    Ok(f) => f,
+    Err(e) => {
+      error!("could not create {}: {}", path.to_string_lossy(), e);
+      return Err(());
+    },
+  };
+  if let Err(e) = file.write_all(report.as_bytes()) {
+    error!("could not write report: {}", e);
+    return Err(());
+  }
+
+  Ok(())
+}
+
+fn send_mail(addr: &str, subject: &str, report: &str) -> Result<(), ()> {
+  let mut child = match Command::new("sendmail")
+    .arg("-t")
+    .stdin(Stdio::piped())
+    .spawn()
+  {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not run sendmail: {}", e);
+      return Err(());
+    },
+  };
+
+  let message = format!(
+    "To: {}\nSubject: gpg-alias: {}\n\n{}",
+    addr,
+    subject,
+    report,
+  );
+  if let Some(mut stdin) = child.stdin.take() {
+    if let Err(e) = stdin.write_all(message.as_bytes()) {
+      error!("could not write to sendmail: {}", e);
+      return Err(());
+    }
+  }
+
+  match child.wait() {
+    Ok(s) if s.success() => Ok(()),
+    Ok(s) => {
+      error!("sendmail exited with {}", s);
+      Err(())
+    },
+    Err(e) => {
+      error!("could not wait for sendmail: {}", e);
+      Err(())
+    },
+  }
+}
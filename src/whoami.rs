@@ -0,0 +1,111 @@
+use gpgme::{Context, Protocol};
+
+use crate::{Config, Method};
+
+use std::collections::BTreeMap;
+
+// the signing key as gpg sees it, and which attestations it made. after a key rotation, aliases
+// still attested by an older key are listed by that key, since they stop verifying once it's gone
+pub fn run(config: &Config) -> i32 {
+  if !config.signing.enabled {
+    println!("signing:     disabled");
+    return 0;
+  }
+  if config.signing.method == Method::Hmac {
+    println!("signing:     hmac, which doesn't use a signing key");
+    return 0;
+  }
+
+  let mut ctx = match Context::from_protocol(Protocol::OpenPgp) {
+    Ok(c) => c,
+    Err(e) => {
+      error!("could not create gpgme context: {}", e);
+      return 1;
+    },
+  };
+  let key = match ctx.get_key(config.signing.key.as_str()) {
+    Ok(k) => k,
+    Err(e) => {
+      error!("could not get signing key `{}`: {}", config.signing.key, e);
+      return 1;
+    },
+  };
+
+  println!("signing key: {}", config.signing.key);
+  println!("fingerprint: {}", key.fingerprint().unwrap_or(""));
+  for uid in key.user_ids().filter(|u| !u.is_revoked() && !u.is_invalid()) {
+    println!("uid:         {}", uid.id().unwrap_or(""));
+  }
+  let expires = match key.subkeys().next().and_then(|k| k.expiration_time()) {
+    Some(t) => crate::provenance::date(t),
+    None => "never".to_string(),
+  };
+  let state = if key.is_revoked() {
+    " (revoked)"
+  } else if key.is_expired() {
+    " (expired)"
+  } else {
+    ""
+  };
+  println!("expires:     {}{}", expires, state);
+  // the public key says nothing about where its secret half is kept
+  match ctx.get_secret_key(config.signing.key.as_str()) {
+    Ok(secret) => match secret.subkeys().find(|k| k.can_sign() && k.is_card_key()) {
+      Some(card) => println!("secret key:  on a smartcard ({})", card.card_serial_number().unwrap_or("unknown serial")),
+      None => println!("secret key:  in the keyring"),
+    },
+    Err(_) => println!("secret key:  not available"),
+  }
+
+  if config.signing.method == Method::Lsign {
+    println!("attestations: local certifications, which are always checked against the current signing key");
+    return 0;
+  }
+  if crate::mock::is_enabled() {
+    println!("attestations: made with the mock backend, so their signers can't be inspected");
+    return 0;
+  }
+  attestations(config, &mut ctx, &key)
+}
+
+fn attestations(config: &Config, ctx: &mut Context, key: &gpgme::Key) -> i32 {
+  let own: Vec<&str> = key.fingerprint().ok().into_iter()
+    .chain(key.subkeys().filter_map(|k| k.fingerprint().ok()))
+    .collect();
+
+  let mut names: Vec<&String> = config.aliases.keys()
+    .filter(|name| !config.system.aliases.contains(name.as_str()))
+    .chain(config.groups.keys())
+    .collect();
+  names.sort();
+
+  let mut current = 0;
+  let mut unattested = 0;
+  let mut older: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+  for name in names {
+    // checked first, since reading a missing attestation logs an error
+    let exists = crate::signature_path(config, name).map(|p| p.exists()).unwrap_or(false);
+    let id = config.aliases.get(name.as_str()).map_or("", |a| a.key());
+    let signer = if exists {
+      crate::audit::attestation(config, ctx, name, id).and_then(|a| a.signer)
+    } else {
+      None
+    };
+    match signer {
+      Some(signer) if own.contains(&signer.as_str()) => current += 1,
+      Some(signer) => older.entry(signer).or_default().push(name),
+      None => unattested += 1,
+    }
+  }
+
+  println!("attestations: {} by this key, {} by older keys, {} missing or unreadable", current, older.values().map(Vec::len).sum::<usize>(), unattested);
+  for (signer, names) in &older {
+    let owner = crate::pin::primary_uid(signer).map(|u| format!(" ({})", u)).unwrap_or_default();
+    println!("  by {}{}: {}", signer, owner, names.join(", "));
+  }
+  if !older.is_empty() {
+    info!("attestations made by older keys no longer verify: resolve each of those aliases to attest it again with this key");
+  }
+
+  0
+}
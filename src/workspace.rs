@@ -0,0 +1,30 @@
+use crate::Config;
+
+use std::path::PathBuf;
+
+// the default recipients of the innermost workspace containing the current directory, with the
+// workspace as it was written in the config
+pub fn defaults(config: &Config) -> Option<(&str, &[String])> {
+  let cwd = std::env::current_dir().ok()?;
+  let cwd = cwd.canonicalize().unwrap_or(cwd);
+
+  config.workspaces.iter()
+    .filter_map(|(dir, names)| {
+      let path = expand(dir);
+      let path = path.canonicalize().unwrap_or(path);
+      if cwd.starts_with(&path) {
+        Some((dir.as_str(), path.components().count(), names.as_slice()))
+      } else {
+        None
+      }
+    })
+    .max_by_key(|(_, depth, _)| *depth)
+    .map(|(dir, _, names)| (dir, names))
+}
+
+fn expand(dir: &str) -> PathBuf {
+  match (dir.strip_prefix("~/"), dirs::home_dir()) {
+    (Some(rest), Some(home)) => home.join(rest),
+    _ => PathBuf::from(dir),
+  }
+}
@@ -0,0 +1,199 @@
+use clap::ArgMatches;
+
+use crate::Config;
+
+use std::{
+  ffi::{OsStr, OsString},
+  path::Path,
+  process::Command,
+};
+
+// names gpg-alias answers to as a drop-in for gpg, e.g. through a symlink set as git's gpg.program
+const GPG_NAMES: &[&str] = &["gpg", "gpg2"];
+
+// short options whose value is a key, which may end a cluster such as `-ear alice` or be attached
+// to it, as in `-earalice`
+const SHORT: &[char] = &['r', 'R', 'u'];
+const LONG: &[&str] = &["--recipient", "--hidden-recipient", "--local-user"];
+// gpg's other short options that take a value, which ends the cluster they're in
+const SHORT_WITH_VALUE: &[char] = &['o', 'z', 'f', 'F', 'N'];
+
+pub fn invoked_as_gpg(args: &[OsString]) -> bool {
+  args.first()
+    .and_then(|a| Path::new(a).file_name())
+    .and_then(OsStr::to_str)
+    .is_some_and(|name| GPG_NAMES.contains(&name))
+}
+
+// everything after the program name is gpg's, so none of it may be taken as gpg-alias arguments
+pub fn as_wrap_args(args: Vec<OsString>) -> Vec<OsString> {
+  let mut wrapped = vec![OsString::from(clap::crate_name!()), OsString::from("wrap"), OsString::from("--")];
+  wrapped.extend(args.into_iter().skip(1));
+  wrapped
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> i32 {
+  let args: Vec<OsString> = matches.values_of_os("args").into_iter().flatten().map(OsString::from).collect();
+  let args = match rewrite(config, args) {
+    Ok(a) => a,
+    Err(exit) => return exit,
+  };
+
+  let gpg = match crate::gpg_program() {
+    Ok(g) => g,
+    Err(exit) => return exit,
+  };
+  if is_self(&gpg) {
+    error!("gpg is {}, which is gpg-alias itself: point gpgme at the real gpg", gpg);
+    return 1;
+  }
+
+  let mut cmd = Command::new(gpg);
+  cmd.args(args);
+  debug!("running {:?}", cmd);
+  exec(cmd)
+}
+
+// only values naming a configured alias or group are replaced; anything else is left for gpg to
+// interpret. a group becomes one option per key, so `-r team` may become several `-r`s
+fn rewrite(config: &Config, args: Vec<OsString>) -> Result<Vec<OsString>, i32> {
+  let mut rewritten = Vec::with_capacity(args.len());
+  // the option the next argument is the key for, to repeat for a group's other keys
+  let mut takes_key: Option<String> = None;
+  let mut options_done = false;
+
+  for arg in args {
+    let s = match arg.to_str() {
+      Some(s) => s,
+      None => {
+        takes_key = None;
+        rewritten.push(arg);
+        continue;
+      },
+    };
+
+    if let Some(option) = takes_key.take() {
+      let keys = keys_for(config, s)?;
+      rewritten.push(OsString::from(&keys[0]));
+      push_rest(&mut rewritten, &option, &keys);
+      continue;
+    }
+    if options_done {
+      rewritten.push(arg);
+      continue;
+    }
+
+    if s == "--" {
+      options_done = true;
+    } else if let Some((name, value)) = s.split_once('=').filter(|(name, _)| LONG.contains(name)) {
+      let keys = keys_for(config, value)?;
+      rewritten.push(OsString::from(format!("{}={}", name, keys[0])));
+      push_rest(&mut rewritten, name, &keys);
+      continue;
+    } else if LONG.contains(&s) {
+      takes_key = Some(s.to_string());
+    } else if !s.starts_with("--") && s.len() > 1 && s.starts_with('-') {
+      match short_key(s) {
+        // attached, as in `-ralice` or `-earalice`
+        Some((option, at)) if at < s.len() => {
+          let keys = keys_for(config, &s[at..])?;
+          rewritten.push(OsString::from(format!("{}{}", &s[..at], keys[0])));
+          push_rest(&mut rewritten, &format!("-{}", option), &keys);
+          continue;
+        },
+        Some((option, _)) => takes_key = Some(format!("-{}", option)),
+        None => {},
+      }
+    }
+    rewritten.push(arg);
+  }
+
+  Ok(rewritten)
+}
+
+// the short option in a cluster such as `-ear` whose value is a key, and where its value starts.
+// the first option in the cluster that takes a value takes the rest of it, so `-ofile.gpg` names no
+// key however many `r`s are in the file name
+fn short_key(cluster: &str) -> Option<(char, usize)> {
+  for (i, c) in cluster.char_indices().skip(1) {
+    if SHORT.contains(&c) {
+      return Some((c, i + c.len_utf8()));
+    }
+    if SHORT_WITH_VALUE.contains(&c) {
+      return None;
+    }
+  }
+
+  None
+}
+
+fn push_rest(rewritten: &mut Vec<OsString>, option: &str, keys: &[String]) {
+  for key in &keys[1..] {
+    rewritten.push(OsString::from(option));
+    rewritten.push(OsString::from(key));
+  }
+}
+
+// stdin and stdout belong to gpg, so an alias or group that would need a prompt to attest is an
+// error here. never empty
+fn keys_for(config: &Config, value: &str) -> Result<Vec<String>, i32> {
+  let keys = if config.groups.contains_key(value) {
+    match crate::groups::expand(config, value, true) {
+      Ok(keys) if !keys.is_empty() => keys,
+      Ok(_) => {
+        error!("group `{}` has no keys to pass to gpg", value);
+        return Err(1);
+      },
+      Err(exit) => {
+        error!("group `{}` could not be verified: run `gpg-alias {}` to attest it first", value, value);
+        return Err(exit);
+      },
+    }
+  } else if config.aliases.contains_key(value) {
+    match crate::resolve_verified(config, value) {
+      Ok(k) => vec![k.into_owned()],
+      Err(exit) => {
+        error!("alias `{}` could not be verified: run `gpg-alias {}` to attest it first", value, value);
+        return Err(exit);
+      },
+    }
+  } else {
+    return Ok(vec![value.to_string()]);
+  };
+
+  for key in &keys {
+    crate::validity::check(config, value, key)?;
+  }
+  debug!("replacing `{}` with {}", value, keys.join(", "));
+  Ok(keys)
+}
+
+fn is_self(gpg: &str) -> bool {
+  let gpg = Path::new(gpg).canonicalize();
+  let current = std::env::current_exe().and_then(|p| p.canonicalize());
+  match (gpg, current) {
+    (Ok(g), Ok(c)) => g == c,
+    _ => false,
+  }
+}
+
+#[cfg(unix)]
+fn exec(mut cmd: Command) -> i32 {
+  use std::os::unix::process::CommandExt;
+
+  // only returns if gpg could not be started
+  let e = cmd.exec();
+  error!("could not run gpg: {}", e);
+  1
+}
+
+#[cfg(not(unix))]
+fn exec(mut cmd: Command) -> i32 {
+  match cmd.status() {
+    Ok(s) => s.code().unwrap_or(1),
+    Err(e) => {
+      error!("could not run gpg: {}", e);
+      1
+    },
+  }
+}
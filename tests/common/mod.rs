@@ -0,0 +1,42 @@
+// the fixture shared by the integration tests
+
+use std::{
+  fs,
+  path::PathBuf,
+};
+
+// a config and data directory of its own for each test, removed when it's dropped. tests run in
+// parallel, so the name is random, and creating the directory checks no one else has it
+pub struct Sandbox(pub PathBuf);
+
+impl Sandbox {
+  pub fn new(label: &str, config: &str) -> Sandbox {
+    let path = unique_dir(label);
+    fs::create_dir(path.join("data")).unwrap();
+    fs::write(path.join("gpg-alias.toml"), config).unwrap();
+    Sandbox(path)
+  }
+
+  pub fn config_path(&self) -> PathBuf {
+    self.0.join("gpg-alias.toml")
+  }
+
+  pub fn data_dir(&self) -> PathBuf {
+    self.0.join("data")
+  }
+}
+
+impl Drop for Sandbox {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+// an empty directory under a name nothing else is using
+pub fn unique_dir(label: &str) -> PathBuf {
+  let mut random = [0; 8];
+  getrandom::getrandom(&mut random).unwrap();
+  let path = std::env::temp_dir().join(format!("gpg-alias-{}-{}", label, hex::encode(random)));
+  fs::create_dir(&path).unwrap();
+  path
+}
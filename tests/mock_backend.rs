@@ -0,0 +1,159 @@
+// drives the binary with the mock backend, which needs no keys, through attesting, verifying and
+// noticing a tampered attestation
+
+mod common;
+
+use common::Sandbox;
+
+use std::{
+  fs,
+  io::Write,
+  path::{Path, PathBuf},
+  process::{Command, Output, Stdio},
+};
+
+const SIGNING_KEY: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+const ALICE: &str = "89ABCDEF0123456789ABCDEF0123456789ABCDEF";
+
+fn sandbox(label: &str) -> Sandbox {
+  Sandbox::new(label, &format!(
+    "[signing]\nenabled = true\nkey = \"{}\"\nmethod = \"file\"\n\n[aliases]\nalice = \"{}\"\n",
+    SIGNING_KEY,
+    ALICE,
+  ))
+}
+
+fn command(sandbox: &Sandbox, args: &[&str]) -> Command {
+  let mut command = Command::new(env!("CARGO_BIN_EXE_gpg-alias"));
+  command
+    .arg("--backend").arg("mock")
+    .arg("--data-dir").arg(sandbox.data_dir())
+    .arg("--config").arg(sandbox.config_path())
+    .args(args)
+    .env_remove("GPG_ALIAS_CONFIG")
+    .env_remove("GPG_ALIAS_RECIPIENTS");
+  command
+}
+
+fn run(sandbox: &Sandbox, args: &[&str]) -> Output {
+  command(sandbox, args).output().unwrap()
+}
+
+// one JSON-RPC request per line, answered by `serve --stdio`
+fn serve(sandbox: &Sandbox, args: &[&str], requests: &str) -> String {
+  let mut child = command(sandbox, args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .unwrap();
+  child.stdin.take().unwrap().write_all(requests.as_bytes()).unwrap();
+  let output = child.wait_with_output().unwrap();
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn succeeded(output: &Output) -> bool {
+  if !output.status.success() {
+    eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+  }
+  output.status.success()
+}
+
+fn attestation(data_dir: &Path) -> PathBuf {
+  data_dir.join("alice.asc")
+}
+
+#[test]
+fn attested_aliases_verify() {
+  let sandbox = sandbox("verify");
+  assert!(succeeded(&run(&sandbox, &["--yes", "--sign-all"])));
+  assert!(attestation(&sandbox.data_dir()).exists());
+  assert!(succeeded(&run(&sandbox, &["verify-all"])));
+  assert!(succeeded(&run(&sandbox, &["check", "alice"])));
+}
+
+#[test]
+fn unattested_aliases_do_not_verify() {
+  let sandbox = sandbox("unattested");
+  assert!(!run(&sandbox, &["verify-all"]).status.success());
+  assert_eq!(run(&sandbox, &["--batch", "check", "alice"]).status.code(), Some(4));
+}
+
+#[test]
+fn tampered_attestations_do_not_verify() {
+  let sandbox = sandbox("tampered");
+  assert!(succeeded(&run(&sandbox, &["--yes", "--sign-all"])));
+
+  let path = attestation(&sandbox.data_dir());
+  let signed = fs::read_to_string(&path).unwrap();
+  assert!(signed.contains(ALICE));
+  fs::write(&path, signed.replace(ALICE, SIGNING_KEY)).unwrap();
+
+  assert!(!run(&sandbox, &["verify-all"]).status.success());
+}
+
+#[test]
+fn names_reaching_outside_the_data_directory_are_refused() {
+  let sandbox = sandbox("unsafe");
+  let config = fs::read_to_string(sandbox.config_path()).unwrap();
+  for name in &["../escaped", "a/b", "a\\b"] {
+    assert!(!run(&sandbox, &["--yes", "add", name, ALICE]).status.success());
+  }
+  assert_eq!(fs::read_to_string(sandbox.config_path()).unwrap(), config);
+  assert!(!sandbox.0.join("escaped.asc").exists());
+}
+
+#[test]
+fn group_membership_changes_are_attested() {
+  let sandbox = sandbox("group");
+  assert!(succeeded(&run(&sandbox, &["--yes", "--sign-all"])));
+
+  assert!(!run(&sandbox, &["--yes", "group", "add", "team", "alcie"]).status.success());
+  assert!(succeeded(&run(&sandbox, &["--yes", "group", "add", "team", "alice"])));
+  assert!(sandbox.data_dir().join("team.asc").exists());
+  assert!(succeeded(&run(&sandbox, &["verify-all"])));
+  assert!(succeeded(&run(&sandbox, &["check", "team"])));
+
+  // an alias of the group's name would take its attestation
+  assert!(!run(&sandbox, &["--yes", "add", "team", SIGNING_KEY]).status.success());
+  assert!(!run(&sandbox, &["--yes", "rename", "alice", "team"]).status.success());
+  assert!(sandbox.data_dir().join("team.asc").exists());
+  assert!(succeeded(&run(&sandbox, &["verify-all"])));
+
+  assert!(succeeded(&run(&sandbox, &["--yes", "group", "add", "team", SIGNING_KEY])));
+  let members = run(&sandbox, &["group", "members", "team"]);
+  assert!(succeeded(&members));
+  let members = String::from_utf8_lossy(&members.stdout);
+  assert!(members.contains("alice") && members.contains(SIGNING_KEY));
+  assert!(succeeded(&run(&sandbox, &["verify-all"])));
+
+  assert!(succeeded(&run(&sandbox, &["--yes", "group", "remove", "team", "alice", SIGNING_KEY])));
+  assert!(!sandbox.data_dir().join("team.asc").exists());
+  assert!(!fs::read_to_string(sandbox.config_path()).unwrap().contains("team"));
+}
+
+#[test]
+fn serve_only_attests_when_started_with_yes() {
+  let sandbox = sandbox("serve");
+  let add = |alias: &str| format!(
+    "{{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"add\", \"params\": {{\"alias\": \"{}\", \"key\": \"{}\"}}}}\n",
+    alias,
+    SIGNING_KEY,
+  );
+
+  let refused = serve(&sandbox, &["serve", "--stdio"], &add("bob"));
+  assert!(refused.contains("\"code\":-32000"), "{}", refused);
+  assert!(!sandbox.data_dir().join("bob.asc").exists());
+
+  let escaped = serve(&sandbox, &["--yes", "serve", "--stdio"], &add("../bob"));
+  assert!(escaped.contains("\"error\""), "{}", escaped);
+  assert!(!sandbox.0.join("bob.asc").exists());
+
+  let added = serve(&sandbox, &["--yes", "serve", "--stdio"], &add("bob"));
+  assert!(added.contains("\"result\""), "{}", added);
+  assert!(sandbox.data_dir().join("bob.asc").exists());
+  assert!(fs::read_to_string(sandbox.config_path()).unwrap().contains("bob"));
+
+  let unknown = serve(&sandbox, &["serve", "--stdio"], "{\"jsonrpc\": \"2.0\", \"id\": 2, \"method\": \"resolve\", \"params\": {\"alias\": \"nobody\"}}\n");
+  assert!(unknown.contains("\"code\":-32001"), "{}", unknown);
+}
@@ -0,0 +1,191 @@
+// the library's AliasStore against a throwaway keyring. gpg makes the keys, so these are skipped
+// where it isn't installed
+
+mod common;
+
+use common::Sandbox;
+use gpg_alias::{AliasStore, Config, Error};
+
+use std::{
+  fs,
+  path::Path,
+  process::Command,
+  sync::OnceLock,
+};
+
+// in no keyring, so an alias for it can be attested but never used
+const MISSING_KEY: &str = "0000000000000000000000000000000000000001";
+
+struct Keyring {
+  signer: String,
+  alice: String,
+}
+
+static KEYRING: OnceLock<Option<Keyring>> = OnceLock::new();
+
+// one GNUPGHOME for the whole run, since gpgme reads it from the environment
+fn keyring() -> Option<&'static Keyring> {
+  KEYRING.get_or_init(|| {
+    let available = Command::new("gpg").arg("--version").output().map(|o| o.status.success()).unwrap_or(false);
+    if !available {
+      eprintln!("skipping: gpg is not installed");
+      return None;
+    }
+
+    let home = common::unique_dir("store-keyring");
+    set_private(&home);
+    std::env::set_var("GNUPGHOME", &home);
+
+    Some(Keyring {
+      signer: generate(&home, "Signer <signer@example.com>"),
+      alice: generate(&home, "Alice <alice@example.com>"),
+    })
+  }).as_ref()
+}
+
+#[cfg(unix)]
+fn set_private(path: &Path) {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(0o700)).unwrap();
+}
+
+#[cfg(not(unix))]
+fn set_private(_: &Path) {}
+
+// a key without a passphrase, returning its fingerprint
+fn generate(home: &Path, uid: &str) -> String {
+  let status = Command::new("gpg")
+    .arg("--homedir").arg(home)
+    .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase", ""])
+    .args(["--quick-generate-key", uid, "default", "default", "never"])
+    .status()
+    .unwrap();
+  assert!(status.success(), "could not generate a key for {}", uid);
+
+  let output = Command::new("gpg")
+    .arg("--homedir").arg(home)
+    .args(["--batch", "--with-colons", "--list-keys", uid])
+    .output()
+    .unwrap();
+  String::from_utf8_lossy(&output.stdout).lines()
+    .find(|l| l.starts_with("fpr:"))
+    .and_then(|l| l.split(':').nth(9))
+    .map(ToString::to_string)
+    .expect("the new key has a fingerprint")
+}
+
+fn signed(label: &str, keyring: &Keyring) -> Sandbox {
+  Sandbox::new(label, &format!(
+    "[signing]\nenabled = true\nkey = \"{}\"\nmethod = \"file\"\n\n[aliases]\nalice = \"{}\"\ngone = \"{}\"\n",
+    keyring.signer,
+    keyring.alice,
+    MISSING_KEY,
+  ))
+}
+
+fn store(sandbox: &Sandbox) -> AliasStore {
+  let config = Config::load_with_data_dir(&sandbox.config_path(), &sandbox.data_dir()).unwrap();
+  AliasStore::new(config)
+}
+
+#[test]
+fn exit_codes_match_the_command_line() {
+  assert_eq!(Error::Config.exit_code(), 1);
+  assert_eq!(Error::UnknownAlias("a".to_string()).exit_code(), 3);
+  assert_eq!(Error::Unverified("a".to_string()).exit_code(), 4);
+  assert_eq!(Error::KeyProblem("a".to_string()).exit_code(), 5);
+  assert_eq!(Error::Attestation("a".to_string()).exit_code(), 1);
+}
+
+#[test]
+fn missing_configs_are_config_errors() {
+  let sandbox = Sandbox::new("missing", "");
+  let res = Config::load_with_data_dir(&sandbox.0.join("missing.toml"), &sandbox.data_dir());
+  assert_eq!(res.err(), Some(Error::Config));
+}
+
+#[test]
+fn unknown_aliases() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = signed("unknown", keyring);
+  let store = store(&sandbox);
+  assert_eq!(store.resolve("nobody"), Err(Error::UnknownAlias("nobody".to_string())));
+  assert_eq!(store.verify("nobody"), Err(Error::UnknownAlias("nobody".to_string())));
+  assert_eq!(store.attest("nobody"), Err(Error::UnknownAlias("nobody".to_string())));
+}
+
+#[test]
+fn unattested_aliases_are_unverified() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = signed("unattested", keyring);
+  let store = store(&sandbox);
+  assert_eq!(store.verify("alice"), Err(Error::Unverified("alice".to_string())));
+  assert_eq!(store.resolve("alice"), Err(Error::Unverified("alice".to_string())));
+}
+
+#[test]
+fn attested_aliases_resolve() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = signed("attested", keyring);
+  let store = store(&sandbox);
+  assert_eq!(store.attest("alice"), Ok(()));
+  assert_eq!(store.verify("alice"), Ok(()));
+
+  let key = store.resolve("alice").unwrap();
+  assert_eq!(key.alias, "alice");
+  assert_eq!(key.key_id, keyring.alice);
+  assert_eq!(key.fingerprint.as_deref(), Some(keyring.alice.as_str()));
+  assert!(key.attested);
+}
+
+#[test]
+fn tampered_attestations_are_unverified() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = signed("tampered", keyring);
+  let store = store(&sandbox);
+  assert_eq!(store.attest("alice"), Ok(()));
+
+  let path = sandbox.data_dir().join("alice.asc");
+  let signed = fs::read_to_string(&path).unwrap();
+  fs::write(&path, signed.replacen(&keyring.alice, MISSING_KEY, 1)).unwrap();
+  assert_eq!(store.verify("alice"), Err(Error::Unverified("alice".to_string())));
+}
+
+#[test]
+fn attested_keys_missing_from_the_keyring_are_key_problems() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = signed("missing-key", keyring);
+  let store = store(&sandbox);
+  assert_eq!(store.attest("gone"), Ok(()));
+  assert_eq!(store.verify("gone"), Ok(()));
+  assert_eq!(store.resolve("gone"), Err(Error::KeyProblem("gone".to_string())));
+}
+
+#[test]
+fn aliases_are_not_attested_without_signing() {
+  let keyring = match keyring() {
+    Some(k) => k,
+    None => return,
+  };
+  let sandbox = Sandbox::new("unsigned", &format!("[signing]\nenabled = false\nkey = \"\"\n\n[aliases]\nalice = \"{}\"\n", keyring.alice));
+  let store = store(&sandbox);
+  assert_eq!(store.attest("alice"), Err(Error::Attestation("alice".to_string())));
+
+  let key = store.resolve("alice").unwrap();
+  assert!(!key.attested);
+}